@@ -0,0 +1,49 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Exposes the version of the resolved `cel` package as `CEL_VERSION`, so that
+/// ASTs serialized by a different parser are recognized as stale.
+///
+/// The version is read from the `Cargo.lock` of the build, found above the
+/// output directory (which lives in the top-level build's target directory,
+/// also when this crate is built as a dependency) or above the manifest.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let version = [out_dir.as_path(), manifest_dir.as_path()]
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lock| lock.is_file())
+        .and_then(|lock| {
+            println!("cargo:rerun-if-changed={}", lock.display());
+            locked_version(&std::fs::read_to_string(lock).ok()?, "cel")
+        });
+    let version = version.unwrap_or_else(|| {
+        println!("cargo:warning=could not resolve the cel version from Cargo.lock");
+        format!(
+            "unknown+{}",
+            env::var("CARGO_PKG_VERSION").unwrap_or_default()
+        )
+    });
+    println!("cargo:rustc-env=CEL_VERSION={version}");
+    Ok(())
+}
+
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let name_line = format!("name = \"{name}\"");
+    let mut lines = lock.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line == name_line {
+            return lines
+                .next()?
+                .strip_prefix("version = \"")?
+                .strip_suffix('"')
+                .map(str::to_string);
+        }
+    }
+    None
+}
@@ -16,4 +16,8 @@ pub enum CelType {
     Timestamp,
     Uuid,
     Decimal,
+
+    /// Statically unknown. Never produced for a runtime value — only by the
+    /// type checker, for expressions whose type depends on the data.
+    Dyn,
 }
@@ -0,0 +1,687 @@
+use cel::common::ast::{operators, CallExpr, EntryExpr, Expr, IdedExpr, LiteralValue};
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{cel_type::*, error::*};
+
+/// The declared shape of a variable visible to an expression.
+///
+/// Maps are described either by their exact fields ([`Self::Fields`], e.g.
+/// `context.vars.account`) or by the type of every value under arbitrary
+/// keys ([`Self::MapOf`], e.g. a metadata blob).
+#[derive(Debug, Clone, PartialEq)]
+pub enum CelTypeDecl {
+    Type(CelType),
+    Fields(BTreeMap<String, CelTypeDecl>),
+    MapOf(Box<CelTypeDecl>),
+}
+
+impl CelTypeDecl {
+    pub fn fields<K: Into<String>>(fields: impl IntoIterator<Item = (K, CelTypeDecl)>) -> Self {
+        Self::Fields(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    pub fn map_of(value: impl Into<CelTypeDecl>) -> Self {
+        Self::MapOf(Box::new(value.into()))
+    }
+
+    pub fn cel_type(&self) -> CelType {
+        match self {
+            Self::Type(t) => *t,
+            Self::Fields(_) | Self::MapOf(_) => CelType::Map,
+        }
+    }
+}
+
+impl From<CelType> for CelTypeDecl {
+    fn from(t: CelType) -> Self {
+        Self::Type(t)
+    }
+}
+
+/// One accepted call shape of a function. `CelType::Dyn` in any position
+/// accepts (or produces) a value of any type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CelFunctionOverload {
+    receiver: Option<CelType>,
+    args: Vec<CelType>,
+    result: CelType,
+}
+
+impl CelFunctionOverload {
+    pub fn new(args: impl Into<Vec<CelType>>, result: CelType) -> Self {
+        Self {
+            receiver: None,
+            args: args.into(),
+            result,
+        }
+    }
+
+    /// An overload invoked with receiver syntax, e.g. `ts.format('%Y')`.
+    pub fn method(receiver: CelType, args: impl Into<Vec<CelType>>, result: CelType) -> Self {
+        Self {
+            receiver: Some(receiver),
+            args: args.into(),
+            result,
+        }
+    }
+
+    fn accepts(&self, receiver: Option<CelType>, args: &[CelType]) -> bool {
+        let receiver_matches = match (self.receiver, receiver) {
+            (None, None) => true,
+            (Some(expected), Some(found)) => compatible(expected, found),
+            _ => false,
+        };
+        receiver_matches
+            && self.args.len() == args.len()
+            && self
+                .args
+                .iter()
+                .zip(args)
+                .all(|(expected, found)| compatible(*expected, *found))
+    }
+}
+
+/// Declares the variables and functions an expression may reference, so that
+/// [`crate::CelExpression::check`] can reject it before it is ever evaluated.
+///
+/// [`Self::new`] comes with the builtins registered by
+/// [`crate::CelContext::new`] plus the commonly used parts of the CEL standard
+/// library; variables are added by the caller to mirror what it will bind
+/// with [`crate::CelContext::add_variable`].
+#[derive(Debug, Clone)]
+pub struct CelTypeEnv {
+    variables: HashMap<String, CelTypeDecl>,
+    functions: HashMap<String, Vec<CelFunctionOverload>>,
+}
+
+impl CelTypeEnv {
+    pub fn new() -> Self {
+        use CelType::*;
+
+        let mut env = Self {
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+        };
+
+        env.add_function("date", CelFunctionOverload::new([], Timestamp));
+        env.add_function("date", CelFunctionOverload::new([String], Timestamp));
+        env.add_function("date", CelFunctionOverload::new([Timestamp], Timestamp));
+        env.add_function("uuid", CelFunctionOverload::new([String], Uuid));
+        for arg in [Decimal, String, Int, UInt] {
+            env.add_function("decimal", CelFunctionOverload::new([arg], Decimal));
+        }
+        for name in ["decimal.Add", "decimal.Sub", "decimal.Mul"] {
+            env.add_function(name, CelFunctionOverload::new([Dyn, Dyn], Decimal));
+        }
+        env.add_function("decimal.Cmp", CelFunctionOverload::new([Dyn, Dyn], Int));
+        env.add_function(
+            "format",
+            CelFunctionOverload::method(Timestamp, [String], String),
+        );
+
+        for receiver in [String, List, Map, Bytes] {
+            env.add_function("size", CelFunctionOverload::new([receiver], Int));
+            env.add_function("size", CelFunctionOverload::method(receiver, [], Int));
+        }
+        for name in ["contains", "startsWith", "endsWith", "matches"] {
+            env.add_function(name, CelFunctionOverload::method(String, [String], Bool));
+        }
+        for (name, result) in [
+            ("string", String),
+            ("int", Int),
+            ("uint", UInt),
+            ("double", Double),
+            ("bytes", Bytes),
+            ("timestamp", Timestamp),
+            ("dyn", Dyn),
+        ] {
+            env.add_function(name, CelFunctionOverload::new([Dyn], result));
+        }
+        for name in [
+            "getFullYear",
+            "getMonth",
+            "getDate",
+            "getDayOfMonth",
+            "getDayOfWeek",
+            "getDayOfYear",
+            "getHours",
+            "getMinutes",
+            "getSeconds",
+            "getMilliseconds",
+        ] {
+            env.add_function(name, CelFunctionOverload::method(Timestamp, [], Int));
+            env.add_function(name, CelFunctionOverload::method(Timestamp, [String], Int));
+        }
+
+        env
+    }
+
+    pub fn add_variable(&mut self, name: impl Into<String>, decl: impl Into<CelTypeDecl>) {
+        self.variables.insert(name.into(), decl.into());
+    }
+
+    pub fn add_function(&mut self, name: impl Into<String>, overload: CelFunctionOverload) {
+        self.functions
+            .entry(name.into())
+            .or_default()
+            .push(overload);
+    }
+
+    /// Declare a function without a known signature: any call to it is
+    /// accepted and its result is `Dyn`.
+    pub fn add_variadic_function(&mut self, name: impl Into<String>) {
+        self.functions.entry(name.into()).or_default();
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&CelTypeDecl> {
+        self.variables.get(name)
+    }
+
+    pub(crate) fn check(&self, expr: &IdedExpr) -> Result<CelType, Vec<CelTypeError>> {
+        let mut checker = Checker {
+            env: self,
+            scopes: Vec::new(),
+            errors: Vec::new(),
+        };
+        let res = checker.check(expr);
+        if checker.errors.is_empty() {
+            Ok(res.cel_type())
+        } else {
+            Err(checker.errors)
+        }
+    }
+}
+
+impl Default for CelTypeEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a value statically typed `found` may flow where `expected` is
+/// required. Dates are bound as timestamps at runtime, so the two are
+/// interchangeable.
+pub(crate) fn compatible(expected: CelType, found: CelType) -> bool {
+    use CelType::*;
+    expected == found
+        || expected == Dyn
+        || found == Dyn
+        || matches!((expected, found), (Date, Timestamp) | (Timestamp, Date))
+}
+
+fn is_numeric(t: CelType) -> bool {
+    matches!(t, CelType::Int | CelType::UInt | CelType::Double)
+}
+
+fn dyn_decl() -> CelTypeDecl {
+    CelTypeDecl::Type(CelType::Dyn)
+}
+
+struct Checker<'a> {
+    env: &'a CelTypeEnv,
+    scopes: Vec<HashMap<String, CelTypeDecl>>,
+    errors: Vec<CelTypeError>,
+}
+
+impl Checker<'_> {
+    fn lookup(&self, name: &str) -> Option<CelTypeDecl> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.env.variables.get(name))
+            .cloned()
+    }
+
+    fn check(&mut self, expr: &IdedExpr) -> CelTypeDecl {
+        match &expr.expr {
+            Expr::Literal(literal) => CelTypeDecl::Type(match literal {
+                LiteralValue::Boolean(_) => CelType::Bool,
+                LiteralValue::Bytes(_) => CelType::Bytes,
+                LiteralValue::Double(_) => CelType::Double,
+                LiteralValue::Int(_) => CelType::Int,
+                LiteralValue::Null => CelType::Null,
+                LiteralValue::String(_) => CelType::String,
+                LiteralValue::UInt(_) => CelType::UInt,
+            }),
+            Expr::Ident(name) => self.lookup(name).unwrap_or_else(|| {
+                self.errors.push(CelTypeError::UnknownIdent(name.clone()));
+                dyn_decl()
+            }),
+            Expr::Select(select) => {
+                let operand = self.check(&select.operand);
+                let field = self.field(&select.operand, operand, &select.field);
+                if select.test {
+                    CelTypeDecl::Type(CelType::Bool)
+                } else {
+                    field
+                }
+            }
+            Expr::Call(call) => self.call(call),
+            Expr::List(list) => {
+                for element in list.elements.iter() {
+                    self.check(element);
+                }
+                CelTypeDecl::Type(CelType::List)
+            }
+            Expr::Map(map) => {
+                let mut fields = BTreeMap::new();
+                let mut all_string_keys = true;
+                for entry in map.entries.iter() {
+                    if let EntryExpr::MapEntry(entry) = &entry.expr {
+                        self.check(&entry.key);
+                        let value = self.check(&entry.value);
+                        match &entry.key.expr {
+                            Expr::Literal(LiteralValue::String(key)) => {
+                                fields.insert(key.inner().to_string(), value);
+                            }
+                            _ => all_string_keys = false,
+                        }
+                    }
+                }
+                if all_string_keys {
+                    CelTypeDecl::Fields(fields)
+                } else {
+                    CelTypeDecl::Type(CelType::Map)
+                }
+            }
+            Expr::Comprehension(comprehension) => {
+                self.check(&comprehension.iter_range);
+                let accu = self.check(&comprehension.accu_init);
+                let mut scope = HashMap::new();
+                scope.insert(comprehension.iter_var.clone(), dyn_decl());
+                if let Some(iter_var2) = comprehension.iter_var2.as_ref() {
+                    scope.insert(iter_var2.clone(), dyn_decl());
+                }
+                scope.insert(comprehension.accu_var.clone(), accu.clone());
+                self.scopes.push(scope);
+                self.check(&comprehension.loop_cond);
+                let step = self.check(&comprehension.loop_step);
+                if step.cel_type() != accu.cel_type() {
+                    self.scopes
+                        .last_mut()
+                        .expect("scope pushed above")
+                        .insert(comprehension.accu_var.clone(), dyn_decl());
+                }
+                let res = self.check(&comprehension.result);
+                self.scopes.pop();
+                res
+            }
+            Expr::Struct(_) => {
+                self.errors
+                    .push(CelTypeError::Unsupported("struct construction".to_string()));
+                dyn_decl()
+            }
+            Expr::Unspecified => dyn_decl(),
+        }
+    }
+
+    fn field(&mut self, operand_expr: &IdedExpr, operand: CelTypeDecl, field: &str) -> CelTypeDecl {
+        match operand {
+            CelTypeDecl::Fields(mut fields) => fields.remove(field).unwrap_or_else(|| {
+                self.errors.push(CelTypeError::UnknownAttribute(
+                    path_of(operand_expr),
+                    field.to_string(),
+                ));
+                dyn_decl()
+            }),
+            CelTypeDecl::MapOf(value) => *value,
+            CelTypeDecl::Type(CelType::Map | CelType::Dyn) => dyn_decl(),
+            CelTypeDecl::Type(other) => {
+                self.errors.push(CelTypeError::UnknownAttribute(
+                    format!("{} ({other:?})", path_of(operand_expr)),
+                    field.to_string(),
+                ));
+                dyn_decl()
+            }
+        }
+    }
+
+    fn call(&mut self, call: &CallExpr) -> CelTypeDecl {
+        use CelType::*;
+
+        let name = call.func_name.as_str();
+        if call.target.is_none() {
+            match (name, call.args.as_slice()) {
+                (operators::CONDITIONAL, [cond, left, right]) => {
+                    self.expect_bool(cond);
+                    let left = self.check(left);
+                    let right = self.check(right);
+                    return if left == right { left } else { dyn_decl() };
+                }
+                (operators::LOGICAL_AND | operators::LOGICAL_OR, [left, right]) => {
+                    self.expect_bool(left);
+                    self.expect_bool(right);
+                    return Bool.into();
+                }
+                (operators::LOGICAL_NOT, [arg]) => {
+                    self.expect_bool(arg);
+                    return Bool.into();
+                }
+                (operators::NOT_STRICTLY_FALSE, [arg]) => {
+                    self.check(arg);
+                    return Bool.into();
+                }
+                (operators::NEGATE, [arg]) => {
+                    let arg = self.check(arg).cel_type();
+                    if !matches!(arg, Int | Double | Dyn) {
+                        self.errors.push(CelTypeError::NoMatchingOverload(
+                            name.to_string(),
+                            vec![arg],
+                        ));
+                        return dyn_decl();
+                    }
+                    return arg.into();
+                }
+                (operators::EQUALS | operators::NOT_EQUALS, [left, right]) => {
+                    let left = self.check(left).cel_type();
+                    let right = self.check(right).cel_type();
+                    if !(comparable(left, right) || left == Null || right == Null) {
+                        self.errors.push(CelTypeError::BadType(left, right));
+                    }
+                    return Bool.into();
+                }
+                (
+                    operators::LESS
+                    | operators::LESS_EQUALS
+                    | operators::GREATER
+                    | operators::GREATER_EQUALS,
+                    [left, right],
+                ) => {
+                    let left = self.check(left).cel_type();
+                    let right = self.check(right).cel_type();
+                    let orderable = matches!(
+                        left,
+                        Int | UInt | Double | String | Bytes | Bool | Date | Timestamp | Dyn
+                    );
+                    if !orderable || !comparable(left, right) {
+                        self.errors.push(CelTypeError::NoMatchingOverload(
+                            name.to_string(),
+                            vec![left, right],
+                        ));
+                    }
+                    return Bool.into();
+                }
+                (
+                    operators::ADD
+                    | operators::SUBSTRACT
+                    | operators::MULTIPLY
+                    | operators::DIVIDE
+                    | operators::MODULO,
+                    [left, right],
+                ) => {
+                    let left = self.check(left).cel_type();
+                    let right = self.check(right).cel_type();
+                    return self.arithmetic(name, left, right).into();
+                }
+                (operators::IN, [left, right]) => {
+                    self.check(left);
+                    let container = self.check(right).cel_type();
+                    if !matches!(container, List | Map | Dyn) {
+                        self.errors.push(CelTypeError::BadType(List, container));
+                    }
+                    return Bool.into();
+                }
+                (operators::INDEX, [operand, index]) => {
+                    let operand_decl = self.check(operand);
+                    let index_type = self.check(index).cel_type();
+                    return match (operand_decl, &index.expr) {
+                        (
+                            decl @ CelTypeDecl::Fields(_),
+                            Expr::Literal(LiteralValue::String(key)),
+                        ) => self.field(operand, decl, key.inner()),
+                        (CelTypeDecl::MapOf(value), _) => *value,
+                        (CelTypeDecl::Type(List), _) if !compatible(Int, index_type) => {
+                            self.errors.push(CelTypeError::BadType(Int, index_type));
+                            dyn_decl()
+                        }
+                        (CelTypeDecl::Type(List | Map | Dyn) | CelTypeDecl::Fields(_), _) => {
+                            dyn_decl()
+                        }
+                        (CelTypeDecl::Type(other), _) => {
+                            self.errors.push(CelTypeError::NoMatchingOverload(
+                                name.to_string(),
+                                vec![other, index_type],
+                            ));
+                            dyn_decl()
+                        }
+                    };
+                }
+                (operators::OPT_INDEX | operators::OPT_SELECT, args) => {
+                    for arg in args {
+                        self.check(arg);
+                    }
+                    return dyn_decl();
+                }
+                _ => (),
+            }
+        }
+
+        let arg_types: Vec<CelType> = call
+            .args
+            .iter()
+            .map(|arg| self.check(arg).cel_type())
+            .collect();
+
+        match call.target.as_deref() {
+            None => self.function(name, None, &arg_types),
+            Some(target) => {
+                if let Expr::Ident(prefix) = &target.expr {
+                    let qualified = format!("{prefix}.{name}");
+                    if self.lookup(prefix).is_none() && self.env.functions.contains_key(&qualified)
+                    {
+                        return self.function(&qualified, None, &arg_types);
+                    }
+                }
+                let receiver = self.check(target).cel_type();
+                self.function(name, Some(receiver), &arg_types)
+            }
+        }
+    }
+
+    fn function(&mut self, name: &str, receiver: Option<CelType>, args: &[CelType]) -> CelTypeDecl {
+        let Some(overloads) = self.env.functions.get(name) else {
+            self.errors
+                .push(CelTypeError::UnknownFunction(name.to_string()));
+            return dyn_decl();
+        };
+        if overloads.is_empty() {
+            return dyn_decl();
+        }
+        let candidates: Vec<&CelFunctionOverload> = overloads
+            .iter()
+            .filter(|o| o.receiver.is_some() == receiver.is_some())
+            .collect();
+        if !candidates.iter().any(|o| o.args.len() == args.len()) {
+            let mut expected: Vec<usize> = candidates.iter().map(|o| o.args.len()).collect();
+            expected.sort_unstable();
+            expected.dedup();
+            if expected.is_empty() {
+                self.errors
+                    .push(CelTypeError::UnknownFunction(name.to_string()));
+            } else {
+                self.errors.push(CelTypeError::WrongArgumentCount {
+                    function: name.to_string(),
+                    expected,
+                    found: args.len(),
+                });
+            }
+            return dyn_decl();
+        }
+        let mut matching = candidates.iter().filter(|o| o.accepts(receiver, args));
+        match (matching.next(), matching.next()) {
+            (Some(only), None) => only.result.into(),
+            // Several overloads accept a Dyn argument: only agree on the
+            // result type if they all do.
+            (Some(first), Some(second)) => {
+                let result = first.result;
+                if second.result == result && matching.all(|o| o.result == result) {
+                    result.into()
+                } else {
+                    dyn_decl()
+                }
+            }
+            (None, _) => {
+                let mut found = Vec::with_capacity(args.len() + 1);
+                found.extend(receiver);
+                found.extend_from_slice(args);
+                self.errors
+                    .push(CelTypeError::NoMatchingOverload(name.to_string(), found));
+                dyn_decl()
+            }
+        }
+    }
+
+    fn arithmetic(&mut self, name: &str, left: CelType, right: CelType) -> CelType {
+        use CelType::*;
+
+        let supported: &[CelType] = match name {
+            operators::ADD => &[Int, UInt, Double, String, Bytes, List],
+            operators::MODULO => &[Int, UInt],
+            _ => &[Int, UInt, Double],
+        };
+        match (left, right) {
+            (Dyn, Dyn) => Dyn,
+            (Dyn, known) | (known, Dyn) if supported.contains(&known) => known,
+            (left, right) if left == right && supported.contains(&left) => left,
+            (left, right) => {
+                self.errors.push(CelTypeError::NoMatchingOverload(
+                    name.to_string(),
+                    vec![left, right],
+                ));
+                Dyn
+            }
+        }
+    }
+
+    fn expect_bool(&mut self, expr: &IdedExpr) {
+        let found = self.check(expr).cel_type();
+        if !compatible(CelType::Bool, found) {
+            self.errors
+                .push(CelTypeError::BadType(CelType::Bool, found));
+        }
+    }
+}
+
+fn comparable(left: CelType, right: CelType) -> bool {
+    compatible(left, right) || (is_numeric(left) && is_numeric(right))
+}
+
+fn path_of(expr: &IdedExpr) -> String {
+    match &expr.expr {
+        Expr::Ident(name) => name.clone(),
+        Expr::Select(select) => format!("{}.{}", path_of(&select.operand), select.field),
+        _ => "<expression>".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CelExpression;
+
+    fn velocity_env() -> CelTypeEnv {
+        let mut env = CelTypeEnv::new();
+        env.add_variable(
+            "context",
+            CelTypeDecl::fields([(
+                "vars",
+                CelTypeDecl::fields([
+                    (
+                        "entry",
+                        CelTypeDecl::fields([
+                            ("units", CelType::Decimal.into()),
+                            ("currency", CelType::String.into()),
+                        ]),
+                    ),
+                    (
+                        "account",
+                        CelTypeDecl::fields([
+                            ("id", CelType::Uuid.into()),
+                            ("metadata", CelType::Dyn.into()),
+                        ]),
+                    ),
+                ]),
+            )]),
+        );
+        env.add_variable("params", CelTypeDecl::map_of(CelType::Decimal));
+        env
+    }
+
+    fn check(source: &str) -> Result<CelType, Vec<CelTypeError>> {
+        source
+            .parse::<CelExpression>()
+            .unwrap()
+            .check(&velocity_env())
+    }
+
+    #[test]
+    fn infers_result_types() {
+        assert_eq!(check("1 + 2"), Ok(CelType::Int));
+        assert_eq!(check("'a' + 'b'"), Ok(CelType::String));
+        assert_eq!(check("date('2022-10-10')"), Ok(CelType::Timestamp));
+        assert_eq!(
+            check("decimal.Add(context.vars.entry.units, params.fee)"),
+            Ok(CelType::Decimal)
+        );
+        assert_eq!(
+            check("decimal.Cmp(context.vars.entry.units, decimal('100')) > 0"),
+            Ok(CelType::Bool)
+        );
+        assert_eq!(
+            check("context.vars.account.metadata.region"),
+            Ok(CelType::Dyn)
+        );
+        assert_eq!(check("{'a': 1}.a"), Ok(CelType::Int));
+        assert_eq!(check("[1, 2].all(x, x > 0)"), Ok(CelType::Bool));
+        assert_eq!(check("true ? 'a' : 'b'"), Ok(CelType::String));
+        assert_eq!(check("true ? 'a' : 1"), Ok(CelType::Dyn));
+        assert_eq!(check("date().format('%Y')"), Ok(CelType::String));
+    }
+
+    #[test]
+    fn rejects_unknown_identifiers_and_attributes() {
+        assert_eq!(
+            check("contex.vars"),
+            Err(vec![CelTypeError::UnknownIdent("contex".to_string())])
+        );
+        assert_eq!(
+            check("context.vars.entry.amount"),
+            Err(vec![CelTypeError::UnknownAttribute(
+                "context.vars.entry".to_string(),
+                "amount".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arity_and_overloads() {
+        assert!(matches!(
+            check("decimal.Add(decimal('1'))").unwrap_err().as_slice(),
+            [CelTypeError::WrongArgumentCount { found: 1, .. }]
+        ));
+        assert!(matches!(
+            check("uuid(1)").unwrap_err().as_slice(),
+            [CelTypeError::NoMatchingOverload(name, _)] if name == "uuid"
+        ));
+        assert!(matches!(
+            check("unknown(1)").unwrap_err().as_slice(),
+            [CelTypeError::UnknownFunction(name)] if name == "unknown"
+        ));
+    }
+
+    #[test]
+    fn rejects_mismatched_operands() {
+        assert!(check("context.vars.entry.units + decimal('1')").is_err());
+        assert!(check("context.vars.entry.units == 'USD'").is_err());
+        assert!(check("1 && true").is_err());
+        assert!(check("context.vars.entry.currency == 'USD' && 1 > 0u").is_ok());
+    }
+
+    #[test]
+    fn collects_all_errors() {
+        let errors = check("foo + bar.baz").unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+}
@@ -0,0 +1,370 @@
+use cel::common::ast::{
+    CallExpr, ComprehensionExpr, EntryExpr, Expr, IdedEntryExpr, IdedExpr, ListExpr, LiteralValue,
+    MapEntryExpr, MapExpr, SelectExpr, StructExpr, StructFieldExpr,
+};
+use serde::{Deserialize, Serialize};
+
+/// Version of the serialized AST layout defined in this module. Bumped
+/// whenever [`Node`] changes shape.
+pub(crate) const AST_FORMAT_VERSION: u32 = 1;
+
+/// Version of the `cel` parser the ASTs are produced by, resolved by the
+/// build script. An upgrade may change the parser's output for a given
+/// source, so ASTs from any other version are re-parsed instead of trusted.
+pub(crate) const PARSER_VERSION: &str = env!("CEL_VERSION");
+
+/// A [`crate::CelExpression`] together with its parsed AST, in a form that
+/// can be persisted and turned back into an expression without running the
+/// parser.
+///
+/// Obtained from [`crate::CelExpression::compiled`] or
+/// [`crate::CelCompileCache::export`], and loaded with
+/// `CelExpression::try_from` or [`crate::CelCompileCache::preload`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CelCompiledExpression {
+    pub(crate) version: u32,
+    #[serde(default)]
+    pub(crate) parser: String,
+    pub(crate) source: String,
+    pub(crate) ast: Node,
+    /// Binds the AST to the source and versions it was exported with, so
+    /// that an AST stored or edited apart from its source is not loaded
+    /// under it.
+    #[serde(default)]
+    pub(crate) checksum: u64,
+}
+
+impl CelCompiledExpression {
+    pub(crate) fn new(source: String, expr: &IdedExpr) -> Self {
+        let mut compiled = Self {
+            version: AST_FORMAT_VERSION,
+            parser: PARSER_VERSION.to_string(),
+            source,
+            ast: Node::from(expr),
+            checksum: 0,
+        };
+        compiled.checksum = compiled.compute_checksum();
+        compiled
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the AST was produced by a compatible version of this crate
+    /// and parser, and was exported together with its source.
+    pub(crate) fn is_current(&self) -> bool {
+        self.version == AST_FORMAT_VERSION
+            && self.parser == PARSER_VERSION
+            && self.checksum == self.compute_checksum()
+    }
+
+    /// FNV-1a over the serialized versions, source and AST — stable across
+    /// processes and Rust releases, unlike `std`'s hashers.
+    fn compute_checksum(&self) -> u64 {
+        let bytes = serde_json::to_vec(&(self.version, &self.parser, &self.source, &self.ast))
+            .expect("AST serializes to JSON");
+        bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum Node {
+    Call {
+        id: u64,
+        function: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target: Option<Box<Node>>,
+        args: Vec<Node>,
+    },
+    Comprehension {
+        id: u64,
+        iter_range: Box<Node>,
+        iter_var: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        iter_var2: Option<String>,
+        accu_var: String,
+        accu_init: Box<Node>,
+        loop_cond: Box<Node>,
+        loop_step: Box<Node>,
+        result: Box<Node>,
+    },
+    Ident {
+        id: u64,
+        name: String,
+    },
+    List {
+        id: u64,
+        elements: Vec<Node>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        optional_indices: Vec<usize>,
+    },
+    Literal {
+        id: u64,
+        value: Literal,
+    },
+    Map {
+        id: u64,
+        entries: Vec<Entry>,
+    },
+    Select {
+        id: u64,
+        operand: Box<Node>,
+        field: String,
+        test: bool,
+    },
+    Struct {
+        id: u64,
+        type_name: String,
+        entries: Vec<Entry>,
+    },
+    Unspecified {
+        id: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Literal {
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Double(f64),
+    Int(i64),
+    Null,
+    String(String),
+    UInt(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum Entry {
+    Field {
+        id: u64,
+        field: String,
+        value: Node,
+        optional: bool,
+    },
+    Map {
+        id: u64,
+        key: Node,
+        value: Node,
+        optional: bool,
+    },
+}
+
+impl From<&IdedExpr> for Node {
+    fn from(expr: &IdedExpr) -> Self {
+        let id = expr.id;
+        match &expr.expr {
+            Expr::Call(call) => Node::Call {
+                id,
+                function: call.func_name.clone(),
+                target: call.target.as_deref().map(|t| Box::new(t.into())),
+                args: call.args.iter().map(Node::from).collect(),
+            },
+            Expr::Comprehension(c) => Node::Comprehension {
+                id,
+                iter_range: Box::new((&c.iter_range).into()),
+                iter_var: c.iter_var.clone(),
+                iter_var2: c.iter_var2.clone(),
+                accu_var: c.accu_var.clone(),
+                accu_init: Box::new((&c.accu_init).into()),
+                loop_cond: Box::new((&c.loop_cond).into()),
+                loop_step: Box::new((&c.loop_step).into()),
+                result: Box::new((&c.result).into()),
+            },
+            Expr::Ident(name) => Node::Ident {
+                id,
+                name: name.clone(),
+            },
+            Expr::List(list) => Node::List {
+                id,
+                elements: list.elements.iter().map(Node::from).collect(),
+                optional_indices: list.optional_indices.clone(),
+            },
+            Expr::Literal(literal) => Node::Literal {
+                id,
+                value: match literal {
+                    LiteralValue::Boolean(b) => Literal::Bool(*b.inner()),
+                    LiteralValue::Bytes(b) => Literal::Bytes(b.inner().to_vec()),
+                    LiteralValue::Double(d) => Literal::Double(*d.inner()),
+                    LiteralValue::Int(i) => Literal::Int(*i.inner()),
+                    LiteralValue::Null => Literal::Null,
+                    LiteralValue::String(s) => Literal::String(s.inner().to_string()),
+                    LiteralValue::UInt(u) => Literal::UInt(*u.inner()),
+                },
+            },
+            Expr::Map(map) => Node::Map {
+                id,
+                entries: map.entries.iter().map(Entry::from).collect(),
+            },
+            Expr::Select(select) => Node::Select {
+                id,
+                operand: Box::new(select.operand.as_ref().into()),
+                field: select.field.clone(),
+                test: select.test,
+            },
+            Expr::Struct(s) => Node::Struct {
+                id,
+                type_name: s.type_name.clone(),
+                entries: s.entries.iter().map(Entry::from).collect(),
+            },
+            Expr::Unspecified => Node::Unspecified { id },
+        }
+    }
+}
+
+impl From<&IdedEntryExpr> for Entry {
+    fn from(entry: &IdedEntryExpr) -> Self {
+        let id = entry.id;
+        match &entry.expr {
+            EntryExpr::StructField(field) => Entry::Field {
+                id,
+                field: field.field.clone(),
+                value: (&field.value).into(),
+                optional: field.optional,
+            },
+            EntryExpr::MapEntry(entry) => Entry::Map {
+                id,
+                key: (&entry.key).into(),
+                value: (&entry.value).into(),
+                optional: entry.optional,
+            },
+        }
+    }
+}
+
+impl From<Node> for IdedExpr {
+    fn from(node: Node) -> Self {
+        let (id, expr) = match node {
+            Node::Call {
+                id,
+                function,
+                target,
+                args,
+            } => (
+                id,
+                Expr::Call(CallExpr {
+                    func_name: function,
+                    target: target.map(|t| Box::new((*t).into())),
+                    args: args.into_iter().map(IdedExpr::from).collect(),
+                }),
+            ),
+            Node::Comprehension {
+                id,
+                iter_range,
+                iter_var,
+                iter_var2,
+                accu_var,
+                accu_init,
+                loop_cond,
+                loop_step,
+                result,
+            } => (
+                id,
+                Expr::Comprehension(Box::new(ComprehensionExpr {
+                    iter_range: (*iter_range).into(),
+                    iter_var,
+                    iter_var2,
+                    accu_var,
+                    accu_init: (*accu_init).into(),
+                    loop_cond: (*loop_cond).into(),
+                    loop_step: (*loop_step).into(),
+                    result: (*result).into(),
+                })),
+            ),
+            Node::Ident { id, name } => (id, Expr::Ident(name)),
+            Node::List {
+                id,
+                elements,
+                optional_indices,
+            } => (
+                id,
+                Expr::List(ListExpr::new_with_optionals(
+                    elements.into_iter().map(IdedExpr::from).collect(),
+                    optional_indices,
+                )),
+            ),
+            Node::Literal { id, value } => (
+                id,
+                Expr::Literal(match value {
+                    Literal::Bool(b) => LiteralValue::Boolean(b.into()),
+                    Literal::Bytes(b) => LiteralValue::Bytes(b.into()),
+                    Literal::Double(d) => LiteralValue::Double(d.into()),
+                    Literal::Int(i) => LiteralValue::Int(i.into()),
+                    Literal::Null => LiteralValue::Null,
+                    Literal::String(s) => LiteralValue::String(s.into()),
+                    Literal::UInt(u) => LiteralValue::UInt(u.into()),
+                }),
+            ),
+            Node::Map { id, entries } => (
+                id,
+                Expr::Map(MapExpr {
+                    entries: entries.into_iter().map(IdedEntryExpr::from).collect(),
+                }),
+            ),
+            Node::Select {
+                id,
+                operand,
+                field,
+                test,
+            } => (
+                id,
+                Expr::Select(SelectExpr {
+                    operand: Box::new((*operand).into()),
+                    field,
+                    test,
+                }),
+            ),
+            Node::Struct {
+                id,
+                type_name,
+                entries,
+            } => (
+                id,
+                Expr::Struct(StructExpr {
+                    type_name,
+                    entries: entries.into_iter().map(IdedEntryExpr::from).collect(),
+                }),
+            ),
+            Node::Unspecified { id } => (id, Expr::Unspecified),
+        };
+        IdedExpr { id, expr }
+    }
+}
+
+impl From<Entry> for IdedEntryExpr {
+    fn from(entry: Entry) -> Self {
+        match entry {
+            Entry::Field {
+                id,
+                field,
+                value,
+                optional,
+            } => IdedEntryExpr {
+                id,
+                expr: EntryExpr::StructField(StructFieldExpr {
+                    field,
+                    value: value.into(),
+                    optional,
+                }),
+            },
+            Entry::Map {
+                id,
+                key,
+                value,
+                optional,
+            } => IdedEntryExpr {
+                id,
+                expr: EntryExpr::MapEntry(MapEntryExpr {
+                    key: key.into(),
+                    value: value.into(),
+                    optional,
+                }),
+            },
+        }
+    }
+}
@@ -1,30 +1,70 @@
 use std::borrow::Cow;
 
-use cel::Context;
+use cel::{objects::Value, Context};
 use es_entity::clock::{Clock, ClockHandle};
 
-use crate::{builtins, value::CelValue};
+use crate::{
+    builtins, explain,
+    limits::{CelEvaluationLimits, Meter, METER_FUNCTION},
+    registry::CelFunctionRegistry,
+    value::CelValue,
+};
 
 pub struct CelContext {
     inner: Context<'static>,
     clock: ClockHandle,
     debug_vars: Vec<(String, CelValue)>,
+    meter: Option<Meter>,
 }
 
 impl CelContext {
+    /// Bind `name` to `value`. Binding a name again replaces its value, so a
+    /// context can be reused across evaluations that differ in one variable.
     pub fn add_variable(&mut self, name: impl Into<Cow<'static, str>>, value: impl Into<CelValue>) {
         let name = name.into();
         let name_string = name.to_string();
         let value = value.into();
         self.inner
             .add_variable_from_value(name_string.clone(), value.clone().into_cel_value());
-        self.debug_vars.push((name_string, value));
+        match self
+            .debug_vars
+            .iter_mut()
+            .find(|(existing, _)| *existing == name_string)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.debug_vars.push((name_string, value)),
+        }
+    }
+
+    /// Enforce `limits` on every subsequent evaluation against this context.
+    /// Evaluations exceeding them fail with [`crate::CelError::StepLimitExceeded`],
+    /// [`crate::CelError::SizeLimitExceeded`] or [`crate::CelError::DeadlineExceeded`].
+    pub fn set_evaluation_limits(&mut self, limits: CelEvaluationLimits) {
+        let meter = Meter::new(limits);
+        let step_meter = meter.clone();
+        self.inner
+            .add_function(METER_FUNCTION, move |value: Value| step_meter.step(value));
+        self.meter = Some(meter);
+    }
+
+    pub fn evaluation_limits(&self) -> Option<&CelEvaluationLimits> {
+        self.meter.as_ref().map(Meter::limits)
+    }
+
+    /// Make the functions in `registry` callable from expressions evaluated
+    /// against this context.
+    pub fn add_functions(&mut self, registry: &CelFunctionRegistry) {
+        registry.install(&mut self.inner);
     }
 
     pub(crate) fn inner(&self) -> &Context<'static> {
         &self.inner
     }
 
+    pub(crate) fn meter(&self) -> Option<&Meter> {
+        self.meter.as_ref()
+    }
+
     pub fn debug_context(&self) -> String {
         if self.debug_vars.is_empty() {
             String::new()
@@ -53,11 +93,14 @@ impl CelContext {
         inner.add_function("decimal.Mul", builtins::decimal_mul);
         inner.add_function("decimal.Cmp", builtins::decimal_cmp);
         inner.add_function("format", builtins::timestamp_format);
+        inner.add_function(explain::EXPLAIN_ENTER, explain::enter);
+        inner.add_function(explain::EXPLAIN_EXIT, explain::exit);
 
         Self {
             inner,
             clock,
             debug_vars: Vec::new(),
+            meter: None,
         }
     }
 }
@@ -73,6 +116,7 @@ impl std::fmt::Debug for CelContext {
         f.debug_struct("CelContext")
             .field("debug_vars", &self.debug_vars)
             .field("clock", &self.clock)
+            .field("limits", &self.evaluation_limits())
             .finish()
     }
 }
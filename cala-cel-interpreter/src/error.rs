@@ -13,6 +13,30 @@ pub enum ResultCoercionError {
     ExternalTypeCoercionError(String, String, &'static str, String),
 }
 
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CelTypeError {
+    #[error("CelTypeError - UnknownIdentifier: {0}")]
+    UnknownIdent(String),
+    #[error("CelTypeError - UnknownAttribute: No attribute '{1}' on '{0}'")]
+    UnknownAttribute(String, String),
+    #[error("CelTypeError - UnknownFunction: {0}")]
+    UnknownFunction(String),
+    #[error("CelTypeError - WrongArgumentCount: '{function}' takes {expected:?} argument(s), found {found}")]
+    WrongArgumentCount {
+        function: String,
+        expected: Vec<usize>,
+        found: usize,
+    },
+    #[error("CelTypeError - NoMatchingOverload: '{0}' cannot be applied to {1:?}")]
+    NoMatchingOverload(String, Vec<CelType>),
+    #[error("CelTypeError - BadType: expected {0:?} found {1:?}")]
+    BadType(CelType, CelType),
+    #[error("CelTypeError - BadResultType: expected {0:?} found {1:?}")]
+    BadResultType(CelType, CelType),
+    #[error("CelTypeError - Unsupported: {0}")]
+    Unsupported(String),
+}
+
 #[derive(Error, Debug)]
 pub enum CelError {
     #[error("CelError - CelParseError: {0}")]
@@ -41,6 +65,12 @@ pub enum CelError {
     TimestampError(String),
     #[error("CelError - NoMatchingOverload: {0}")]
     NoMatchingOverload(String),
+    #[error("CelError - StepLimitExceeded: more than {0} evaluation steps")]
+    StepLimitExceeded(u64),
+    #[error("CelError - SizeLimitExceeded: value larger than {0}")]
+    SizeLimitExceeded(usize),
+    #[error("CelError - DeadlineExceeded: evaluation took longer than {0:?}")]
+    DeadlineExceeded(std::time::Duration),
     #[error("CelError - Unexpected: {0}")]
     Unexpected(String),
 
@@ -0,0 +1,522 @@
+use cel::{
+    common::ast::{
+        operators, CallExpr, EntryExpr, Expr, IdedExpr, LiteralValue, MapExpr, StructExpr,
+    },
+    objects::Value,
+    Context, ExecutionError,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+};
+
+use crate::{error::CelError, value::CelValue};
+
+/// The values an evaluation produced, as a tree mirroring the structure of
+/// the expression — see [`crate::CelExpression::try_evaluate_explained`].
+///
+/// Literals and the variable a field is selected from are omitted as
+/// children, their value being evident from the parent's source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CelExplanation {
+    /// The sub-expression, rendered back to CEL source.
+    pub expression: String,
+    pub value: Option<String>,
+    pub error: Option<String>,
+    pub children: Vec<CelExplanation>,
+}
+
+impl CelExplanation {
+    fn fmt_indented(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(f, "{:indent$}{}", "", self.expression, indent = depth * 2)?;
+        match (&self.value, &self.error) {
+            (Some(value), _) => writeln!(f, " = {value}")?,
+            (None, Some(error)) => writeln!(f, " ! {error}")?,
+            (None, None) => writeln!(f)?,
+        }
+        for child in self.children.iter() {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for CelExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// Name of the internal function called before a recorded sub-expression
+/// is evaluated. The `@` prefix makes it impossible to call from
+/// user-written expressions.
+pub(crate) const EXPLAIN_ENTER: &str = "@cala.explain.enter";
+/// Name of the internal function a recorded sub-expression's value is
+/// passed through once evaluated.
+pub(crate) const EXPLAIN_EXIT: &str = "@cala.explain.exit";
+
+#[derive(Debug, Default)]
+struct Recorded {
+    entered: HashSet<u64>,
+    values: HashMap<u64, Value>,
+}
+
+thread_local! {
+    /// The values recorded by the explained evaluations running on this
+    /// thread, innermost last.
+    static RECORDINGS: RefCell<Vec<Recorded>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) fn enter(id: u64) -> Result<Value, ExecutionError> {
+    RECORDINGS.with_borrow_mut(|recordings| {
+        if let Some(recorded) = recordings.last_mut() {
+            recorded.entered.insert(id);
+        }
+    });
+    Ok(Value::Null)
+}
+
+pub(crate) fn exit(_entered: Value, value: Value, id: u64) -> Result<Value, ExecutionError> {
+    RECORDINGS.with_borrow_mut(|recordings| {
+        if let Some(recorded) = recordings.last_mut() {
+            recorded.values.insert(id, value.clone());
+        }
+    });
+    Ok(value)
+}
+
+pub(crate) fn is_probe(func_name: &str) -> bool {
+    func_name == EXPLAIN_ENTER || func_name == EXPLAIN_EXIT
+}
+
+/// The values recorded while evaluating an expression rewritten by
+/// [`instrument`], lasting until dropped.
+pub(crate) struct Recording {
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl Recording {
+    pub(crate) fn start() -> Self {
+        RECORDINGS.with_borrow_mut(|recordings| recordings.push(Recorded::default()));
+        Self {
+            _thread_bound: PhantomData,
+        }
+    }
+
+    /// Explain `expr` from the values recorded while evaluating it to
+    /// `result`.
+    ///
+    /// A sub-expression that was entered but produced no value failed along
+    /// with the whole evaluation and is reported with its error. One that
+    /// was never entered sat on a branch the interpreter short-circuited
+    /// (the untaken side of `&&`, `||` or `?:`) and has neither.
+    pub(crate) fn finish(
+        self,
+        expr: &IdedExpr,
+        ctx: &Context<'static>,
+        result: &Result<CelValue, CelError>,
+    ) -> CelExplanation {
+        RECORDINGS.with_borrow(|recordings| {
+            let recorded = recordings.last().expect("recording started");
+            let (value, error) = match result {
+                Ok(value) => (Some(format!("{value:?}")), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            CelExplanation {
+                expression: render(expr),
+                value,
+                children: explain_children(expr, ctx, recorded, error.as_deref()),
+                error,
+            }
+        })
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        RECORDINGS.with_borrow_mut(|recordings| recordings.pop());
+    }
+}
+
+fn explain_children(
+    expr: &IdedExpr,
+    ctx: &Context<'static>,
+    recorded: &Recorded,
+    failure: Option<&str>,
+) -> Vec<CelExplanation> {
+    children(expr, ctx)
+        .into_iter()
+        .filter(|child| !matches!(child.expr, Expr::Literal(_)))
+        .map(|child| {
+            let (value, error) = match recorded.values.get(&child.id) {
+                Some(value) => match CelValue::from_cel_value(value.clone()) {
+                    Ok(value) => (Some(format!("{value:?}")), None),
+                    Err(e) => (None, Some(e.to_string())),
+                },
+                None if recorded.entered.contains(&child.id) => (None, failure.map(str::to_string)),
+                None => (None, None),
+            };
+            CelExplanation {
+                expression: render(child),
+                value,
+                error,
+                children: explain_children(child, ctx, recorded, failure),
+            }
+        })
+        .collect()
+}
+
+/// Rewrite `expr` so that each sub-expression appearing in its explanation
+/// reports to [`EXPLAIN_ENTER`] before and [`EXPLAIN_EXIT`] after it is
+/// evaluated, so that a single evaluation records every value.
+///
+/// Comprehension bodies are not descended into, as they depend on loop
+/// variables that only exist during the iteration.
+pub(crate) fn instrument(expr: &IdedExpr, ctx: &Context<'static>) -> IdedExpr {
+    let probe = |child: &IdedExpr| match child.expr {
+        Expr::Literal(_) => child.clone(),
+        _ => probed(instrument(child, ctx)),
+    };
+    let mut expr = expr.clone();
+    match &mut expr.expr {
+        Expr::Call(call) => {
+            if let Some(target) = call.target.as_mut() {
+                if shown_target(target, ctx) {
+                    **target = probe(target);
+                }
+            }
+            call.args = call.args.iter().map(probe).collect();
+        }
+        Expr::List(list) => list.elements = list.elements.iter().map(probe).collect(),
+        Expr::Map(MapExpr { entries }) | Expr::Struct(StructExpr { entries, .. }) => {
+            for entry in entries.iter_mut() {
+                match &mut entry.expr {
+                    EntryExpr::MapEntry(entry) => {
+                        entry.key = probe(&entry.key);
+                        entry.value = probe(&entry.value);
+                    }
+                    EntryExpr::StructField(field) => field.value = probe(&field.value),
+                }
+            }
+        }
+        Expr::Select(select) if !matches!(select.operand.expr, Expr::Ident(_)) => {
+            *select.operand = probe(&select.operand);
+        }
+        Expr::Comprehension(comprehension) => {
+            comprehension.iter_range = probe(&comprehension.iter_range);
+        }
+        _ => (),
+    }
+    expr
+}
+
+fn probed(expr: IdedExpr) -> IdedExpr {
+    let id = expr.id;
+    let id_literal = || IdedExpr {
+        id,
+        expr: Expr::Literal(LiteralValue::UInt(id.into())),
+    };
+    let enter = IdedExpr {
+        id,
+        expr: Expr::Call(CallExpr {
+            func_name: EXPLAIN_ENTER.to_string(),
+            target: None,
+            args: vec![id_literal()],
+        }),
+    };
+    IdedExpr {
+        id,
+        expr: Expr::Call(CallExpr {
+            func_name: EXPLAIN_EXIT.to_string(),
+            target: None,
+            args: vec![enter, expr, id_literal()],
+        }),
+    }
+}
+
+/// An identifier target that does not resolve is a function namespace
+/// (`decimal.Add`), not a value.
+fn shown_target(target: &IdedExpr, ctx: &Context<'static>) -> bool {
+    !matches!(target.expr, Expr::Ident(_)) || Value::resolve(target, ctx).is_ok()
+}
+
+fn children<'a>(expr: &'a IdedExpr, ctx: &Context<'static>) -> Vec<&'a IdedExpr> {
+    match &expr.expr {
+        Expr::Call(call) => call
+            .target
+            .as_deref()
+            .filter(|target| shown_target(target, ctx))
+            .into_iter()
+            .chain(call.args.iter())
+            .collect(),
+        Expr::List(list) => list.elements.iter().collect(),
+        Expr::Map(map) => map
+            .entries
+            .iter()
+            .flat_map(|entry| match &entry.expr {
+                EntryExpr::MapEntry(entry) => vec![&entry.key, &entry.value],
+                EntryExpr::StructField(field) => vec![&field.value],
+            })
+            .collect(),
+        Expr::Struct(s) => s
+            .entries
+            .iter()
+            .filter_map(|entry| match &entry.expr {
+                EntryExpr::StructField(field) => Some(&field.value),
+                EntryExpr::MapEntry(_) => None,
+            })
+            .collect(),
+        Expr::Select(select) if !matches!(select.operand.expr, Expr::Ident(_)) => {
+            vec![&select.operand]
+        }
+        Expr::Comprehension(comprehension) => vec![&comprehension.iter_range],
+        _ => Vec::new(),
+    }
+}
+
+/// Symbol and precedence of a binary operator.
+fn binary_operator(name: &str) -> Option<(&'static str, u8)> {
+    Some(match name {
+        operators::LOGICAL_OR => ("||", 1),
+        operators::LOGICAL_AND => ("&&", 2),
+        operators::EQUALS => ("==", 3),
+        operators::NOT_EQUALS => ("!=", 3),
+        operators::LESS => ("<", 3),
+        operators::LESS_EQUALS => ("<=", 3),
+        operators::GREATER => (">", 3),
+        operators::GREATER_EQUALS => (">=", 3),
+        operators::IN => ("in", 3),
+        operators::ADD => ("+", 4),
+        operators::SUBSTRACT => ("-", 4),
+        operators::MULTIPLY => ("*", 5),
+        operators::DIVIDE => ("/", 5),
+        operators::MODULO => ("%", 5),
+        _ => return None,
+    })
+}
+
+fn precedence(expr: &IdedExpr) -> u8 {
+    match &expr.expr {
+        Expr::Call(call) if call.func_name == operators::CONDITIONAL => 0,
+        Expr::Call(call) => binary_operator(&call.func_name).map_or(u8::MAX, |(_, p)| p),
+        _ => u8::MAX,
+    }
+}
+
+/// Render `expr` as an operand of an operator binding at least as tightly
+/// as `min`, parenthesizing it if needed.
+fn operand(expr: &IdedExpr, min: u8) -> String {
+    if precedence(expr) < min {
+        format!("({})", render(expr))
+    } else {
+        render(expr)
+    }
+}
+
+fn join(exprs: &[IdedExpr]) -> String {
+    exprs.iter().map(render).collect::<Vec<_>>().join(", ")
+}
+
+pub(crate) fn render(expr: &IdedExpr) -> String {
+    match &expr.expr {
+        Expr::Call(call) => match (call.func_name.as_str(), call.args.as_slice()) {
+            (operators::CONDITIONAL, [cond, left, right]) => format!(
+                "{} ? {} : {}",
+                operand(cond, 1),
+                operand(left, 1),
+                render(right)
+            ),
+            (operators::LOGICAL_NOT, [arg]) => format!("!{}", operand(arg, u8::MAX)),
+            (operators::NEGATE, [arg]) => format!("-{}", operand(arg, u8::MAX)),
+            (operators::NOT_STRICTLY_FALSE, [arg]) => render(arg),
+            (operators::INDEX, [arg, index]) => {
+                format!("{}[{}]", operand(arg, u8::MAX), render(index))
+            }
+            (name, [left, right]) if binary_operator(name).is_some() => {
+                let (symbol, p) = binary_operator(name).expect("checked above");
+                format!("{} {symbol} {}", operand(left, p), operand(right, p + 1))
+            }
+            (name, args) => match &call.target {
+                Some(target) => format!("{}.{name}({})", operand(target, u8::MAX), join(args)),
+                None => format!("{name}({})", join(args)),
+            },
+        },
+        Expr::Comprehension(comprehension) => format!(
+            "{}.<comprehension over {}>",
+            operand(&comprehension.iter_range, u8::MAX),
+            comprehension.iter_var
+        ),
+        Expr::Ident(name) => name.clone(),
+        Expr::List(list) => format!("[{}]", join(&list.elements)),
+        Expr::Literal(literal) => match literal {
+            LiteralValue::Boolean(b) => b.inner().to_string(),
+            LiteralValue::Bytes(b) => format!("b{:?}", String::from_utf8_lossy(b.inner())),
+            LiteralValue::Double(d) => format!("{:?}", d.inner()),
+            LiteralValue::Int(i) => i.inner().to_string(),
+            LiteralValue::Null => "null".to_string(),
+            LiteralValue::String(s) => format!("'{}'", s.inner().replace('\'', "\\'")),
+            LiteralValue::UInt(u) => format!("{}u", u.inner()),
+        },
+        Expr::Map(map) => format!(
+            "{{{}}}",
+            map.entries
+                .iter()
+                .map(|entry| match &entry.expr {
+                    EntryExpr::MapEntry(entry) => {
+                        format!("{}: {}", render(&entry.key), render(&entry.value))
+                    }
+                    EntryExpr::StructField(field) => {
+                        format!("{}: {}", field.field, render(&field.value))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Expr::Select(select) if select.test => {
+            format!(
+                "has({}.{})",
+                operand(&select.operand, u8::MAX),
+                select.field
+            )
+        }
+        Expr::Select(select) => format!("{}.{}", operand(&select.operand, u8::MAX), select.field),
+        Expr::Struct(s) => format!(
+            "{}{{{}}}",
+            s.type_name,
+            s.entries
+                .iter()
+                .filter_map(|entry| match &entry.expr {
+                    EntryExpr::StructField(field) => {
+                        Some(format!("{}: {}", field.field, render(&field.value)))
+                    }
+                    EntryExpr::MapEntry(_) => None,
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Expr::Unspecified => "<unspecified>".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::{
+        CelContext, CelError, CelEvaluationLimits, CelExpression, CelFunctionRegistry, CelMap,
+        CelValue,
+    };
+
+    #[test]
+    fn renders_expressions_back_to_source() {
+        for source in [
+            "decimal.Cmp(params.amount, decimal('100')) > 0",
+            "!(a && b) || c[0] == -1",
+            "(a + b) * c - (d - e)",
+            "has(params.metadata) ? params.metadata.code : 'NONE'",
+            "x in [1, 2u, 3.5]",
+            "size({'a': 1})",
+        ] {
+            let expression: CelExpression = source.parse().unwrap();
+            assert_eq!(super::render(expression.ast()), source);
+        }
+    }
+
+    #[test]
+    fn explains_sub_expressions() {
+        let mut params = CelMap::new();
+        params.insert("amount", rust_decimal::Decimal::new(150, 0));
+        params.insert("currency", "USD");
+        let mut context = CelContext::new();
+        context.add_variable("params", params);
+
+        let expression: CelExpression =
+            "params.currency == 'USD' && decimal.Cmp(params.amount, decimal('100')) > 0"
+                .parse()
+                .unwrap();
+        let (result, explanation) = expression.try_evaluate_explained::<bool>(&context);
+        assert!(result.unwrap());
+        assert_eq!(explanation.value.as_deref(), Some("Bool(true)"));
+
+        let [currency, comparison] = explanation.children.as_slice() else {
+            panic!("unexpected children: {explanation}");
+        };
+        assert_eq!(currency.expression, "params.currency == 'USD'");
+        assert_eq!(currency.children[0].expression, "params.currency");
+        assert_eq!(
+            currency.children[0].value.as_deref(),
+            Some("String(\"USD\")")
+        );
+
+        let cmp = &comparison.children[0];
+        assert_eq!(cmp.expression, "decimal.Cmp(params.amount, decimal('100'))");
+        assert_eq!(cmp.value.as_deref(), Some("Int(1)"));
+        assert_eq!(cmp.children[0].expression, "params.amount");
+        assert_eq!(cmp.children[0].value.as_deref(), Some("Decimal(150)"));
+    }
+
+    #[test]
+    fn explains_failed_evaluations() {
+        let context = CelContext::new();
+        let expression: CelExpression = "missing.field == 1".parse().unwrap();
+        let (result, explanation) = expression.try_evaluate_explained::<bool>(&context);
+        assert!(result.is_err());
+        assert!(explanation.error.is_some());
+        assert!(explanation.children[0].error.is_some());
+    }
+
+    #[test]
+    fn explains_in_a_single_evaluation() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&calls);
+        let mut functions = CelFunctionRegistry::new();
+        functions.register("counted", move |_| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(CelValue::Int(1))
+        });
+        let mut context = CelContext::new();
+        context.add_functions(&functions);
+
+        let expression: CelExpression = "counted() + counted() == 2".parse().unwrap();
+        let (result, explanation) = expression.try_evaluate_explained::<bool>(&context);
+        assert!(result.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let sum = &explanation.children[0];
+        assert_eq!(sum.value.as_deref(), Some("Int(2)"));
+        assert_eq!(sum.children[0].value.as_deref(), Some("Int(1)"));
+    }
+
+    #[test]
+    fn short_circuited_branches_are_not_evaluated() {
+        let context = CelContext::new();
+        let expression: CelExpression = "1 == 2 && missing.field == 1".parse().unwrap();
+        let (result, explanation) = expression.try_evaluate_explained::<bool>(&context);
+        assert!(!result.unwrap());
+        let [taken, skipped] = explanation.children.as_slice() else {
+            panic!("unexpected children: {explanation}");
+        };
+        assert_eq!(taken.value.as_deref(), Some("Bool(false)"));
+        assert_eq!((&skipped.value, &skipped.error), (&None, &None));
+    }
+
+    #[test]
+    fn explains_within_evaluation_limits() {
+        let mut context = CelContext::new();
+        context.set_evaluation_limits(CelEvaluationLimits::new().max_steps(20));
+        let expression: CelExpression = "size([1, 2, 3, 4, 5, 6, 7, 8, 9, 10].map(x, x * 2)) > 0"
+            .parse()
+            .unwrap();
+        let (result, explanation) = expression.try_evaluate_explained::<bool>(&context);
+        assert!(matches!(
+            result,
+            Err(CelError::EvaluationError(_, ref e)) if matches!(**e, CelError::StepLimitExceeded(20))
+        ));
+        assert!(explanation.children[0].error.is_some());
+    }
+}
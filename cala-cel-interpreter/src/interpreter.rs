@@ -1,7 +1,10 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, OnceLock,
+};
 
-use cached::cached;
-use cel::Program;
+use cached::{cached, Cached, CachedPeek};
+use cel::{common::ast::IdedExpr, objects::Value, Program};
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
@@ -30,7 +33,7 @@ const COMPILE_STACK_BYTES: usize = 32 * 1024 * 1024;
 /// unique expression thanks to the memoization above.
 #[cached(max_size = 10000, cache_err = true)]
 #[instrument(name = "cel.compile", skip(source), fields(expression = %source), err(level = tracing::Level::WARN))]
-fn compile_program(source: String) -> Result<Arc<Program>, String> {
+fn compile_program(source: String) -> Result<Arc<CompiledProgram>, String> {
     let started = std::time::Instant::now();
     let expression = source.clone();
     let result = std::thread::Builder::new()
@@ -38,7 +41,7 @@ fn compile_program(source: String) -> Result<Arc<Program>, String> {
         .stack_size(COMPILE_STACK_BYTES)
         .spawn(move || {
             Program::compile(&source)
-                .map(Arc::new)
+                .map(|program| Arc::new(CompiledProgram::new(program.expression().clone())))
                 .map_err(|e| e.to_string())
         })
         .expect("failed to spawn cel-compile thread")
@@ -61,7 +64,108 @@ fn compile_program(source: String) -> Result<Arc<Program>, String> {
     result
 }
 
-use crate::{context::*, error::*, value::*};
+/// Number of programs loaded into the compile cache without parsing.
+static PRELOADED: AtomicU64 = AtomicU64::new(0);
+
+/// Hit and miss counters of the process-wide compile cache, see
+/// [`CelCompileCache::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CelCompileCacheStats {
+    /// Expressions whose program was found in the cache.
+    pub hits: u64,
+    /// Expressions that had to be parsed.
+    pub misses: u64,
+    /// Programs loaded from a [`CelCompiledExpression`] instead of parsed.
+    pub preloaded: u64,
+    /// Programs currently cached.
+    pub entries: usize,
+}
+
+/// The process-wide cache of compiled programs every [`CelExpression`] is
+/// built from.
+///
+/// Parsing is by far the most expensive step of creating an expression, so a
+/// process that would otherwise compile many stored expressions on startup
+/// can [`Self::export`] the cache of a warm process, persist it, and
+/// [`Self::preload`] it on the next start.
+pub struct CelCompileCache;
+
+impl CelCompileCache {
+    /// Load precompiled expressions into the cache, returning how many were
+    /// loaded. Expressions serialized by an incompatible version, or whose
+    /// AST does not match the source it was exported with, are skipped and
+    /// will be parsed on first use instead. A source already cached keeps
+    /// its program.
+    pub fn preload(expressions: impl IntoIterator<Item = CelCompiledExpression>) -> usize {
+        let mut cache = COMPILE_PROGRAM.write();
+        let mut loaded = 0;
+        for compiled in expressions.into_iter().filter(|c| c.is_current()) {
+            if cache.cache_peek(&compiled.source).is_some() {
+                continue;
+            }
+            let program = Arc::new(CompiledProgram::new(compiled.ast.into()));
+            cache.cache_set(compiled.source, Ok(program));
+            loaded += 1;
+        }
+        PRELOADED.fetch_add(loaded as u64, Ordering::Relaxed);
+        loaded
+    }
+
+    /// Every successfully compiled expression currently cached.
+    pub fn export() -> Vec<CelCompiledExpression> {
+        let cache = COMPILE_PROGRAM.read();
+        cache
+            .iter_order()
+            .into_iter()
+            .filter_map(|(source, program)| {
+                program
+                    .ok()
+                    .map(|program| CelCompiledExpression::new(source, &program.expr))
+            })
+            .collect()
+    }
+
+    pub fn stats() -> CelCompileCacheStats {
+        let cache = COMPILE_PROGRAM.read();
+        CelCompileCacheStats {
+            hits: cache.cache_hits().unwrap_or_default(),
+            misses: cache.cache_misses().unwrap_or_default(),
+            preloaded: PRELOADED.load(Ordering::Relaxed),
+            entries: cache.cache_size(),
+        }
+    }
+}
+
+/// A parsed program together with what is derived from it once per unique
+/// source.
+#[derive(Debug)]
+struct CompiledProgram {
+    expr: IdedExpr,
+    cost: u64,
+    /// The AST rewritten to report to the evaluation meter — only built the
+    /// first time the expression is evaluated against a context with limits.
+    metered: OnceLock<IdedExpr>,
+}
+
+impl CompiledProgram {
+    fn new(expr: IdedExpr) -> Self {
+        let cost = limits::estimate_cost(&expr);
+        Self {
+            expr,
+            cost,
+            metered: OnceLock::new(),
+        }
+    }
+
+    fn metered(&self) -> &IdedExpr {
+        self.metered
+            .get_or_init(|| limits::meter_expression(&self.expr))
+    }
+}
+
+use crate::{
+    cel_type::*, checker::*, compiled::*, context::*, error::*, explain::*, limits, value::*,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(try_from = "String")]
@@ -70,7 +174,7 @@ use crate::{context::*, error::*, value::*};
 pub struct CelExpression {
     source: String,
     #[serde(skip)]
-    program: Arc<Program>,
+    program: Arc<CompiledProgram>,
 }
 
 impl CelExpression {
@@ -85,17 +189,105 @@ impl CelExpression {
         })?)
     }
 
-    #[instrument(name = "cel.evaluate", skip_all, fields(expression = %self.source, context = tracing::field::Empty, result = tracing::field::Empty), err(level = tracing::Level::WARN))]
+    /// Like [`Self::try_evaluate`], additionally recording the value every
+    /// sub-expression produced. The explanation is also emitted as a debug
+    /// level tracing event.
+    ///
+    /// The values are recorded during the one evaluation producing the
+    /// result, subject to the same [`crate::CelEvaluationLimits`]; rewriting
+    /// the expression to record them is paid on every call, so explaining is
+    /// meant for diagnosing a result rather than for every evaluation.
+    pub fn try_evaluate_explained<'a, T: TryFrom<CelResult<'a>, Error = ResultCoercionError>>(
+        &'a self,
+        ctx: &CelContext,
+    ) -> (Result<T, CelError>, CelExplanation) {
+        let recording = Recording::start();
+        let instrumented = instrument(self.ast(), ctx.inner());
+        let res = match ctx.meter() {
+            None => self.resolve(&instrumented, ctx),
+            Some(_) => self.resolve(&limits::meter_expression(&instrumented), ctx),
+        };
+        let explanation = recording.finish(self.ast(), ctx.inner(), &res);
+        tracing::debug!(expression = %self.source, explanation = %explanation, "explained CEL evaluation");
+        let res = res.and_then(|val| {
+            Ok(T::try_from(CelResult {
+                expr: &self.source,
+                val,
+            })?)
+        });
+        (res, explanation)
+    }
+
+    pub(crate) fn ast(&self) -> &IdedExpr {
+        &self.program.expr
+    }
+
+    /// The expression together with its parsed AST, for persisting
+    /// alongside (or instead of) the source.
+    pub fn compiled(&self) -> CelCompiledExpression {
+        CelCompiledExpression::new(self.source.clone(), &self.program.expr)
+    }
+
+    /// Statically type-check the expression against the variables and
+    /// functions declared in `env`, returning the type it evaluates to.
+    /// All problems found are reported, not just the first.
+    pub fn check(&self, env: &CelTypeEnv) -> Result<CelType, Vec<CelTypeError>> {
+        env.check(&self.program.expr)
+    }
+
+    /// Like [`Self::check`], additionally requiring the result to be usable
+    /// as `expected` (a `Dyn` result is accepted).
+    pub fn check_as(
+        &self,
+        env: &CelTypeEnv,
+        expected: CelType,
+    ) -> Result<CelType, Vec<CelTypeError>> {
+        let found = self.check(env)?;
+        if compatible(expected, found) {
+            Ok(found)
+        } else {
+            Err(vec![CelTypeError::BadResultType(expected, found)])
+        }
+    }
+
+    /// Static estimate of the work evaluating this expression takes, in
+    /// the same unit as [`crate::CelEvaluationLimits::max_steps`].
+    /// Comprehensions over ranges of unknown size are assumed to iterate a
+    /// fixed number of times, so the estimate is meant for rejecting
+    /// outliers rather than as an exact bound.
+    pub fn estimated_cost(&self) -> u64 {
+        self.program.cost
+    }
+
     pub fn evaluate(&self, ctx: &CelContext) -> Result<CelValue, CelError> {
+        match ctx.meter() {
+            None => self.resolve(&self.program.expr, ctx),
+            Some(_) => self.resolve(self.program.metered(), ctx),
+        }
+    }
+
+    #[instrument(name = "cel.evaluate", skip_all, fields(expression = %self.source, context = tracing::field::Empty, result = tracing::field::Empty), err(level = tracing::Level::WARN))]
+    fn resolve(&self, program: &IdedExpr, ctx: &CelContext) -> Result<CelValue, CelError> {
         let context_debug = ctx.debug_context();
         if !context_debug.is_empty() {
             tracing::Span::current().record("context", &context_debug);
         }
 
-        let value = self
-            .program
-            .execute(ctx.inner())
-            .map_err(|e| CelError::EvaluationError(self.source.clone(), Box::new(e.into())))?;
+        let value = match ctx.meter() {
+            None => Value::resolve(program, ctx.inner()),
+            Some(meter) => {
+                let metering = meter.start();
+                let res = Value::resolve(program, ctx.inner());
+                if let Some(exceeded) = metering.exceeded() {
+                    return Err(CelError::EvaluationError(
+                        self.source.clone(),
+                        Box::new(exceeded),
+                    ));
+                }
+                res
+            }
+        }
+        .map_err(|e| CelError::EvaluationError(self.source.clone(), Box::new(e.into())))?;
         let result = CelValue::from_cel_value(value)?;
 
         tracing::Span::current().record("result", format!("{:?}", result));
@@ -125,6 +317,24 @@ impl TryFrom<String> for CelExpression {
     }
 }
 
+impl TryFrom<CelCompiledExpression> for CelExpression {
+    type Error = CelError;
+
+    /// Rebuild the expression from its AST, without parsing. The program is
+    /// also added to the compile cache, so expressions later created from
+    /// the same source skip the parser too. A program already cached for the
+    /// source is used as is, and the source is parsed when the AST was
+    /// serialized by an incompatible version or does not match it.
+    fn try_from(compiled: CelCompiledExpression) -> Result<Self, Self::Error> {
+        if !compiled.is_current() {
+            return Self::try_from(compiled.source);
+        }
+        let source = compiled.source.clone();
+        CelCompileCache::preload(std::iter::once(compiled));
+        Self::try_from(source)
+    }
+}
+
 impl TryFrom<&str> for CelExpression {
     type Error = CelError;
 
@@ -144,8 +354,85 @@ impl std::str::FromStr for CelExpression {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::CelEvaluationLimits;
     use chrono::NaiveDate;
 
+    #[test]
+    fn compiled_expression_round_trip() -> anyhow::Result<()> {
+        let source = "[1, 2, 3].map(x, x * 2).exists(y, y == 6) ? {'k': b'v'}.k : b'none'";
+        let expression: CelExpression = source.parse()?;
+        let json = serde_json::to_string(&expression.compiled())?;
+        COMPILE_PROGRAM.write().cache_remove(source);
+
+        let preloaded = CelCompileCache::stats().preloaded;
+        let compiled: CelCompiledExpression = serde_json::from_str(&json)?;
+        assert_eq!(compiled.source(), source);
+        let restored = CelExpression::try_from(compiled)?;
+        assert!(CelCompileCache::stats().preloaded > preloaded);
+        assert_eq!(restored.ast(), expression.ast());
+
+        let context = CelContext::new();
+        assert_eq!(restored.evaluate(&context)?, expression.evaluate(&context)?);
+        Ok(())
+    }
+
+    #[test]
+    fn compiled_expression_from_other_version_is_reparsed() -> anyhow::Result<()> {
+        let expression: CelExpression = "1 + 2".parse()?;
+        let mut compiled = expression.compiled();
+        compiled.version += 1;
+        compiled.ast = "0".parse::<CelExpression>()?.compiled().ast;
+        assert_eq!(CelCompileCache::preload([compiled.clone()]), 0);
+
+        let restored = CelExpression::try_from(compiled)?;
+        assert_eq!(restored.evaluate(&CelContext::new())?, CelValue::Int(3));
+        Ok(())
+    }
+
+    #[test]
+    fn preload_keeps_cached_programs() -> anyhow::Result<()> {
+        let expression: CelExpression = "'preload_keeps_cached_programs'".parse()?;
+        let other = "0".parse::<CelExpression>()?;
+        let forged = CelCompiledExpression::new(expression.to_string(), other.ast());
+        assert_eq!(CelCompileCache::preload([forged.clone()]), 0);
+
+        let restored = CelExpression::try_from(forged)?;
+        assert_eq!(
+            restored.evaluate(&CelContext::new())?,
+            CelValue::from("preload_keeps_cached_programs")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compiled_expression_not_matching_its_source_is_reparsed() -> anyhow::Result<()> {
+        let source = "'compiled_expression_not_matching_its_source_is_reparsed'";
+        let mut compiled = source.parse::<CelExpression>()?.compiled();
+        COMPILE_PROGRAM.write().cache_remove(source);
+        compiled.ast = "0".parse::<CelExpression>()?.compiled().ast;
+        assert_eq!(CelCompileCache::preload([compiled.clone()]), 0);
+
+        let restored = CelExpression::try_from(compiled)?;
+        assert_eq!(
+            restored.evaluate(&CelContext::new())?,
+            CelValue::from("compiled_expression_not_matching_its_source_is_reparsed")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compile_cache_exports_and_counts_hits() -> anyhow::Result<()> {
+        let source = "'compile_cache_exports_and_counts_hits'";
+        let _: CelExpression = source.parse()?;
+        let hits = CelCompileCache::stats().hits;
+        let _: CelExpression = source.parse()?;
+        assert!(CelCompileCache::stats().hits > hits);
+        assert!(CelCompileCache::export()
+            .iter()
+            .any(|compiled| compiled.source() == source));
+        Ok(())
+    }
+
     #[test]
     fn parser_panic_surfaces_as_error() {
         // A panic during compilation must surface as a parse error, not
@@ -281,6 +568,110 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn evaluation_within_limits() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        context.set_evaluation_limits(CelEvaluationLimits::new().max_steps(50).max_size(10));
+
+        let expression = "decimal.Add(decimal('1'), decimal('2'))".parse::<CelExpression>()?;
+        assert_eq!(expression.evaluate(&context)?, CelValue::Decimal(3.into()));
+
+        let expression = "[1, 2, 3].all(x, x > 0)".parse::<CelExpression>()?;
+        assert_eq!(expression.evaluate(&context)?, CelValue::Bool(true));
+        Ok(())
+    }
+
+    #[test]
+    fn step_limit() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        context.set_evaluation_limits(CelEvaluationLimits::new().max_steps(20));
+        let expression =
+            "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10].map(x, x * 2)".parse::<CelExpression>()?;
+        let err = expression.evaluate(&context).unwrap_err();
+        assert!(matches!(
+            err,
+            CelError::EvaluationError(_, ref e) if matches!(**e, CelError::StepLimitExceeded(20))
+        ));
+
+        // steps are counted per evaluation, not per context
+        let expression = "1 + 1".parse::<CelExpression>()?;
+        assert_eq!(expression.evaluate(&context)?, CelValue::Int(2));
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_evaluations_are_metered_separately() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        context.set_evaluation_limits(CelEvaluationLimits::new().max_steps(20));
+        let expression = "[1, 2, 3, 4, 5].map(x, x * 2)".parse::<CelExpression>()?;
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..200 {
+                        assert!(expression.evaluate(&context).is_ok());
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn rebinding_a_variable_replaces_it() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        let expression = "x + 1".parse::<CelExpression>()?;
+        context.add_variable("x", 1i64);
+        assert_eq!(expression.evaluate(&context)?, CelValue::from(2i64));
+        context.add_variable("x", 5i64);
+        assert_eq!(expression.evaluate(&context)?, CelValue::from(6i64));
+        assert_eq!(context.debug_context().matches("x=").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn size_limit() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        context.set_evaluation_limits(CelEvaluationLimits::new().max_size(4));
+        let expression = "'ab' + 'cd'".parse::<CelExpression>()?;
+        assert_eq!(expression.evaluate(&context)?, CelValue::from("abcd"));
+
+        let expression = "'ab' + 'cd' + 'e'".parse::<CelExpression>()?;
+        let err = expression.evaluate(&context).unwrap_err();
+        assert!(matches!(
+            err,
+            CelError::EvaluationError(_, ref e) if matches!(**e, CelError::SizeLimitExceeded(4))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn deadline() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        context
+            .set_evaluation_limits(CelEvaluationLimits::new().timeout(std::time::Duration::ZERO));
+        let expression = "[1, 2, 3].map(x, x * 2)".parse::<CelExpression>()?;
+        let err = expression.evaluate(&context).unwrap_err();
+        assert!(matches!(
+            err,
+            CelError::EvaluationError(_, ref e) if matches!(**e, CelError::DeadlineExceeded(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn estimated_cost() -> anyhow::Result<()> {
+        assert_eq!("1".parse::<CelExpression>()?.estimated_cost(), 1);
+        assert_eq!("1 + 2".parse::<CelExpression>()?.estimated_cost(), 3);
+
+        let literal_range = "[1, 2].map(x, x * 2)".parse::<CelExpression>()?;
+        let unknown_range = "params.xs.map(x, x * 2)".parse::<CelExpression>()?;
+        assert!(literal_range.estimated_cost() < unknown_range.estimated_cost());
+
+        let nested = "params.xs.map(x, params.ys.map(y, x * y))".parse::<CelExpression>()?;
+        assert!(nested.estimated_cost() > 100 * unknown_range.estimated_cost() / 2);
+        Ok(())
+    }
+
     #[test]
     fn has_macro_with_map() {
         let expression = "has(params.hello)".parse::<CelExpression>().unwrap();
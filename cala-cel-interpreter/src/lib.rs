@@ -3,13 +3,23 @@
 
 mod builtins;
 mod cel_type;
+mod checker;
+mod compiled;
 mod context;
 mod error;
+mod explain;
 mod interpreter;
+mod limits;
+mod registry;
 mod value;
 
 pub use cel_type::*;
+pub use checker::*;
+pub use compiled::CelCompiledExpression;
 pub use context::*;
 pub use error::*;
+pub use explain::CelExplanation;
 pub use interpreter::*;
+pub use limits::CelEvaluationLimits;
+pub use registry::*;
 pub use value::*;
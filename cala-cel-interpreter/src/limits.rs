@@ -0,0 +1,297 @@
+use cel::{
+    common::ast::{CallExpr, ComprehensionExpr, EntryExpr, Expr, IdedExpr, MapExpr, StructExpr},
+    objects::Value,
+    ExecutionError,
+};
+
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use crate::{error::CelError, explain};
+
+/// Name of the internal function every metered node is wrapped in. The `@`
+/// prefix makes it impossible to call from user-written expressions.
+pub(crate) const METER_FUNCTION: &str = "@cala.meter";
+
+/// Number of iterations assumed for a comprehension over a range whose size
+/// is not known statically (anything but a list literal).
+const UNKNOWN_RANGE_ITERATIONS: u64 = 100;
+
+/// Resource budget for a single evaluation, configured on a
+/// [`crate::CelContext`] via [`crate::CelContext::set_evaluation_limits`].
+///
+/// Every limit is optional; an unset limit is not enforced.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CelEvaluationLimits {
+    max_steps: Option<u64>,
+    max_size: Option<usize>,
+    timeout: Option<Duration>,
+}
+
+impl CelEvaluationLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of function calls, operators, literals of lists or
+    /// maps and comprehension iterations evaluated.
+    pub fn max_steps(mut self, max_steps: u64) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Maximum length of any string or bytes value, or number of elements
+    /// of any list or map, produced during evaluation.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Wall-clock time an evaluation may take.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Exceeded {
+    Steps(u64),
+    Size(usize),
+    Deadline(Duration),
+}
+
+#[derive(Debug)]
+struct MeterState {
+    steps: u64,
+    started: Instant,
+    exceeded: Option<Exceeded>,
+}
+
+thread_local! {
+    /// Accounting of the metered evaluations running on this thread,
+    /// innermost last. Evaluation is synchronous, so its accounting never
+    /// leaves the thread it started on, and evaluations sharing a context
+    /// on other threads cannot reset it. A stack keeps an application
+    /// function that itself evaluates an expression from clobbering the
+    /// evaluation calling it.
+    static EVALUATIONS: RefCell<Vec<MeterState>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The limits a context enforces; the work done is accounted per
+/// evaluation, see [`Meter::start`].
+#[derive(Debug, Clone)]
+pub(crate) struct Meter {
+    limits: CelEvaluationLimits,
+}
+
+impl Meter {
+    pub(crate) fn new(limits: CelEvaluationLimits) -> Self {
+        Self { limits }
+    }
+
+    pub(crate) fn limits(&self) -> &CelEvaluationLimits {
+        &self.limits
+    }
+
+    /// Begin accounting an evaluation on the current thread, lasting until
+    /// the returned guard is dropped.
+    pub(crate) fn start(&self) -> Metering {
+        EVALUATIONS.with_borrow_mut(|evaluations| {
+            evaluations.push(MeterState {
+                steps: 0,
+                started: Instant::now(),
+                exceeded: None,
+            })
+        });
+        Metering {
+            _thread_bound: PhantomData,
+        }
+    }
+
+    pub(crate) fn step(&self, value: Value) -> Result<Value, ExecutionError> {
+        EVALUATIONS.with_borrow_mut(|evaluations| {
+            let Some(state) = evaluations.last_mut() else {
+                return Ok(value);
+            };
+            state.steps += 1;
+            let exceeded = match (
+                self.limits.max_steps,
+                self.limits.max_size,
+                self.limits.timeout,
+            ) {
+                (Some(max), _, _) if state.steps > max => Some(Exceeded::Steps(max)),
+                (_, Some(max), _) if size_of(&value) > max => Some(Exceeded::Size(max)),
+                (_, _, Some(timeout)) if state.started.elapsed() > timeout => {
+                    Some(Exceeded::Deadline(timeout))
+                }
+                _ => None,
+            };
+            if let Some(exceeded) = exceeded {
+                state.exceeded = Some(exceeded);
+                return Err(ExecutionError::function_error(
+                    METER_FUNCTION,
+                    format!("{exceeded:?} limit exceeded"),
+                ));
+            }
+            Ok(value)
+        })
+    }
+}
+
+/// The accounting of one evaluation, see [`Meter::start`].
+pub(crate) struct Metering {
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl Metering {
+    /// The limit that aborted the evaluation, if any.
+    pub(crate) fn exceeded(&self) -> Option<CelError> {
+        EVALUATIONS.with_borrow(|evaluations| {
+            evaluations
+                .last()
+                .and_then(|state| state.exceeded)
+                .map(|exceeded| match exceeded {
+                    Exceeded::Steps(max) => CelError::StepLimitExceeded(max),
+                    Exceeded::Size(max) => CelError::SizeLimitExceeded(max),
+                    Exceeded::Deadline(timeout) => CelError::DeadlineExceeded(timeout),
+                })
+        })
+    }
+}
+
+impl Drop for Metering {
+    fn drop(&mut self) {
+        EVALUATIONS.with_borrow_mut(|evaluations| evaluations.pop());
+    }
+}
+
+fn size_of(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.len(),
+        Value::Bytes(b) => b.len(),
+        Value::List(l) => l.len(),
+        Value::Map(m) => m.map.len(),
+        _ => 0,
+    }
+}
+
+/// Rewrites `expr` so that every call, list or map literal and comprehension
+/// step reports its result to [`METER_FUNCTION`] before it is used.
+pub(crate) fn meter_expression(expr: &IdedExpr) -> IdedExpr {
+    let id = expr.id;
+    let inner = match &expr.expr {
+        // Explain probes are bookkeeping rather than work: only what they
+        // wrap is metered.
+        Expr::Call(call) if explain::is_probe(&call.func_name) => {
+            return IdedExpr {
+                id,
+                expr: Expr::Call(CallExpr {
+                    args: call.args.iter().map(meter_expression).collect(),
+                    ..call.clone()
+                }),
+            };
+        }
+        Expr::Call(call) => Expr::Call(CallExpr {
+            func_name: call.func_name.clone(),
+            // An identifier target may be a function namespace
+            // (`decimal.Add`), which must stay an identifier.
+            target: call.target.as_ref().map(|target| match &target.expr {
+                Expr::Ident(_) => target.clone(),
+                _ => Box::new(meter_expression(target)),
+            }),
+            args: call.args.iter().map(meter_expression).collect(),
+        }),
+        Expr::List(list) => {
+            let mut list = list.clone();
+            list.elements = list.elements.iter().map(meter_expression).collect();
+            Expr::List(list)
+        }
+        Expr::Map(map) => {
+            let mut map = map.clone();
+            for entry in map.entries.iter_mut() {
+                if let EntryExpr::MapEntry(entry) = &mut entry.expr {
+                    entry.key = meter_expression(&entry.key);
+                    entry.value = meter_expression(&entry.value);
+                }
+            }
+            Expr::Map(map)
+        }
+        Expr::Comprehension(comprehension) => Expr::Comprehension(Box::new(ComprehensionExpr {
+            iter_range: meter_expression(&comprehension.iter_range),
+            accu_init: meter_expression(&comprehension.accu_init),
+            loop_cond: meter_expression(&comprehension.loop_cond),
+            loop_step: metered(meter_expression(&comprehension.loop_step)),
+            result: meter_expression(&comprehension.result),
+            ..(**comprehension).clone()
+        })),
+        Expr::Select(select) => {
+            let mut select = select.clone();
+            select.operand = Box::new(meter_expression(&select.operand));
+            return IdedExpr {
+                id,
+                expr: Expr::Select(select),
+            };
+        }
+        _ => return expr.clone(),
+    };
+    metered(IdedExpr { id, expr: inner })
+}
+
+fn metered(expr: IdedExpr) -> IdedExpr {
+    if matches!(&expr.expr, Expr::Call(call) if call.func_name == METER_FUNCTION) {
+        return expr;
+    }
+    IdedExpr {
+        id: expr.id,
+        expr: Expr::Call(CallExpr {
+            func_name: METER_FUNCTION.to_string(),
+            target: None,
+            args: vec![expr],
+        }),
+    }
+}
+
+/// Static upper-bound style estimate of the work an expression performs:
+/// one unit per node, with comprehension bodies counted once per iteration.
+pub(crate) fn estimate_cost(expr: &IdedExpr) -> u64 {
+    match &expr.expr {
+        Expr::Call(call) => call
+            .args
+            .iter()
+            .chain(call.target.as_deref())
+            .map(estimate_cost)
+            .fold(1, u64::saturating_add),
+        Expr::List(list) => list
+            .elements
+            .iter()
+            .map(estimate_cost)
+            .fold(1, u64::saturating_add),
+        Expr::Map(MapExpr { entries }) | Expr::Struct(StructExpr { entries, .. }) => entries
+            .iter()
+            .map(|entry| match &entry.expr {
+                EntryExpr::MapEntry(entry) => {
+                    estimate_cost(&entry.key).saturating_add(estimate_cost(&entry.value))
+                }
+                EntryExpr::StructField(field) => estimate_cost(&field.value),
+            })
+            .fold(1, u64::saturating_add),
+        Expr::Select(select) => estimate_cost(&select.operand).saturating_add(1),
+        Expr::Comprehension(comprehension) => {
+            let iterations = match &comprehension.iter_range.expr {
+                Expr::List(list) => list.elements.len() as u64,
+                _ => UNKNOWN_RANGE_ITERATIONS,
+            };
+            let per_iteration = estimate_cost(&comprehension.loop_cond)
+                .saturating_add(estimate_cost(&comprehension.loop_step));
+            estimate_cost(&comprehension.iter_range)
+                .saturating_add(estimate_cost(&comprehension.accu_init))
+                .saturating_add(iterations.saturating_mul(per_iteration))
+                .saturating_add(estimate_cost(&comprehension.result))
+        }
+        Expr::Ident(_) | Expr::Literal(_) | Expr::Unspecified => 1,
+    }
+}
@@ -0,0 +1,173 @@
+use cel::{extractors::Arguments, objects::Value, Context, ExecutionError};
+
+use std::sync::Arc;
+
+use crate::{checker::*, error::*, value::*};
+
+type CelFunction = Arc<dyn Fn(&[CelValue]) -> Result<CelValue, CelError> + Send + Sync>;
+
+#[derive(Clone)]
+struct RegisteredFunction {
+    name: String,
+    function: CelFunction,
+    overloads: Vec<CelFunctionOverload>,
+}
+
+/// Application defined functions made available to expressions alongside the
+/// builtins.
+///
+/// Names may be namespaced with a `.` (`tax.Rate`), in which case they are
+/// called the same way the builtin `decimal.Add` is. Registering a name twice
+/// replaces the earlier function. Cloning is cheap, so a registry can be
+/// shared by every context a process builds.
+#[derive(Clone, Default)]
+pub struct CelFunctionRegistry {
+    functions: Arc<Vec<RegisteredFunction>>,
+}
+
+impl CelFunctionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `function` under `name`. Its arguments are not declared, so
+    /// the type checker accepts any call to it and treats the result as `Dyn`.
+    pub fn register<F>(&mut self, name: impl Into<String>, function: F) -> &mut Self
+    where
+        F: Fn(&[CelValue]) -> Result<CelValue, CelError> + Send + Sync + 'static,
+    {
+        self.register_typed(name, [], function)
+    }
+
+    /// Register `function` under `name`, declaring the call shapes it
+    /// accepts so that [`crate::CelExpression::check`] can validate calls.
+    pub fn register_typed<F>(
+        &mut self,
+        name: impl Into<String>,
+        overloads: impl IntoIterator<Item = CelFunctionOverload>,
+        function: F,
+    ) -> &mut Self
+    where
+        F: Fn(&[CelValue]) -> Result<CelValue, CelError> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        let functions = Arc::make_mut(&mut self.functions);
+        functions.retain(|f| f.name != name);
+        functions.push(RegisteredFunction {
+            name,
+            function: Arc::new(function),
+            overloads: overloads.into_iter().collect(),
+        });
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.functions.iter().map(|f| f.name.as_str())
+    }
+
+    /// Declare the registered functions in `env`.
+    pub fn declare(&self, env: &mut CelTypeEnv) {
+        for f in self.functions.iter() {
+            if f.overloads.is_empty() {
+                env.add_variadic_function(f.name.clone());
+            }
+            for overload in f.overloads.iter() {
+                env.add_function(f.name.clone(), overload.clone());
+            }
+        }
+    }
+
+    pub(crate) fn install(&self, ctx: &mut Context<'static>) {
+        for f in self.functions.iter() {
+            let name = f.name.clone();
+            let function = Arc::clone(&f.function);
+            ctx.add_function(&f.name, move |Arguments(args): Arguments| {
+                call(&name, &function, &args)
+            });
+        }
+    }
+}
+
+fn call(name: &str, function: &CelFunction, args: &[Value]) -> Result<Value, ExecutionError> {
+    let args = args
+        .iter()
+        .cloned()
+        .map(CelValue::from_cel_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ExecutionError::function_error(name, e))?;
+    function(&args)
+        .map(CelValue::into_cel_value)
+        .map_err(|e| ExecutionError::function_error(name, e))
+}
+
+impl std::fmt::Debug for CelFunctionRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{CelContext, CelExpression, CelType};
+
+    fn registry() -> CelFunctionRegistry {
+        let mut registry = CelFunctionRegistry::new();
+        registry
+            .register_typed(
+                "tax.Rate",
+                [CelFunctionOverload::new(
+                    [CelType::String],
+                    CelType::Decimal,
+                )],
+                |args| match args {
+                    [CelValue::String(country)] if country.as_str() == "SV" => {
+                        Ok(CelValue::Decimal(Decimal::new(13, 2)))
+                    }
+                    [CelValue::String(country)] => {
+                        Err(CelError::Unexpected(format!("no tax rate for '{country}'")))
+                    }
+                    _ => Err(CelError::MissingArgument),
+                },
+            )
+            .register("isBusinessDay", |_| Ok(CelValue::Bool(true)));
+        registry
+    }
+
+    #[test]
+    fn calls_registered_functions() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        context.add_functions(&registry());
+
+        let expression: CelExpression = "decimal.Mul(decimal('100'), tax.Rate('SV'))".parse()?;
+        assert_eq!(
+            expression.evaluate(&context)?,
+            CelValue::Decimal(Decimal::new(1300, 2))
+        );
+
+        let expression: CelExpression = "isBusinessDay(date())".parse()?;
+        assert_eq!(expression.evaluate(&context)?, CelValue::Bool(true));
+
+        let expression: CelExpression = "tax.Rate('XX')".parse()?;
+        assert!(expression.evaluate(&context).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn declares_registered_functions() -> anyhow::Result<()> {
+        let mut env = CelTypeEnv::new();
+        registry().declare(&mut env);
+
+        let expression: CelExpression = "tax.Rate('SV')".parse()?;
+        assert_eq!(expression.check(&env), Ok(CelType::Decimal));
+
+        let expression: CelExpression = "tax.Rate(1)".parse()?;
+        assert!(expression.check(&env).is_err());
+
+        let expression: CelExpression = "isBusinessDay(date(), 'SV')".parse()?;
+        assert_eq!(expression.check(&env), Ok(CelType::Dyn));
+        Ok(())
+    }
+}
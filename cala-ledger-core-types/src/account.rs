@@ -1,3 +1,4 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use super::primitives::*;
@@ -15,6 +16,8 @@ pub struct AccountValues {
     pub description: Option<String>,
     pub metadata: Option<serde_json::Value>,
     pub config: AccountConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub balance_constraints: Vec<BalanceConstraint>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -23,3 +26,116 @@ pub struct AccountConfig {
     pub is_account_set: bool,
     pub eventually_consistent: bool,
 }
+
+/// A bound on an account's available balance, checked against the balance
+/// every posting leaves behind.
+///
+/// The amount is the balance in the direction of the account's normal
+/// balance type, as returned by `AccountBalance::available(layer)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct BalanceConstraint {
+    pub layer: Layer,
+    /// `None` applies the bound in every currency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<Decimal>,
+}
+
+impl BalanceConstraint {
+    /// The available balance on `layer` must stay at or above `min`.
+    pub fn floor(layer: Layer, min: Decimal) -> Self {
+        Self {
+            layer,
+            currency: None,
+            min: Some(min),
+            max: None,
+        }
+    }
+
+    /// The available balance on `layer` must stay at or below `max`.
+    pub fn ceiling(layer: Layer, max: Decimal) -> Self {
+        Self {
+            layer,
+            currency: None,
+            min: None,
+            max: Some(max),
+        }
+    }
+
+    /// Restrict the bound to balances in `currency`.
+    pub fn in_currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    pub fn applies_to(&self, currency: Currency) -> bool {
+        self.currency.is_none_or(|c| c == currency)
+    }
+
+    pub fn is_satisfied_by(&self, available: Decimal) -> bool {
+        self.min.is_none_or(|min| available >= min) && self.max.is_none_or(|max| available <= max)
+    }
+
+    /// How far `available` lies outside the bound; zero when satisfied.
+    pub fn excess(&self, available: Decimal) -> Decimal {
+        let below = self.min.map_or(Decimal::ZERO, |min| min - available);
+        let above = self.max.map_or(Decimal::ZERO, |max| available - max);
+        below.max(above).max(Decimal::ZERO)
+    }
+}
+
+/// The constraints of an account as cached on its row for the posting flow.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BalanceConstraints(pub Vec<BalanceConstraint>);
+
+impl From<&AccountValues> for BalanceConstraints {
+    fn from(values: &AccountValues) -> Self {
+        Self(values.balance_constraints.clone())
+    }
+}
+
+mod sqlx {
+    use sqlx::{
+        postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef},
+        Postgres,
+    };
+
+    use super::BalanceConstraints;
+
+    impl sqlx::Type<Postgres> for BalanceConstraints {
+        fn type_info() -> PgTypeInfo {
+            <serde_json::Value as sqlx::Type<Postgres>>::type_info()
+        }
+    }
+
+    impl<'q> sqlx::Encode<'q, Postgres> for BalanceConstraints {
+        fn encode_by_ref(
+            &self,
+            buf: &mut PgArgumentBuffer,
+        ) -> Result<sqlx::encode::IsNull, Box<dyn std::error::Error + Send + Sync + 'static>>
+        {
+            let json_value = serde_json::to_value(self)?;
+            <serde_json::Value as sqlx::Encode<Postgres>>::encode_by_ref(&json_value, buf)
+        }
+    }
+
+    impl<'r> sqlx::Decode<'r, Postgres> for BalanceConstraints {
+        fn decode(
+            value: PgValueRef<'r>,
+        ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+            let json_value = <serde_json::Value as sqlx::Decode<Postgres>>::decode(value)?;
+            Ok(serde_json::from_value(json_value)?)
+        }
+    }
+
+    impl PgHasArrayType for BalanceConstraints {
+        fn array_type_info() -> PgTypeInfo {
+            <serde_json::Value as sqlx::postgres::PgHasArrayType>::array_type_info()
+        }
+    }
+}
@@ -14,6 +14,8 @@ pub struct AccountSetValues {
     pub description: Option<String>,
     pub metadata: Option<serde_json::Value>,
     pub normal_balance_type: DebitOrCredit,
+    #[serde(default)]
+    pub balance_rollup: BalanceRollup,
 }
 
 #[derive(Clone, Debug)]
@@ -22,6 +24,16 @@ pub struct AccountSetMember {
     pub created_at: DateTime<Utc>,
 }
 
+/// A period during which `member_id` was a direct member of
+/// `account_set_id`. `left_at` is `None` while it still is.
+#[derive(Clone, Debug)]
+pub struct AccountSetMembershipPeriod {
+    pub account_set_id: AccountSetId,
+    pub member_id: AccountSetMemberId,
+    pub joined_at: DateTime<Utc>,
+    pub left_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct AccountSetMemberByExternalId {
     pub id: AccountSetMemberId,
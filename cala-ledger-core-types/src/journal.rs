@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 use super::primitives::*;
@@ -12,6 +13,9 @@ pub struct JournalValues {
     pub status: Status,
     pub description: Option<String>,
     pub config: JournalConfig,
+    /// Postings effective on or before this date are rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_through: Option<NaiveDate>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
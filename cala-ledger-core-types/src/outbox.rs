@@ -1,7 +1,9 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    account::*, account_set::*, entry::*, journal::*, primitives::*, transaction::*, tx_template::*,
+    account::*, account_set::*, balance::*, entry::*, journal::*, primitives::*, transaction::*,
+    tx_template::*,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +33,15 @@ pub enum OutboxEventPayload {
         account_set_id: AccountSetId,
         member_id: AccountSetMemberId,
     },
+    AccountSetMemberMoved {
+        journal_id: JournalId,
+        member_id: AccountId,
+        from_account_set_id: AccountSetId,
+        to_account_set_id: AccountSetId,
+        effective: NaiveDate,
+        /// The ancestor balances the move wrote.
+        balances: Vec<BalanceSnapshot>,
+    },
     JournalCreated {
         journal: JournalValues,
     },
@@ -38,6 +49,15 @@ pub enum OutboxEventPayload {
         journal: JournalValues,
         fields: Vec<String>,
     },
+    JournalPeriodClosed {
+        journal: JournalValues,
+        closed_through: NaiveDate,
+    },
+    JournalPeriodReopened {
+        journal: JournalValues,
+        previously_closed_through: NaiveDate,
+        reason: String,
+    },
     TxTemplateCreated {
         tx_template: TxTemplateValues,
     },
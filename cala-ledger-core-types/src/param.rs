@@ -24,6 +24,22 @@ pub enum ParamDataType {
 }
 
 impl ParamDataType {
+    /// The static type of the value a param of this type is bound to, for
+    /// type-checking expressions that read it. Integers may be bound as
+    /// either signed or unsigned, so they are `Dyn`.
+    pub fn cel_type(&self) -> CelType {
+        match self {
+            ParamDataType::String => CelType::String,
+            ParamDataType::Integer => CelType::Dyn,
+            ParamDataType::Decimal => CelType::Decimal,
+            ParamDataType::Boolean => CelType::Bool,
+            ParamDataType::Uuid => CelType::Uuid,
+            ParamDataType::Date => CelType::Date,
+            ParamDataType::Timestamp => CelType::Timestamp,
+            ParamDataType::Json => CelType::Map,
+        }
+    }
+
     pub fn coerce_value(&self, value: CelValue) -> Result<CelValue, String> {
         use cel_interpreter::CelType::*;
         match CelType::from(&value) {
@@ -101,13 +101,16 @@ impl From<DebitOrCredit> for CelValue {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum BalanceRollup {
     /// Rolled up inside every posting to a member account, under an
     /// exclusive lock per (journal, set, currency).
+    #[default]
     Synchronous,
-    /// Skipped at posting time; refreshed by recalculating the sets
-    /// returned from `list_eventually_consistent_ids`.
+    /// Skipped at posting time; maintained by the streaming rollup as it
+    /// applies each committed transaction.
     EventuallyConsistent,
 }
 
@@ -0,0 +1,11 @@
+-- Exchange rates read by `DbFxRates`. A rate applies from its `effective`
+-- date until the next one for the pair; the inverse pair is derived when
+-- only one direction is recorded.
+CREATE TABLE cala_fx_rates (
+  from_currency VARCHAR NOT NULL,
+  to_currency VARCHAR NOT NULL,
+  effective DATE NOT NULL,
+  rate NUMERIC NOT NULL CHECK (rate > 0),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (from_currency, to_currency, effective)
+);
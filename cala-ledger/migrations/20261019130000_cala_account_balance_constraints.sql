@@ -0,0 +1,3 @@
+-- Cached for enforcement in the posting flow, like velocity_context_values.
+ALTER TABLE cala_accounts
+  ADD COLUMN balance_constraints JSONB NOT NULL DEFAULT '[]';
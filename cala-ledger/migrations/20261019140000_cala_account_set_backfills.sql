@@ -0,0 +1,21 @@
+-- Members added to eventually-consistent sets together with their balance
+-- history (`add_member_with_backfill`). The id is the backfill job's id;
+-- `completed_at` is set once the job has replayed every entry.
+CREATE TABLE cala_account_set_backfills (
+  id UUID PRIMARY KEY,
+  journal_id UUID NOT NULL REFERENCES cala_journals(id),
+  account_set_id UUID NOT NULL REFERENCES cala_account_sets(id),
+  member_account_id UUID NOT NULL REFERENCES cala_accounts(id),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  completed_at TIMESTAMPTZ
+);
+CREATE INDEX idx_cala_account_set_backfills_pending
+  ON cala_account_set_backfills (account_set_id) WHERE completed_at IS NULL;
+
+-- The entries a backfill has left to replay, captured when the member
+-- joined. Rows are deleted as the job folds them in.
+CREATE TABLE cala_account_set_backfill_entries (
+  backfill_id UUID NOT NULL REFERENCES cala_account_set_backfills(id),
+  entry_id UUID NOT NULL REFERENCES cala_entries(id),
+  PRIMARY KEY (backfill_id, entry_id)
+);
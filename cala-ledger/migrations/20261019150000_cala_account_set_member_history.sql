@@ -0,0 +1,31 @@
+-- Every direct membership a set has had: the period from when the member
+-- joined to when it left, open while it is still a member. Written in the
+-- same statement as the edge insert or delete in
+-- cala_account_set_member_accounts / cala_account_set_member_account_sets,
+-- and never deleted, so membership can be read as of any past time.
+--
+-- No foreign keys: a row is only ever written alongside its edge, which
+-- carries them, and the edge-insert error mapping matches on the edge
+-- tables' constraint names.
+CREATE TABLE cala_account_set_member_history (
+  account_set_id UUID NOT NULL,
+  member_id UUID NOT NULL,
+  member_is_account_set BOOLEAN NOT NULL,
+  joined_at TIMESTAMPTZ NOT NULL,
+  left_at TIMESTAMPTZ
+);
+-- At most one open period per edge; also the lookup for closing it.
+CREATE UNIQUE INDEX idx_cala_account_set_member_history_open
+  ON cala_account_set_member_history (member_id, account_set_id) WHERE left_at IS NULL;
+CREATE INDEX idx_cala_account_set_member_history_set
+  ON cala_account_set_member_history (account_set_id, joined_at);
+CREATE INDEX idx_cala_account_set_member_history_member
+  ON cala_account_set_member_history (member_id, joined_at);
+
+INSERT INTO cala_account_set_member_history
+  (account_set_id, member_id, member_is_account_set, joined_at)
+SELECT account_set_id, member_account_id, FALSE, created_at
+FROM cala_account_set_member_accounts
+UNION ALL
+SELECT account_set_id, member_account_set_id, TRUE, created_at
+FROM cala_account_set_member_account_sets;
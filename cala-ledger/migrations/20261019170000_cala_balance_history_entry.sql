@@ -0,0 +1,5 @@
+-- The balance versions an entry produced, read by the balance subscription
+-- to find every balance an entry stepped, including those of sets the
+-- account has since left.
+CREATE INDEX idx_cala_balance_history_latest_entry_id
+  ON cala_balance_history (latest_entry_id);
@@ -49,6 +49,10 @@ impl Account {
         VelocityContextAccountValues::from(self.values())
     }
 
+    pub(super) fn balance_constraints(&self) -> BalanceConstraints {
+        BalanceConstraints::from(self.values())
+    }
+
     pub fn update_status(&mut self, status: Status) -> es_entity::Idempotent<()> {
         let mut update = AccountUpdate::default();
         update.status(status);
@@ -64,6 +68,7 @@ impl Account {
             description,
             status,
             metadata,
+            balance_constraints,
         } = builder
             .into()
             .build()
@@ -114,6 +119,13 @@ impl Account {
             }
         }
 
+        if let Some(balance_constraints) = balance_constraints {
+            if balance_constraints != self.values().balance_constraints {
+                self.values.balance_constraints = balance_constraints;
+                updated_fields.push("balance_constraints".to_string());
+            }
+        }
+
         if updated_fields.is_empty() {
             return es_entity::Idempotent::AlreadyApplied;
         }
@@ -126,6 +138,24 @@ impl Account {
         es_entity::Idempotent::Executed(())
     }
 
+    /// Switch whether the account's balances are maintained by the
+    /// streaming rollup rather than inline. Only account sets change this,
+    /// through `AccountSets::update_balance_rollup`.
+    pub(crate) fn update_eventually_consistent(
+        &mut self,
+        eventually_consistent: bool,
+    ) -> es_entity::Idempotent<()> {
+        if self.values.config.eventually_consistent == eventually_consistent {
+            return es_entity::Idempotent::AlreadyApplied;
+        }
+        self.values.config.eventually_consistent = eventually_consistent;
+        self.events.push(AccountEvent::Updated {
+            values: self.values.clone(),
+            fields: vec!["eventually_consistent".to_string()],
+        });
+        es_entity::Idempotent::Executed(())
+    }
+
     pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
         self.events
             .entity_first_persisted_at()
@@ -180,6 +210,9 @@ pub struct AccountUpdateValues {
     pub status: Option<Status>,
     #[builder(setter(custom))]
     pub metadata: Option<serde_json::Value>,
+    /// Replaces the account's constraints; an empty list removes them.
+    #[builder(setter(strip_option, into))]
+    pub balance_constraints: Option<Vec<BalanceConstraint>>,
 }
 
 impl AccountUpdate {
@@ -217,6 +250,11 @@ pub struct NewAccount {
     description: Option<String>,
     #[builder(setter(custom), default)]
     metadata: Option<serde_json::Value>,
+    /// Bounds the account's balances must stay within, enforced on every
+    /// posting that moves them. Only balances maintained inline are checked:
+    /// an eventually-consistent account's balance is not known at post time.
+    #[builder(setter(into), default)]
+    balance_constraints: Vec<BalanceConstraint>,
     /// The account set this account joins **in the same atomic operation
     /// that creates it** — the lock-free create-inside-set fast path
     /// honored natively by `Accounts::create_in_op` /
@@ -264,11 +302,14 @@ pub struct NewAccount {
     ///    by retaining the **class-2 per-member EXCLUSIVE**
     ///    (`MEMBER_LOCK_CLASS = 2`, `crate::account_set_member`) in the
     ///    fast path: the classic attacher blocks on it and, after our
-    ///    commit, its probe sees membership {S}. This is the ONLY
-    ///    advisory lock the fast path takes; it is uncontended in sane
-    ///    usage. Lock-ordering safety: the fast path never waits on the
-    ///    coarse lock while holding class-2 (it never touches the coarse
-    ///    lock at all), so the doctrine in the `ADDVISORY_LOCK_ID` doc
+    ///    commit, its probe sees membership {S}. Without a configured
+    ///    ancestor or member cap this is the ONLY advisory lock the fast
+    ///    path takes; it is uncontended in sane usage. With a cap, the
+    ///    coarse SHARED lock (and, for the member cap, the class-2 lock on
+    ///    S) is taken first so the cap is counted under the classic
+    ///    path's locks. Lock-ordering safety: the fast path never waits on the
+    ///    coarse lock while holding class-2 (it takes the coarse lock, when
+    ///    at all, before any class-2 lock), so the doctrine in the `ADDVISORY_LOCK_ID` doc
     ///    comment (`account_set/repo.rs`) is not violated. **Composition
     ///    rule:** within one op, sequence classic membership mutations
     ///    BEFORE fast-path creates, or don't mix the two — a fast-path
@@ -324,12 +365,23 @@ impl NewAccount {
         NewAccountBuilder::default()
     }
 
+    /// Join `account_set_id` on creation, as the `initial_account_set`
+    /// builder setter would.
+    pub(crate) fn with_initial_account_set(mut self, account_set_id: AccountSetId) -> Self {
+        self.initial_account_set = Some(account_set_id);
+        self
+    }
+
     pub(super) fn context_values(&self) -> VelocityContextAccountValues {
         self.velocity_context_values
             .clone()
             .unwrap_or_else(|| VelocityContextAccountValues::from(self.clone().into_values()))
     }
 
+    pub(super) fn balance_constraints(&self) -> BalanceConstraints {
+        BalanceConstraints(self.balance_constraints.clone())
+    }
+
     pub(super) fn into_values(self) -> AccountValues {
         AccountValues {
             id: self.id,
@@ -345,6 +397,7 @@ impl NewAccount {
                 is_account_set: self.is_account_set,
                 eventually_consistent: self.eventually_consistent,
             },
+            balance_constraints: self.balance_constraints,
         }
     }
 }
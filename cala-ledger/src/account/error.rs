@@ -31,6 +31,24 @@ pub enum AccountError {
     CannotUpdateAccountSetAccounts,
     #[error("AccountError - initial account set '{0}' not found")]
     InitialAccountSetNotFound(AccountSetId),
+    #[error(
+        "AccountError - Account '{account_id}' would belong to {ancestors} account sets, \
+         exceeding the maximum of {max}"
+    )]
+    TooManyAncestors {
+        account_id: AccountId,
+        ancestors: usize,
+        max: usize,
+    },
+    #[error(
+        "AccountError - Account set '{account_set_id}' would have {members} direct members, \
+         exceeding the maximum of {max}"
+    )]
+    TooManyMembers {
+        account_set_id: AccountSetId,
+        members: usize,
+        max: usize,
+    },
 }
 
 impl From<AccountFindError> for AccountError {
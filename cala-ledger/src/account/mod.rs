@@ -200,6 +200,9 @@ impl Accounts {
     /// This takes NEITHER the coarse membership-graph lock nor the
     /// class-1 balance-history guard lock — only the class-2 per-member
     /// EXCLUSIVE — and runs no balance-history or path-uniqueness check.
+    /// The exception is the configured ancestor and member caps: with
+    /// either set, the coarse lock is taken SHARED and the caps are
+    /// checked, as on the classic path.
     /// The invariant argument for why that is sound (and its accepted
     /// caveat) lives on the `NewAccount::initial_account_set` field
     /// docs; the restriction that makes it hold is enforced at the type
@@ -227,10 +230,53 @@ impl Accounts {
                         *missing.first().expect("missing ids are never empty"),
                     )
                 }
+                AccountSetMemberError::TooManyAncestors {
+                    account_id,
+                    ancestors,
+                    max,
+                } => AccountError::TooManyAncestors {
+                    account_id,
+                    ancestors,
+                    max,
+                },
+                AccountSetMemberError::TooManyMembers {
+                    account_set_id,
+                    members,
+                    max,
+                } => AccountError::TooManyMembers {
+                    account_set_id,
+                    members,
+                    max,
+                },
                 AccountSetMemberError::Sqlx(e) => AccountError::Sqlx(e),
             })
     }
 
+    /// Flip the `eventually_consistent` flag of an account set's own
+    /// account, which [`Self::persist_in_op`] refuses to write. The caller
+    /// fences postings and the streaming rollup — see
+    /// `AccountSets::update_balance_rollup_in_op`.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.accounts.update_eventually_consistent_in_op",
+        skip(self, db)
+    )]
+    pub(crate) async fn update_eventually_consistent_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        id: AccountId,
+        eventually_consistent: bool,
+    ) -> Result<(), AccountError> {
+        let mut account = self.repo.find_by_id_in_op(&mut *db, id).await?;
+        if account
+            .update_eventually_consistent(eventually_consistent)
+            .did_execute()
+        {
+            self.repo.update_in_op(db, &mut account).await?;
+        }
+        Ok(())
+    }
+
     #[instrument(
         level = "debug",
         name = "cala_ledger.accounts.update_velocity_context_values_in_op",
@@ -31,13 +31,22 @@ use super::{entity::*, error::AccountError};
             update(accessor = "values().normal_balance_type")
         ),
         status(ty = "Status", update(accessor = "values().status")),
-        eventually_consistent(ty = "bool", update(persist = false)),
+        eventually_consistent(
+            ty = "bool",
+            update(accessor = "values().config.eventually_consistent")
+        ),
         is_account_set(ty = "bool", update(persist = false)),
         velocity_context_values(
             ty = "VelocityContextAccountValues",
             create(accessor = "context_values()"),
             update(accessor = "context_values()")
         ),
+        balance_constraints(
+            ty = "BalanceConstraints",
+            create(accessor = "balance_constraints()"),
+            update(accessor = "balance_constraints()"),
+            find_by = false
+        ),
     ),
     tbl_prefix = "cala",
     post_persist_hook = "publish",
@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use job::{CurrentJob, Job, JobCompletion, JobInitializer, JobRunner, JobSpawner, JobType};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::{
+    balance::{Balances, EcRollupTxn},
+    entry::Entries,
+    primitives::TransactionId,
+};
+
+use super::repo::AccountSetBackfillRepo;
+
+pub(super) const ACCOUNT_SET_BACKFILL_JOB: JobType = JobType::new("cala.account_set_backfill");
+
+/// Entries replayed per run. Each run commits its chunk together with the
+/// job's reschedule, so work is bounded per transaction and a crashed run
+/// resumes from the last committed chunk.
+const ENTRIES_PER_CHUNK: i64 = 1_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct AccountSetBackfillConfig {}
+
+pub(super) struct AccountSetBackfillInit {
+    pub repo: AccountSetBackfillRepo,
+    pub balances: Balances,
+    pub entries: Entries,
+}
+
+impl JobInitializer for AccountSetBackfillInit {
+    type Config = AccountSetBackfillConfig;
+
+    fn job_type(&self) -> JobType {
+        ACCOUNT_SET_BACKFILL_JOB
+    }
+
+    fn init(
+        &self,
+        _: &Job,
+        _: JobSpawner<Self::Config>,
+    ) -> Result<Box<dyn JobRunner>, Box<dyn std::error::Error>> {
+        Ok(Box::new(AccountSetBackfillRunner {
+            repo: self.repo.clone(),
+            balances: self.balances.clone(),
+            entries: self.entries.clone(),
+        }))
+    }
+}
+
+struct AccountSetBackfillRunner {
+    repo: AccountSetBackfillRepo,
+    balances: Balances,
+    entries: Entries,
+}
+
+#[async_trait]
+impl JobRunner for AccountSetBackfillRunner {
+    #[tracing::instrument(
+        name = "cala_ledger.account_set_backfill.run",
+        skip_all,
+        fields(backfill_id = %current_job.id(), entries_count),
+        err(level = "warn")
+    )]
+    async fn run(
+        &self,
+        current_job: CurrentJob,
+    ) -> Result<JobCompletion, Box<dyn std::error::Error>> {
+        let id = *current_job.id();
+        let mut op = current_job.begin_op().await?;
+        let backfill = self.repo.find_in_op(&mut op, id).await?;
+        let chunk = self
+            .repo
+            .take_chunk_in_op(&mut op, id, ENTRIES_PER_CHUNK)
+            .await?;
+        tracing::Span::current().record("entries_count", chunk.len());
+        if chunk.is_empty() {
+            self.repo.complete_in_op(&mut op, id).await?;
+            return Ok(JobCompletion::CompleteWithOp(op));
+        }
+
+        let entry_ids: Vec<_> = chunk.iter().map(|e| e.entry_id).collect();
+        let mut entries = self.entries.find_all_in_op(&mut op, &entry_ids).await?;
+        let mut txns: Vec<EcRollupTxn> = Vec::new();
+        let mut tx_ids: HashMap<TransactionId, usize> = HashMap::new();
+        for backfilled in chunk {
+            let Some(entry) = entries.remove(&backfilled.entry_id) else {
+                continue;
+            };
+            let idx = *tx_ids.entry(backfilled.transaction_id).or_insert_with(|| {
+                txns.push(EcRollupTxn {
+                    journal_id: backfill.journal_id,
+                    effective: backfilled.effective,
+                    created_at: backfilled.created_at,
+                    entries: Vec::new(),
+                });
+                txns.len() - 1
+            });
+            txns[idx].entries.push(entry.into_values());
+        }
+        for tx in txns.iter_mut() {
+            tx.entries.sort_by_key(|e| e.sequence);
+        }
+
+        let target_set_ids = self
+            .repo
+            .find_target_sets_in_op(&mut op, backfill.account_set_id)
+            .await?;
+        self.balances
+            .apply_ec_backfill_in_op(
+                &mut op,
+                backfill.journal_id,
+                backfill.member_id,
+                &target_set_ids,
+                txns,
+            )
+            .await?;
+
+        Ok(JobCompletion::RescheduleNowWithOp(op))
+    }
+}
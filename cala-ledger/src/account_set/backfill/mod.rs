@@ -0,0 +1,79 @@
+//! Replay of a member's earlier entries into eventually-consistent sets it
+//! joins with [`AccountSets::add_member_with_backfill`](super::AccountSets::add_member_with_backfill).
+//!
+//! Joining records the membership and a work list of the member's entries
+//! in one transaction; from then on the streaming rollup folds the
+//! member's new entries into the set, and a job works through the list in
+//! bounded chunks to fold in the old ones. Until the list is empty the set
+//! (and every set above it) reports as backfilling.
+
+mod job;
+mod repo;
+
+use ::job::{JobId, JobSpawner, Jobs};
+use sqlx::PgPool;
+
+use crate::{balance::Balances, entry::Entries, primitives::*};
+
+pub(crate) use self::job::AccountSetBackfillConfig;
+use self::job::AccountSetBackfillInit;
+use super::error::AccountSetError;
+pub(crate) use repo::AccountSetBackfillRepo;
+
+/// Register the account-set backfill job.
+///
+/// Must be called before [`Jobs::start_poll`].
+pub(crate) fn register_account_set_backfill(
+    jobs: &mut Jobs,
+    pool: &PgPool,
+    balances: &Balances,
+    entries: &Entries,
+) -> AccountSetBackfills {
+    let repo = AccountSetBackfillRepo::new(pool);
+    let spawner = jobs.add_initializer(AccountSetBackfillInit {
+        repo: repo.clone(),
+        balances: balances.clone(),
+        entries: entries.clone(),
+    });
+    AccountSetBackfills { repo, spawner }
+}
+
+#[derive(Clone)]
+pub(crate) struct AccountSetBackfills {
+    repo: AccountSetBackfillRepo,
+    spawner: JobSpawner<AccountSetBackfillConfig>,
+}
+
+impl AccountSetBackfills {
+    pub fn repo(&self) -> &AccountSetBackfillRepo {
+        &self.repo
+    }
+
+    /// Record the backfill of `member_id` into `account_set_id` and spawn
+    /// its job, both as part of `op`.
+    pub async fn spawn_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_set_id: AccountSetId,
+        member_id: AccountId,
+    ) -> Result<(), AccountSetError> {
+        let id = JobId::new();
+        let entries = self
+            .repo
+            .create_in_op(op, id, journal_id, account_set_id, member_id)
+            .await?;
+        tracing::debug!(%account_set_id, %member_id, entries, "spawning account set backfill");
+        self.spawner
+            .spawn_in_op(op, id, AccountSetBackfillConfig {})
+            .await?;
+        Ok(())
+    }
+
+    pub async fn is_backfilling(
+        &self,
+        account_set_id: AccountSetId,
+    ) -> Result<bool, AccountSetError> {
+        self.repo.is_backfilling(account_set_id).await
+    }
+}
@@ -0,0 +1,253 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use job::JobId;
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use cala_types::primitives::*;
+
+use crate::account_set::error::AccountSetError;
+
+pub(super) struct Backfill {
+    pub journal_id: JournalId,
+    pub account_set_id: AccountSetId,
+    pub member_id: AccountId,
+}
+
+/// A backfilled entry with the transaction fields the rollup fold needs.
+pub(super) struct BackfillEntry {
+    pub entry_id: EntryId,
+    pub transaction_id: TransactionId,
+    pub effective: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct AccountSetBackfillRepo {
+    pool: PgPool,
+}
+
+impl AccountSetBackfillRepo {
+    pub(super) fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Record a backfill together with its work list: every entry the
+    /// member has in the journal at this point. Returns the number of
+    /// entries to replay.
+    #[instrument(
+        name = "account_set_backfill.create_in_op",
+        skip(self, op),
+        err(level = "warn")
+    )]
+    pub(super) async fn create_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        id: JobId,
+        journal_id: JournalId,
+        account_set_id: AccountSetId,
+        member_id: AccountId,
+    ) -> Result<u64, AccountSetError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_account_set_backfills (id, journal_id, account_set_id, member_account_id)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            journal_id as JournalId,
+            account_set_id as AccountSetId,
+            member_id as AccountId,
+        )
+        .execute(op.as_executor())
+        .await?;
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO cala_account_set_backfill_entries (backfill_id, entry_id)
+            SELECT $1, e.id
+            FROM cala_entries e
+            WHERE e.journal_id = $2 AND e.account_id = $3
+            "#,
+            Uuid::from(id),
+            journal_id as JournalId,
+            member_id as AccountId,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub(super) async fn find_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        id: JobId,
+    ) -> Result<Backfill, AccountSetError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                journal_id AS "journal_id: JournalId",
+                account_set_id AS "account_set_id: AccountSetId",
+                member_account_id AS "member_id: AccountId"
+            FROM cala_account_set_backfills
+            WHERE id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .fetch_one(op.as_executor())
+        .await?;
+        Ok(Backfill {
+            journal_id: row.journal_id,
+            account_set_id: row.account_set_id,
+            member_id: row.member_id,
+        })
+    }
+
+    /// Take the next `limit` entries off the work list, oldest first. The
+    /// removal commits with the chunk's balance writes, so a crashed run
+    /// leaves the list as it was.
+    #[instrument(
+        name = "account_set_backfill.take_chunk_in_op",
+        skip(self, op),
+        err(level = "warn")
+    )]
+    pub(super) async fn take_chunk_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        id: JobId,
+        limit: i64,
+    ) -> Result<Vec<BackfillEntry>, AccountSetError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH chunk AS (
+                SELECT b.entry_id
+                FROM cala_account_set_backfill_entries b
+                JOIN cala_entries e ON e.id = b.entry_id
+                WHERE b.backfill_id = $1
+                ORDER BY e.created_at, e.id
+                LIMIT $2
+            ),
+            taken AS (
+                DELETE FROM cala_account_set_backfill_entries b
+                USING chunk c
+                WHERE b.backfill_id = $1 AND b.entry_id = c.entry_id
+                RETURNING b.entry_id
+            )
+            SELECT
+                e.id AS "entry_id!: EntryId",
+                t.id AS "transaction_id!: TransactionId",
+                t.effective AS "effective!",
+                t.created_at AS "created_at!"
+            FROM taken
+            JOIN cala_entries e ON e.id = taken.entry_id
+            JOIN cala_transactions t ON t.id = e.transaction_id
+            ORDER BY t.created_at, t.id
+            "#,
+            Uuid::from(id),
+            limit,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| BackfillEntry {
+                entry_id: row.entry_id,
+                transaction_id: row.transaction_id,
+                effective: row.effective,
+                created_at: row.created_at,
+            })
+            .collect())
+    }
+
+    /// The set a backfill writes and its eventually-consistent ancestors,
+    /// read per chunk so sets joining above it while it runs are covered.
+    pub(super) async fn find_target_sets_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+    ) -> Result<Vec<AccountSetId>, AccountSetError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT $1::uuid AS account_set_id
+                UNION
+                SELECT e.account_set_id
+                FROM ancestors a
+                JOIN cala_account_set_member_account_sets e
+                  ON e.member_account_set_id = a.account_set_id
+            )
+            SELECT a.account_set_id AS "account_set_id!: AccountSetId"
+            FROM ancestors a
+            JOIN cala_accounts acc
+              ON acc.id = a.account_set_id AND acc.eventually_consistent = TRUE
+            ORDER BY a.account_set_id
+            "#,
+            account_set_id as AccountSetId,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows.into_iter().map(|row| row.account_set_id).collect())
+    }
+
+    pub(super) async fn complete_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        id: JobId,
+    ) -> Result<(), AccountSetError> {
+        sqlx::query!(
+            r#"
+            UPDATE cala_account_set_backfills
+            SET completed_at = NOW()
+            WHERE id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    /// Whether a backfill into `account_set_id`, or into a set below it,
+    /// has entries left to replay.
+    pub(super) async fn is_backfilling(
+        &self,
+        account_set_id: AccountSetId,
+    ) -> Result<bool, AccountSetError> {
+        let row = sqlx::query!(
+            r#"
+            WITH RECURSIVE descendants AS (
+                SELECT $1::uuid AS account_set_id
+                UNION
+                SELECT e.member_account_set_id
+                FROM descendants d
+                JOIN cala_account_set_member_account_sets e
+                  ON e.account_set_id = d.account_set_id
+            )
+            SELECT EXISTS (
+                SELECT 1
+                FROM cala_account_set_backfills b
+                JOIN descendants d ON d.account_set_id = b.account_set_id
+                WHERE b.completed_at IS NULL
+            ) AS "backfilling!"
+            "#,
+            account_set_id as AccountSetId,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.backfilling)
+    }
+
+    /// Backfills not yet complete, and the entries they have left to
+    /// replay.
+    pub(crate) async fn count_pending(&self) -> Result<(u64, u64), sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM cala_account_set_backfills
+                 WHERE completed_at IS NULL) AS "backfills!",
+                (SELECT COUNT(*) FROM cala_account_set_backfill_entries) AS "entries!"
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok((row.backfills as u64, row.entries as u64))
+    }
+}
@@ -94,6 +94,34 @@ impl AccountSet {
         es_entity::Idempotent::Executed(())
     }
 
+    /// Take the policy from the set's account row on load. Events written
+    /// before the policy could change don't carry it; the row always does.
+    pub(super) fn fill_balance_rollup(&mut self, eventually_consistent: bool) {
+        self.values.balance_rollup = if eventually_consistent {
+            BalanceRollup::EventuallyConsistent
+        } else {
+            BalanceRollup::Synchronous
+        };
+    }
+
+    /// Record a change of the set's [`BalanceRollup`] policy. Applied by
+    /// [`AccountSets::update_balance_rollup`](super::AccountSets::update_balance_rollup),
+    /// which also switches how the set's balances are maintained.
+    pub(super) fn update_balance_rollup(
+        &mut self,
+        balance_rollup: BalanceRollup,
+    ) -> es_entity::Idempotent<()> {
+        if self.values.balance_rollup == balance_rollup {
+            return es_entity::Idempotent::AlreadyApplied;
+        }
+        self.values.balance_rollup = balance_rollup;
+        self.events.push(AccountSetEvent::Updated {
+            values: self.values.clone(),
+            fields: vec!["balance_rollup".to_string()],
+        });
+        es_entity::Idempotent::Executed(())
+    }
+
     pub fn into_values(self) -> AccountSetValues {
         self.values
     }
@@ -168,6 +196,9 @@ impl From<(AccountSetValues, Vec<String>)> for AccountSetUpdate {
                             .expect("Failed to serialize metadata");
                     }
                 }
+                // Not an `AccountSetUpdate` field: the policy only changes
+                // through `AccountSets::update_balance_rollup`.
+                "balance_rollup" => {}
                 _ => unreachable!("Unknown field: {}", field),
             }
         }
@@ -248,6 +279,7 @@ impl IntoEvents<AccountSetEvent> for NewAccountSet {
                     normal_balance_type: self.normal_balance_type,
                     description: self.description,
                     metadata: self.metadata,
+                    balance_rollup: self.balance_rollup,
                 },
             }],
         )
@@ -43,6 +43,28 @@ pub enum AccountSetError {
         account_set_id: AccountSetId,
         member_id: AccountId,
     },
+    #[error(
+        "AccountSetError - Account '{member_id}' is not a direct member of account set \
+         '{account_set_id}'"
+    )]
+    NotAMember {
+        account_set_id: AccountSetId,
+        member_id: AccountId,
+    },
+    #[error(
+        "AccountSetError - Cannot backfill into account set '{0}': it is not eventually \
+         consistent, and only eventually-consistent sets can take members with history"
+    )]
+    BackfillRequiresEventuallyConsistentSet(AccountSetId),
+    #[error(
+        "AccountSetError - Cannot make account set '{0}' synchronous: a backfill into it, \
+         or into a set below it, is still replaying entries"
+    )]
+    BackfillInProgress(AccountSetId),
+    #[error("AccountSetError - Job: {0}")]
+    Job(#[from] job::JobError),
+    #[error("AccountSetError - EcRollupCheckpoint: {0}")]
+    EcRollupCheckpoint(#[from] obix::out::HandlerCheckpointError),
     #[error(
         "AccountSetError - Cannot add account set '{member_account_set_id}' as a member of \
          account set '{account_set_id}': the member is already an ancestor of the set, \
@@ -63,6 +85,24 @@ pub enum AccountSetError {
         depth: i32,
         max: i32,
     },
+    #[error(
+        "AccountSetError - Account '{account_id}' would belong to {ancestors} account sets, \
+         exceeding the maximum of {max}"
+    )]
+    TooManyAncestors {
+        account_id: AccountId,
+        ancestors: usize,
+        max: usize,
+    },
+    #[error(
+        "AccountSetError - Account set '{account_set_id}' would have {members} direct members, \
+         exceeding the maximum of {max}"
+    )]
+    TooManyMembers {
+        account_set_id: AccountSetId,
+        members: usize,
+        max: usize,
+    },
 }
 
 impl From<AccountSetFindError> for AccountSetError {
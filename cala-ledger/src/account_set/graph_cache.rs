@@ -36,7 +36,8 @@
 //!   in-process, validated by the `cala_account_set_graph_epoch`
 //!   counter read in the SAME statement/snapshot as the probe. Per-set
 //!   metadata (`journal_id`, `eventually_consistent`) is immutable
-//!   after creation, so only the edge set needs the epoch guard.
+//!   after creation except through `update_balance_rollup`, which bumps
+//!   the epoch too, so the epoch guards the metadata along with the edges.
 //!
 //! Equivalence with the single-statement walk: when the probed epoch
 //! matches the snapshot's, the cached graph provably equals the
@@ -82,10 +83,27 @@
 //! braces against any missed-bump path; correctness never depends on
 //! it. Memory: the whole graph is ~thousands of edges + meta — trivial,
 //! no eviction needed.
+//!
+//! Observability: every resolution and validation counts as a hit or as a
+//! fallback by reason, both in-process (`AccountSets::graph_cache_stats`)
+//! and as the `cala_ledger.set_graph_cache.lookups` OpenTelemetry counter,
+//! and each install records the snapshot's epoch and size as gauges. A
+//! sustained `fallback_epoch` rate means structure ops are outpacing
+//! refreshes; `invalidate` drops the snapshot (the next op falls back
+//! and refreshes) and `warm` reloads it in place.
 
+use chrono::{DateTime, Utc};
+use es_entity::clock::ClockHandle;
+use opentelemetry::{
+    metrics::{Counter, Gauge},
+    KeyValue,
+};
 use std::{
     collections::{HashMap, HashSet, VecDeque},
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
 };
 use tracing::instrument;
 
@@ -94,10 +112,11 @@ use crate::primitives::{AccountId, AccountSetId, JournalId};
 use super::{
     error::AccountSetError,
     graph_validation::{
-        has_duplicate_account_membership_paths, validate_set_memberships, AccountMembership,
-        SetMembership,
+        ancestor_counts, has_duplicate_account_membership_paths, validate_account_ancestors,
+        validate_graph_depth, validate_member_counts, validate_set_memberships, AccountMembership,
+        MembershipLimits, SetMembership,
     },
-    repo::{AccountSetRepo, DirectMembershipProbe, SetGraphNode},
+    repo::{AccountSetRepo, DirectMembershipProbe, SetGraphData, SetGraphNode},
 };
 
 /// Interval of the belt-and-braces timer refresh. Correctness never
@@ -111,6 +130,133 @@ const TIMER_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_se
 /// takes the op-local fallback (and triggers the installing refresh).
 const COLD_EPOCH: i64 = -1;
 
+/// Counters and snapshot shape of the set-graph cache, as returned by
+/// [`AccountSets::graph_cache_stats`](super::AccountSets::graph_cache_stats).
+/// Counters are per process and accumulate from startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphCacheStats {
+    /// Resolutions and validations answered from the cached graph,
+    /// including those supplemented with sets created since the last
+    /// refresh.
+    pub hits: u64,
+    pub fallbacks: GraphCacheFallbacks,
+    /// Epoch of the installed snapshot; `None` before the first refresh
+    /// and after [`AccountSets::invalidate_graph_cache`](super::AccountSets::invalidate_graph_cache).
+    pub epoch: Option<i64>,
+    /// Account sets in the installed snapshot.
+    pub nodes: usize,
+    /// Set-to-set membership edges in the installed snapshot.
+    pub edges: usize,
+    pub last_refreshed_at: Option<DateTime<Utc>>,
+}
+
+/// Fallbacks to the SQL walk, by the reason the cached graph could not
+/// answer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraphCacheFallbacks {
+    /// No snapshot installed yet (startup or after invalidation).
+    pub cold: u64,
+    /// A structure op committed since the last refresh.
+    pub epoch_mismatch: u64,
+    /// A set unknown to the snapshot surfaced while walking it.
+    pub unknown_set: u64,
+}
+
+/// How a cache lookup was answered. Recorded on the lookup's span as
+/// `path` and counted in [`CacheMetrics`].
+#[derive(Debug, Clone, Copy)]
+enum LookupPath {
+    Memory,
+    Supplement,
+    FallbackCold,
+    FallbackEpoch,
+    FallbackUnknown,
+}
+
+impl LookupPath {
+    fn fallback_for(snapshot: &GraphSnapshot) -> Self {
+        if snapshot.epoch == COLD_EPOCH {
+            Self::FallbackCold
+        } else {
+            Self::FallbackEpoch
+        }
+    }
+
+    fn hit(overlay: bool) -> Self {
+        if overlay {
+            Self::Supplement
+        } else {
+            Self::Memory
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Memory => "memory",
+            Self::Supplement => "supplement",
+            Self::FallbackCold => "fallback_cold",
+            Self::FallbackEpoch => "fallback_epoch",
+            Self::FallbackUnknown => "fallback_unknown",
+        }
+    }
+}
+
+struct CacheMetrics {
+    hits: AtomicU64,
+    fallbacks_cold: AtomicU64,
+    fallbacks_epoch: AtomicU64,
+    fallbacks_unknown: AtomicU64,
+    lookups: Counter<u64>,
+    epoch: Gauge<i64>,
+    nodes: Gauge<u64>,
+    edges: Gauge<u64>,
+}
+
+impl CacheMetrics {
+    fn new() -> Self {
+        let meter = opentelemetry::global::meter("cala_ledger");
+        Self {
+            hits: AtomicU64::new(0),
+            fallbacks_cold: AtomicU64::new(0),
+            fallbacks_epoch: AtomicU64::new(0),
+            fallbacks_unknown: AtomicU64::new(0),
+            lookups: meter
+                .u64_counter("cala_ledger.set_graph_cache.lookups")
+                .with_description("Set-graph cache lookups, by how they were answered")
+                .build(),
+            epoch: meter
+                .i64_gauge("cala_ledger.set_graph_cache.epoch")
+                .with_description("Graph epoch of the installed snapshot (-1 when cold)")
+                .build(),
+            nodes: meter
+                .u64_gauge("cala_ledger.set_graph_cache.nodes")
+                .with_description("Account sets in the installed snapshot")
+                .build(),
+            edges: meter
+                .u64_gauge("cala_ledger.set_graph_cache.edges")
+                .with_description("Set-to-set edges in the installed snapshot")
+                .build(),
+        }
+    }
+
+    fn record(&self, path: LookupPath) {
+        let counter = match path {
+            LookupPath::Memory | LookupPath::Supplement => &self.hits,
+            LookupPath::FallbackCold => &self.fallbacks_cold,
+            LookupPath::FallbackEpoch => &self.fallbacks_epoch,
+            LookupPath::FallbackUnknown => &self.fallbacks_unknown,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.lookups.add(1, &[KeyValue::new("path", path.as_str())]);
+    }
+
+    fn record_installed(&self, snapshot: &GraphSnapshot) {
+        self.epoch.record(snapshot.epoch, &[]);
+        self.nodes.record(snapshot.meta.len() as u64, &[]);
+        self.edges.record(snapshot.edge_count() as u64, &[]);
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct SetMeta {
     journal_id: JournalId,
@@ -118,18 +264,21 @@ struct SetMeta {
 }
 
 #[derive(Debug)]
-struct GraphSnapshot {
+pub(super) struct GraphSnapshot {
     epoch: i64,
     /// member set -> direct parent sets (upward edges). Sets with no
     /// parents have no entry.
-    parents: HashMap<AccountSetId, Vec<AccountSetId>>,
+    pub(super) parents: HashMap<AccountSetId, Vec<AccountSetId>>,
     /// parent set -> direct member sets (downward edges). Kept alongside
     /// `parents` so batch validation can find affected components without
     /// repeatedly inverting the snapshot.
-    children: HashMap<AccountSetId, Vec<AccountSetId>>,
+    pub(super) children: HashMap<AccountSetId, Vec<AccountSetId>>,
     /// Every set known at snapshot time. Also the known-set universe for
     /// expansion: an id absent here forces the supplement/fallback path.
     meta: HashMap<AccountSetId, SetMeta>,
+    /// When the snapshot was read from committed data; `None` for the cold
+    /// snapshot and op-local ones.
+    refreshed_at: Option<DateTime<Utc>>,
 }
 
 impl GraphSnapshot {
@@ -139,9 +288,14 @@ impl GraphSnapshot {
             parents: HashMap::new(),
             children: HashMap::new(),
             meta: HashMap::new(),
+            refreshed_at: None,
         }
     }
 
+    fn edge_count(&self) -> usize {
+        self.parents.values().map(Vec::len).sum()
+    }
+
     fn edges_connected_to(&self, members: &[SetMembership]) -> Vec<SetMembership> {
         let mut pending: Vec<_> = members
             .iter()
@@ -199,6 +353,9 @@ pub(super) struct SetGraphCache {
 
 struct SetGraphCacheInner {
     repo: AccountSetRepo,
+    limits: MembershipLimits,
+    /// The ledger clock, which stamps each snapshot's `refreshed_at`.
+    clock: ClockHandle,
     /// Immutable snapshot; readers take a brief uncontended read lock,
     /// clone the `Arc`, and release before any await. Refreshes build a
     /// fresh snapshot and swap it in whole.
@@ -208,6 +365,7 @@ struct SetGraphCacheInner {
     /// still-stale snapshot fails the next op's epoch check and triggers
     /// again.
     refresh_lock: tokio::sync::Mutex<()>,
+    metrics: CacheMetrics,
 }
 
 impl std::fmt::Debug for SetGraphCache {
@@ -217,11 +375,14 @@ impl std::fmt::Debug for SetGraphCache {
 }
 
 impl SetGraphCache {
-    pub(super) fn new(repo: AccountSetRepo) -> Self {
+    pub(super) fn new(repo: AccountSetRepo, limits: MembershipLimits, clock: &ClockHandle) -> Self {
         let inner = Arc::new(SetGraphCacheInner {
             repo,
+            limits,
+            clock: clock.clone(),
             snapshot: RwLock::new(Arc::new(GraphSnapshot::cold())),
             refresh_lock: tokio::sync::Mutex::new(()),
+            metrics: CacheMetrics::new(),
         });
         // Belt-and-braces timer refresh. Holds only a Weak handle so the
         // task exits (and cannot leak) once every cache clone is dropped.
@@ -296,14 +457,7 @@ impl SetGraphCache {
             // sees the op's own writes and takes the ancestor locks in
             // the same statement — and let a background refresh
             // (committed data only) update the shared snapshot.
-            span.record(
-                "path",
-                if snapshot.epoch == COLD_EPOCH {
-                    "fallback_cold"
-                } else {
-                    "fallback_epoch"
-                },
-            );
+            self.record_path(&span, LookupPath::fallback_for(&snapshot));
             self.spawn_refresh();
             return self
                 .inner
@@ -346,14 +500,7 @@ impl SetGraphCache {
             entry_pairs,
         ) {
             Some((mappings, lock_pairs)) => {
-                span.record(
-                    "path",
-                    if overlay.is_some() {
-                        "supplement"
-                    } else {
-                        "memory"
-                    },
-                );
+                self.record_path(&span, LookupPath::hit(overlay.is_some()));
                 self.inner
                     .repo
                     .lock_resolved_ancestors_in_op(op, journal_id, &lock_pairs)
@@ -367,7 +514,7 @@ impl SetGraphCache {
             // rather than reason about it. No locks were taken yet, so
             // the walk's in-statement batch stays the posting's only one.
             None => {
-                span.record("path", "fallback_unknown");
+                self.record_path(&span, LookupPath::FallbackUnknown);
                 self.inner
                     .repo
                     .walk_mappings_and_lock_in_op(&mut *op, journal_id, entry_pairs)
@@ -441,15 +588,17 @@ impl SetGraphCache {
             .await?;
 
         let snapshot = self.load();
+        if let Some(max) = self.inner.limits.max_ancestors_per_account {
+            let graph = self.snapshot_at_epoch_in_op(op, probe.epoch).await?;
+            validate_account_ancestors(
+                members,
+                &probe.seeds,
+                |set_id| graph.parents.get(set_id).map(Vec::as_slice).unwrap_or(&[]),
+                max,
+            )?;
+        }
         if snapshot.epoch != probe.epoch {
-            span.record(
-                "path",
-                if snapshot.epoch == COLD_EPOCH {
-                    "fallback_cold"
-                } else {
-                    "fallback_epoch"
-                },
-            );
+            self.record_path(&span, LookupPath::fallback_for(&snapshot));
             self.spawn_refresh();
             return self
                 .inner
@@ -509,14 +658,7 @@ impl SetGraphCache {
 
         match has_duplicate_account_membership_paths(members, &probe.seeds, parents_of) {
             Some(false) => {
-                span.record(
-                    "path",
-                    if overlay.is_some() {
-                        "supplement"
-                    } else {
-                        "memory"
-                    },
-                );
+                self.record_path(&span, LookupPath::hit(overlay.is_some()));
                 Ok(())
             }
             Some(true) => Err(AccountSetError::MemberAlreadyAdded),
@@ -525,7 +667,7 @@ impl SetGraphCache {
             // walk is always correct, so fall back rather than reason
             // about it (mirrors fetch_mappings_in_op).
             None => {
-                span.record("path", "fallback_unknown");
+                self.record_path(&span, LookupPath::FallbackUnknown);
                 self.inner
                     .repo
                     .assert_no_double_membership(op, members)
@@ -558,17 +700,10 @@ impl SetGraphCache {
         let snapshot = self.load();
         let epoch = self.inner.repo.fetch_set_graph_epoch_in_op(op).await?;
         let existing_edges = if snapshot.epoch == epoch {
-            span.record("path", "memory");
+            self.record_path(&span, LookupPath::Memory);
             snapshot.edges_connected_to(members)
         } else {
-            span.record(
-                "path",
-                if snapshot.epoch == COLD_EPOCH {
-                    "fallback_cold"
-                } else {
-                    "fallback_epoch"
-                },
-            );
+            self.record_path(&span, LookupPath::fallback_for(&snapshot));
             self.spawn_refresh();
             self.inner.repo.fetch_set_membership_edges_in_op(op).await?
         };
@@ -577,7 +712,162 @@ impl SetGraphCache {
             .repo
             .fetch_affected_account_memberships_in_op(op, &existing_edges, members)
             .await?;
-        validate_set_memberships(&existing_edges, members, &account_members)
+        validate_set_memberships(
+            &existing_edges,
+            members,
+            &account_members,
+            &self.inner.limits,
+        )?;
+        if let Some(max) = self.inner.limits.max_members_per_set {
+            let set_ids: Vec<AccountSetId> = members.iter().map(|m| m.account_set_id).collect();
+            let counts = self
+                .inner
+                .repo
+                .count_direct_members_in_op(op, &set_ids)
+                .await?;
+            validate_member_counts(&counts, set_ids, max)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn limits(&self) -> &MembershipLimits {
+        &self.inner.limits
+    }
+
+    /// Install a snapshot of the committed graph and check it against the
+    /// configured limits, before anything else runs against it. Runs on
+    /// startup and on demand; a graph already past the limits is reported
+    /// with the same error a change past them would get. Unlike the
+    /// background refresh it waits for one already in flight rather than
+    /// skipping.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.set_graph_cache.warm",
+        skip_all,
+        err(level = "warn")
+    )]
+    pub(super) async fn warm(&self) -> Result<(), AccountSetError> {
+        let _guard = self.inner.refresh_lock.lock().await;
+        let snapshot = Self::build_snapshot(&self.inner, self.inner.repo.fetch_set_graph().await?);
+        let limits = &self.inner.limits;
+        let edges: Vec<SetMembership> = snapshot
+            .parents
+            .iter()
+            .flat_map(|(member_id, parent_ids)| {
+                parent_ids
+                    .iter()
+                    .map(|parent_id| SetMembership::from((*parent_id, *member_id)))
+            })
+            .collect();
+        validate_graph_depth(&edges, limits.max_depth)?;
+        if let Some(max) = limits.max_members_per_set {
+            if let Some((account_set_id, members)) =
+                self.inner.repo.find_set_over_member_limit(max).await?
+            {
+                return Err(AccountSetError::TooManyMembers {
+                    account_set_id,
+                    members,
+                    max,
+                });
+            }
+        }
+        if let Some(max) = limits.max_ancestors_per_account {
+            let counts = ancestor_counts(snapshot.meta.keys().copied(), &snapshot.parents);
+            if let Some((account_id, ancestors)) = self
+                .inner
+                .repo
+                .find_account_over_ancestor_limit(&counts, max)
+                .await?
+            {
+                return Err(AccountSetError::TooManyAncestors {
+                    account_id,
+                    ancestors,
+                    max,
+                });
+            }
+        }
+        Self::install(&self.inner, snapshot);
+        Ok(())
+    }
+
+    /// The set-to-set edges as this op sees them, for the tree queries.
+    /// An epoch match hands out the shared snapshot; a cold or stale one
+    /// is replaced by an op-local snapshot built from one flat edge read
+    /// (never installed — its `meta` is empty).
+    pub(super) async fn snapshot_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+    ) -> Result<Arc<GraphSnapshot>, AccountSetError> {
+        let epoch = self.inner.repo.fetch_set_graph_epoch_in_op(op).await?;
+        self.snapshot_at_epoch_in_op(op, epoch).await
+    }
+
+    /// [`Self::snapshot_in_op`] for an `epoch` the caller already read in
+    /// `op`.
+    async fn snapshot_at_epoch_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        epoch: i64,
+    ) -> Result<Arc<GraphSnapshot>, AccountSetError> {
+        let snapshot = self.load();
+        if snapshot.epoch == epoch {
+            return Ok(snapshot);
+        }
+        self.spawn_refresh();
+        let mut parents: HashMap<AccountSetId, Vec<AccountSetId>> = HashMap::new();
+        let mut children: HashMap<AccountSetId, Vec<AccountSetId>> = HashMap::new();
+        for edge in self.inner.repo.fetch_set_membership_edges_in_op(op).await? {
+            parents
+                .entry(edge.member_account_set_id)
+                .or_default()
+                .push(edge.account_set_id);
+            children
+                .entry(edge.account_set_id)
+                .or_default()
+                .push(edge.member_account_set_id);
+        }
+        Ok(Arc::new(GraphSnapshot {
+            epoch,
+            parents,
+            children,
+            meta: HashMap::new(),
+            refreshed_at: None,
+        }))
+    }
+
+    pub(super) fn stats(&self) -> GraphCacheStats {
+        let snapshot = self.load();
+        let metrics = &self.inner.metrics;
+        GraphCacheStats {
+            hits: metrics.hits.load(Ordering::Relaxed),
+            fallbacks: GraphCacheFallbacks {
+                cold: metrics.fallbacks_cold.load(Ordering::Relaxed),
+                epoch_mismatch: metrics.fallbacks_epoch.load(Ordering::Relaxed),
+                unknown_set: metrics.fallbacks_unknown.load(Ordering::Relaxed),
+            },
+            epoch: (snapshot.epoch != COLD_EPOCH).then_some(snapshot.epoch),
+            nodes: snapshot.meta.len(),
+            edges: snapshot.edge_count(),
+            last_refreshed_at: snapshot.refreshed_at,
+        }
+    }
+
+    /// Drop the installed snapshot. Every op falls back until the refresh
+    /// triggered by the first of them installs a new one; correctness is
+    /// unaffected either way, since each op validates the epoch.
+    pub(super) fn invalidate(&self) {
+        let cold = GraphSnapshot::cold();
+        self.inner.metrics.record_installed(&cold);
+        *self
+            .inner
+            .snapshot
+            .write()
+            .expect("set_graph_cache snapshot lock poisoned") = Arc::new(cold);
+    }
+
+    fn record_path(&self, span: &tracing::Span, path: LookupPath) {
+        span.record("path", path.as_str());
+        self.inner.metrics.record(path);
     }
 
     fn load(&self) -> Arc<GraphSnapshot> {
@@ -726,21 +1016,29 @@ impl SetGraphCache {
         let Ok(_guard) = inner.refresh_lock.try_lock() else {
             return Ok(());
         };
-        let data = inner.repo.fetch_set_graph().await?;
+        let new = Self::build_snapshot(inner, inner.repo.fetch_set_graph().await?);
+        tracing::Span::current().record("epoch", new.epoch);
+        tracing::Span::current().record("sets", new.meta.len());
+        Self::install(inner, new);
+        Ok(())
+    }
+
+    fn build_snapshot(inner: &SetGraphCacheInner, data: SetGraphData) -> GraphSnapshot {
         let IndexedNodes {
             parents,
             children,
             meta,
         } = Self::index_nodes(data.nodes);
-        let new = GraphSnapshot {
+        GraphSnapshot {
             epoch: data.epoch,
             parents,
             children,
             meta,
-        };
-        tracing::Span::current().record("epoch", new.epoch);
-        tracing::Span::current().record("sets", new.meta.len());
+            refreshed_at: Some(inner.clock.now()),
+        }
+    }
 
+    fn install(inner: &SetGraphCacheInner, new: GraphSnapshot) {
         let mut current = inner
             .snapshot
             .write()
@@ -749,9 +1047,9 @@ impl SetGraphCache {
         // sets created since — a superset); an older epoch never
         // overwrites a newer one.
         if new.epoch >= current.epoch {
+            inner.metrics.record_installed(&new);
             *current = Arc::new(new);
         }
-        Ok(())
     }
 }
 
@@ -787,6 +1085,7 @@ mod tests {
                 (unrelated_root, vec![unrelated_leaf]),
             ]),
             meta: HashMap::new(),
+            refreshed_at: None,
         };
 
         let edges: HashSet<_> = snapshot
@@ -821,6 +1120,7 @@ mod tests {
                 (unrelated_root, vec![unrelated_leaf]),
             ]),
             meta: HashMap::new(),
+            refreshed_at: None,
         };
 
         let edges: HashSet<_> = snapshot
@@ -1,14 +1,61 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::primitives::{AccountId, AccountSetId};
+use crate::{
+    account_set_member::MembershipCaps,
+    primitives::{AccountId, AccountSetId},
+};
 
 use super::error::AccountSetError;
 
-/// Maximum depth (in set->set edges) of any root-to-leaf membership
-/// chain. Rejecting edges past this bound keeps the read-time ancestor
-/// walk cheap and terminating. Real hierarchies are <=10 deep; 16 leaves
-/// headroom.
-pub(super) const MAX_MEMBERSHIP_DEPTH: i32 = 16;
+/// Default maximum depth (in set->set edges) of any root-to-leaf
+/// membership chain. Rejecting edges past this bound keeps the read-time
+/// ancestor walk cheap and terminating. Real hierarchies are <=10 deep; 16
+/// leaves headroom.
+const DEFAULT_MAX_MEMBERSHIP_DEPTH: i32 = 16;
+
+/// Guardrails on the shape of the account-set graph, configured per
+/// deployment through `CalaLedgerConfig::membership_limits`.
+///
+/// The depth cap bounds the read-time ancestor walk; the ancestor cap
+/// bounds how many set balances a single posting fans out to; the member
+/// cap bounds the size of any one set. Every membership change made
+/// through `AccountSets` is checked against them, and so is the committed
+/// graph when the set-graph cache is warmed on startup, so lowering a limit
+/// below what a deployment already holds fails fast rather than on the next
+/// change. The create-inside-set fast path (`NewAccount::initial_account_set`)
+/// is held to the ancestor and member caps too, counted in
+/// `crate::account_set_member` under the same locks; the depth cap does
+/// not apply to it, as it adds no set->set edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MembershipLimits {
+    /// Maximum depth in set->set edges of any membership chain.
+    pub max_depth: i32,
+    /// Maximum number of sets an account may belong to, directly or
+    /// through other sets. Unbounded when `None`.
+    pub max_ancestors_per_account: Option<usize>,
+    /// Maximum number of direct members, accounts and sets together, of a
+    /// single set. Unbounded when `None`.
+    pub max_members_per_set: Option<usize>,
+}
+
+impl From<MembershipLimits> for MembershipCaps {
+    fn from(limits: MembershipLimits) -> Self {
+        Self {
+            max_ancestors_per_account: limits.max_ancestors_per_account,
+            max_members_per_set: limits.max_members_per_set,
+        }
+    }
+}
+
+impl Default for MembershipLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_MEMBERSHIP_DEPTH,
+            max_ancestors_per_account: None,
+            max_members_per_set: None,
+        }
+    }
+}
 
 /// A directed hierarchy edge: `member_account_set_id` is a direct member of
 /// `account_set_id`.
@@ -94,6 +141,127 @@ pub(super) fn has_duplicate_account_membership_paths<'a>(
     Some(false)
 }
 
+/// Reject the first account of `proposed` that would belong to more than
+/// `max` sets once its proposed and existing direct memberships are walked
+/// upward through `parents_of` (unknown sets count as roots).
+pub(super) fn validate_account_ancestors<'a>(
+    proposed: &[AccountMembership],
+    existing: &[AccountMembership],
+    parents_of: impl Fn(&AccountSetId) -> &'a [AccountSetId],
+    max: usize,
+) -> Result<(), AccountSetError> {
+    let mut per_account: HashMap<AccountId, Vec<AccountSetId>> = HashMap::new();
+    for membership in proposed.iter().chain(existing) {
+        per_account
+            .entry(membership.account_id)
+            .or_default()
+            .push(membership.account_set_id);
+    }
+    for membership in proposed {
+        let Some(seeds) = per_account.remove(&membership.account_id) else {
+            continue;
+        };
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<AccountSetId> = seeds.into();
+        while let Some(account_set_id) = queue.pop_front() {
+            if visited.insert(account_set_id) {
+                queue.extend(parents_of(&account_set_id));
+            }
+        }
+        if visited.len() > max {
+            return Err(AccountSetError::TooManyAncestors {
+                account_id: membership.account_id,
+                ancestors: visited.len(),
+                max,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Reject the first set of `proposed` (one entry per proposed member) whose
+/// direct members, `existing` plus proposed, would exceed `max`.
+pub(super) fn validate_member_counts(
+    existing: &HashMap<AccountSetId, usize>,
+    proposed: impl IntoIterator<Item = AccountSetId>,
+    max: usize,
+) -> Result<(), AccountSetError> {
+    let mut added: HashMap<AccountSetId, usize> = HashMap::new();
+    for account_set_id in proposed {
+        let count = added.entry(account_set_id).or_default();
+        *count += 1;
+        let members = existing.get(&account_set_id).copied().unwrap_or_default() + *count;
+        if members > max {
+            return Err(AccountSetError::TooManyMembers {
+                account_set_id,
+                members,
+                max,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The number of sets (itself included) each of `account_set_ids` belongs
+/// to through `parents` — what an account directly in that set inherits.
+pub(super) fn ancestor_counts(
+    account_set_ids: impl IntoIterator<Item = AccountSetId>,
+    parents: &HashMap<AccountSetId, Vec<AccountSetId>>,
+) -> Vec<(AccountSetId, usize)> {
+    account_set_ids
+        .into_iter()
+        .map(|account_set_id| {
+            let mut visited = HashSet::new();
+            let mut pending = vec![account_set_id];
+            while let Some(current) = pending.pop() {
+                if visited.insert(current) {
+                    pending.extend(parents.get(&current).into_iter().flatten());
+                }
+            }
+            (account_set_id, visited.len())
+        })
+        .collect()
+}
+
+/// Check a committed graph against the depth limit, attributing an
+/// overflow to an edge into its deepest set.
+pub(super) fn validate_graph_depth(
+    edges: &[SetMembership],
+    max: i32,
+) -> Result<(), AccountSetError> {
+    let dag = SetDag::new(edges);
+    let traversal = dag.traverse();
+    let Some((deepest, depth)) = traversal
+        .depths
+        .iter()
+        .max_by_key(|(id, depth)| (**depth, **id))
+        .map(|(id, depth)| (*id, *depth))
+    else {
+        return Ok(());
+    };
+    if depth <= max {
+        return Ok(());
+    }
+    let edge = edges
+        .iter()
+        .find(|edge| {
+            edge.member_account_set_id == deepest
+                && traversal
+                    .depths
+                    .get(&edge.account_set_id)
+                    .copied()
+                    .unwrap_or(0)
+                    == depth - 1
+        })
+        .expect("the deepest set is reached through an edge one level up");
+    Err(AccountSetError::MembershipDepthExceeded {
+        account_set_id: edge.account_set_id,
+        member_account_set_id: edge.member_account_set_id,
+        depth,
+        max,
+    })
+}
+
 /// The combined existing-plus-proposed set graph, indexed once.
 ///
 /// Nodes are exactly the keys of `indegree`: every edge endpoint gets an entry
@@ -209,6 +377,7 @@ pub(super) fn validate_set_memberships(
     existing_edges: &[SetMembership],
     proposed_edges: &[SetMembership],
     account_members: &[AccountMembership],
+    limits: &MembershipLimits,
 ) -> Result<(), AccountSetError> {
     let mut dag = SetDag::new(existing_edges.iter().chain(proposed_edges));
     for membership in account_members {
@@ -274,14 +443,32 @@ pub(super) fn validate_set_memberships(
         }
     }
 
-    if traversal.max_depth() > MAX_MEMBERSHIP_DEPTH {
-        let (index, depth) = first_depth_overflow(existing_edges, proposed_edges);
+    if let Some(max) = limits.max_ancestors_per_account {
+        let mut per_account: HashMap<AccountId, usize> = HashMap::new();
+        for membership in &account_paths {
+            *per_account.entry(membership.account_id).or_default() += 1;
+        }
+        if let Some((account_id, ancestors)) = per_account
+            .into_iter()
+            .filter(|(_, ancestors)| *ancestors > max)
+            .min()
+        {
+            return Err(AccountSetError::TooManyAncestors {
+                account_id,
+                ancestors,
+                max,
+            });
+        }
+    }
+
+    if traversal.max_depth() > limits.max_depth {
+        let (index, depth) = first_depth_overflow(existing_edges, proposed_edges, limits.max_depth);
         let edge = proposed_edges[index];
         return Err(AccountSetError::MembershipDepthExceeded {
             account_set_id: edge.account_set_id,
             member_account_set_id: edge.member_account_set_id,
             depth,
-            max: MAX_MEMBERSHIP_DEPTH,
+            max: limits.max_depth,
         });
     }
 
@@ -289,7 +476,7 @@ pub(super) fn validate_set_memberships(
 }
 
 /// Find the first proposed edge whose inclusion makes the *combined*
-/// existing-plus-proposed graph exceed `MAX_MEMBERSHIP_DEPTH`. The returned
+/// existing-plus-proposed graph exceed the depth limit `max`. The returned
 /// depth is the maximum depth of that combined graph (not only the depth of
 /// the chain through the offending edge). The batch enforces a global depth
 /// bound so the read-time ancestor walk stays cheap and terminating; the
@@ -304,6 +491,7 @@ pub(super) fn validate_set_memberships(
 fn first_depth_overflow(
     existing_edges: &[SetMembership],
     proposed_edges: &[SetMembership],
+    max: i32,
 ) -> (usize, i32) {
     let prefix_depth = |take: usize| {
         SetDag::new(existing_edges.iter().chain(&proposed_edges[..take]))
@@ -314,7 +502,7 @@ fn first_depth_overflow(
     let mut high = proposed_edges.len();
     while low < high {
         let middle = (low + high) / 2;
-        if prefix_depth(middle) > MAX_MEMBERSHIP_DEPTH {
+        if prefix_depth(middle) > max {
             high = middle;
         } else {
             low = middle + 1;
@@ -472,7 +660,10 @@ mod tests {
         let existing = [edge(root, branch), edge(branch, existing_leaf)];
         let proposed = [edge(branch, proposed_leaf)];
 
-        assert!(validate_set_memberships(&existing, &proposed, &[]).is_ok());
+        assert!(
+            validate_set_memberships(&existing, &proposed, &[], &MembershipLimits::default())
+                .is_ok()
+        );
     }
 
     #[test]
@@ -481,7 +672,7 @@ mod tests {
         let proposed = [edge(a, b), edge(b, c), edge(c, a)];
 
         assert!(matches!(
-            validate_set_memberships(&[], &proposed, &[]),
+            validate_set_memberships(&[], &proposed, &[], &MembershipLimits::default()),
             Err(AccountSetError::MembershipCycleDetected { .. })
         ));
     }
@@ -493,7 +684,7 @@ mod tests {
         let proposed = [edge(root, leaf)];
 
         assert!(matches!(
-            validate_set_memberships(&existing, &proposed, &[]),
+            validate_set_memberships(&existing, &proposed, &[], &MembershipLimits::default()),
             Err(AccountSetError::MemberAlreadyAdded)
         ));
     }
@@ -506,7 +697,12 @@ mod tests {
         let account_members = [member(left, account_id), member(right, account_id)];
 
         assert!(matches!(
-            validate_set_memberships(&existing, &[], &account_members),
+            validate_set_memberships(
+                &existing,
+                &[],
+                &account_members,
+                &MembershipLimits::default()
+            ),
             Err(AccountSetError::MemberAlreadyAdded)
         ));
     }
@@ -517,7 +713,7 @@ mod tests {
         let proposed: Vec<_> = sets.windows(2).map(|pair| edge(pair[0], pair[1])).collect();
 
         assert!(matches!(
-            validate_set_memberships(&[], &proposed, &[]),
+            validate_set_memberships(&[], &proposed, &[], &MembershipLimits::default()),
             Err(AccountSetError::MembershipDepthExceeded {
                 account_set_id,
                 member_account_set_id,
@@ -526,4 +722,89 @@ mod tests {
             }) if account_set_id == sets[16] && member_account_set_id == sets[17]
         ));
     }
+
+    #[test]
+    fn set_paths_respect_a_configured_depth() {
+        let [root, branch, leaf] = set_ids();
+        let limits = MembershipLimits {
+            max_depth: 1,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_set_memberships(&[edge(root, branch)], &[edge(branch, leaf)], &[], &limits),
+            Err(AccountSetError::MembershipDepthExceeded {
+                depth: 2,
+                max: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn account_ancestors_count_inherited_sets() {
+        let [root, branch, other] = set_ids();
+        let account = AccountId::new();
+        let parents = HashMap::from([(branch, vec![root])]);
+        let parents_of = |id: &AccountSetId| parents.get(id).map(Vec::as_slice).unwrap_or(&[]);
+
+        assert!(validate_account_ancestors(&[member(branch, account)], &[], parents_of, 2).is_ok());
+        assert!(matches!(
+            validate_account_ancestors(
+                &[member(branch, account)],
+                &[member(other, account)],
+                parents_of,
+                2
+            ),
+            Err(AccountSetError::TooManyAncestors {
+                account_id,
+                ancestors: 3,
+                max: 2,
+            }) if account_id == account
+        ));
+    }
+
+    #[test]
+    fn member_counts_add_each_proposed_member() {
+        let [set, other] = set_ids();
+        let existing = HashMap::from([(set, 1)]);
+
+        assert!(validate_member_counts(&existing, [set, other, other], 2).is_ok());
+        assert!(matches!(
+            validate_member_counts(&existing, [other, set, set], 2),
+            Err(AccountSetError::TooManyMembers {
+                account_set_id,
+                members: 3,
+                max: 2,
+            }) if account_set_id == set
+        ));
+    }
+
+    #[test]
+    fn ancestor_counts_include_the_set_itself() {
+        let [root, branch, leaf] = set_ids();
+        let parents = HashMap::from([(branch, vec![root]), (leaf, vec![branch])]);
+
+        assert_eq!(
+            ancestor_counts([root, leaf], &parents),
+            vec![(root, 1), (leaf, 3)]
+        );
+    }
+
+    #[test]
+    fn graph_depth_attributes_the_edge_into_the_deepest_set() {
+        let [root, branch, leaf] = set_ids();
+        let edges = [edge(root, branch), edge(branch, leaf)];
+
+        assert!(validate_graph_depth(&edges, 2).is_ok());
+        assert!(matches!(
+            validate_graph_depth(&edges, 1),
+            Err(AccountSetError::MembershipDepthExceeded {
+                account_set_id,
+                member_account_set_id,
+                depth: 2,
+                max: 1,
+            }) if account_set_id == branch && member_account_set_id == leaf
+        ));
+    }
 }
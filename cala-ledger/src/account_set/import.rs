@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use crate::{
+    account::{Account, NewAccount},
+    primitives::*,
+};
+
+use super::{
+    entity::{AccountSet, NewAccountSet},
+    error::AccountSetError,
+    graph_validation::{
+        validate_account_ancestors, validate_member_counts, validate_set_memberships,
+        AccountMembership, MembershipLimits, SetMembership,
+    },
+};
+
+/// A set to create together with the accounts and sets below it, for
+/// [`AccountSets::import_tree`](super::AccountSets::import_tree).
+///
+/// Every set and account in the tree is new. An account's place in the
+/// tree decides the set it joins; an `initial_account_set` it was built
+/// with is replaced.
+#[derive(Debug)]
+pub struct AccountSetTreeSpec {
+    pub account_set: NewAccountSet,
+    pub accounts: Vec<NewAccount>,
+    pub member_sets: Vec<AccountSetTreeSpec>,
+}
+
+impl AccountSetTreeSpec {
+    pub fn new(account_set: NewAccountSet) -> Self {
+        Self {
+            account_set,
+            accounts: Vec::new(),
+            member_sets: Vec::new(),
+        }
+    }
+
+    pub fn account(mut self, account: NewAccount) -> Self {
+        self.accounts.push(account);
+        self
+    }
+
+    pub fn member_set(mut self, member_set: AccountSetTreeSpec) -> Self {
+        self.member_sets.push(member_set);
+        self
+    }
+}
+
+/// What [`AccountSets::import_tree`](super::AccountSets::import_tree)
+/// created: the sets, parents before their members, and the accounts.
+pub struct ImportedAccountSetTree {
+    pub account_sets: Vec<AccountSet>,
+    pub accounts: Vec<Account>,
+}
+
+/// A tree specification flattened into the rows it creates, parents
+/// before their members.
+pub(super) struct TreeImport {
+    pub account_sets: Vec<NewAccountSet>,
+    pub edges: Vec<SetMembership>,
+    pub accounts: Vec<NewAccount>,
+    memberships: Vec<AccountMembership>,
+}
+
+impl TreeImport {
+    pub fn flatten(spec: AccountSetTreeSpec) -> Self {
+        let mut ret = Self {
+            account_sets: Vec::new(),
+            edges: Vec::new(),
+            accounts: Vec::new(),
+            memberships: Vec::new(),
+        };
+        let mut pending = vec![(None, spec)];
+        while let Some((parent_id, spec)) = pending.pop() {
+            let account_set_id = spec.account_set.id;
+            if let Some(parent_id) = parent_id {
+                ret.edges
+                    .push(SetMembership::from((parent_id, account_set_id)));
+            }
+            ret.account_sets.push(spec.account_set);
+            for account in spec.accounts {
+                ret.memberships
+                    .push(AccountMembership::from((account_set_id, account.id)));
+                ret.accounts
+                    .push(account.with_initial_account_set(account_set_id));
+            }
+            pending.extend(
+                spec.member_sets
+                    .into_iter()
+                    .rev()
+                    .map(|member_set| (Some(account_set_id), member_set)),
+            );
+        }
+        ret
+    }
+
+    /// Check the tree the way the individual membership calls would, all
+    /// in memory: one journal throughout, no cycles or repeated paths,
+    /// and within `limits`.
+    pub fn validate(&self, limits: &MembershipLimits) -> Result<(), AccountSetError> {
+        let journal_id = self.account_sets.first().map(|set| set.journal_id);
+        if self
+            .account_sets
+            .iter()
+            .any(|set| Some(set.journal_id) != journal_id)
+        {
+            return Err(AccountSetError::JournalIdMismatch);
+        }
+
+        validate_set_memberships(&[], &self.edges, &self.memberships, limits)?;
+        if let Some(max) = limits.max_members_per_set {
+            validate_member_counts(
+                &HashMap::new(),
+                self.edges
+                    .iter()
+                    .map(|edge| edge.account_set_id)
+                    .chain(self.memberships.iter().map(|m| m.account_set_id)),
+                max,
+            )?;
+        }
+        if let Some(max) = limits.max_ancestors_per_account {
+            let mut parents: HashMap<AccountSetId, Vec<AccountSetId>> = HashMap::new();
+            for edge in &self.edges {
+                parents
+                    .entry(edge.member_account_set_id)
+                    .or_default()
+                    .push(edge.account_set_id);
+            }
+            validate_account_ancestors(
+                &self.memberships,
+                &[],
+                |id| parents.get(id).map(Vec::as_slice).unwrap_or(&[]),
+                max,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_set(journal_id: JournalId) -> NewAccountSet {
+        NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name("set")
+            .journal_id(journal_id)
+            .balance_rollup(BalanceRollup::Synchronous)
+            .build()
+            .unwrap()
+    }
+
+    fn new_account() -> NewAccount {
+        NewAccount::builder()
+            .id(AccountId::new())
+            .name("account")
+            .code(AccountId::new().to_string())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn flattens_parents_before_members() {
+        let journal_id = JournalId::new();
+        let (root, left, right) = (
+            new_set(journal_id),
+            new_set(journal_id),
+            new_set(journal_id),
+        );
+        let (root_id, left_id, right_id) = (root.id, left.id, right.id);
+        let account = new_account();
+        let account_id = account.id;
+        let spec = AccountSetTreeSpec::new(root)
+            .member_set(AccountSetTreeSpec::new(left).account(account))
+            .member_set(AccountSetTreeSpec::new(right));
+
+        let import = TreeImport::flatten(spec);
+
+        let ids: Vec<_> = import.account_sets.iter().map(|set| set.id).collect();
+        assert_eq!(ids, vec![root_id, left_id, right_id]);
+        assert_eq!(
+            import.edges,
+            vec![
+                SetMembership::from((root_id, left_id)),
+                SetMembership::from((root_id, right_id)),
+            ]
+        );
+        assert_eq!(
+            import.memberships,
+            vec![AccountMembership::from((left_id, account_id))]
+        );
+        assert!(import.validate(&MembershipLimits::default()).is_ok());
+    }
+
+    #[test]
+    fn rejects_sets_from_different_journals() {
+        let spec = AccountSetTreeSpec::new(new_set(JournalId::new()))
+            .member_set(AccountSetTreeSpec::new(new_set(JournalId::new())));
+
+        assert!(matches!(
+            TreeImport::flatten(spec).validate(&MembershipLimits::default()),
+            Err(AccountSetError::JournalIdMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_tree_past_the_limits() {
+        let journal_id = JournalId::new();
+        let spec = AccountSetTreeSpec::new(new_set(journal_id)).member_set(
+            AccountSetTreeSpec::new(new_set(journal_id))
+                .account(new_account())
+                .account(new_account()),
+        );
+        let import = TreeImport::flatten(spec);
+
+        assert!(matches!(
+            import.validate(&MembershipLimits {
+                max_depth: 0,
+                ..Default::default()
+            }),
+            Err(AccountSetError::MembershipDepthExceeded { .. })
+        ));
+        assert!(matches!(
+            import.validate(&MembershipLimits {
+                max_members_per_set: Some(1),
+                ..Default::default()
+            }),
+            Err(AccountSetError::TooManyMembers { members: 2, .. })
+        ));
+        assert!(matches!(
+            import.validate(&MembershipLimits {
+                max_ancestors_per_account: Some(1),
+                ..Default::default()
+            }),
+            Err(AccountSetError::TooManyAncestors { ancestors: 2, .. })
+        ));
+    }
+}
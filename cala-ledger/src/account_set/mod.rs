@@ -1,29 +1,54 @@
+mod backfill;
 mod entity;
 pub mod error;
 mod graph_cache;
 mod graph_validation;
+mod import;
 mod repo;
+mod tree;
 
+use chrono::{DateTime, NaiveDate, Utc};
 use es_entity::clock::ClockHandle;
+use obix::out::RegisteredEventHandler;
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 use tracing::instrument;
 
 use crate::{
-    account::*, account_set_member::AccountSetMembers, balance::*, outbox::*, primitives::JournalId,
+    account::*,
+    account_set_member::AccountSetMembers,
+    balance::*,
+    outbox::*,
+    primitives::{BalanceRollup, JournalId},
 };
 
 pub use crate::account_set_member::members_cursor::*;
+pub(crate) use backfill::{
+    register_account_set_backfill, AccountSetBackfillRepo, AccountSetBackfills,
+};
 pub use entity::*;
 use error::*;
 use graph_cache::SetGraphCache;
+pub use graph_cache::{GraphCacheFallbacks, GraphCacheStats};
 /// Re-exported for the posting flow, which reads the direct-membership probe
 /// seeds as part of its single read statement and hands them back to the
 /// set-graph cache.
 pub(crate) use graph_validation::AccountMembership;
-use graph_validation::SetMembership;
+pub use graph_validation::MembershipLimits;
+use graph_validation::{validate_member_counts, SetMembership};
+use import::TreeImport;
+pub use import::{AccountSetTreeSpec, ImportedAccountSetTree};
 pub use repo::account_set_cursor::*;
 use repo::*;
+pub use tree::{AccountSetAncestor, AccountSetTreeNode};
+
+/// How long a member joining eventually-consistent sets with its balance
+/// history, or a set changing its balance rollup, waits for the streaming
+/// rollup to apply the entries already posted.
+const EC_ROLLUP_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Clone)]
 pub struct AccountSets {
@@ -37,28 +62,68 @@ pub struct AccountSets {
     /// SQL stays in `repo.rs`; the cache orchestrates. Shared across
     /// clones.
     set_graph_cache: SetGraphCache,
+    ec_rollup: RegisteredEventHandler<OutboxEventPayload, CalaMailboxTables>,
+    backfills: AccountSetBackfills,
     clock: ClockHandle,
 }
 
 impl AccountSets {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         pool: &PgPool,
         publisher: &OutboxPublisher,
         accounts: &Accounts,
         balances: &Balances,
         account_set_members: &AccountSetMembers,
+        ec_rollup: &RegisteredEventHandler<OutboxEventPayload, CalaMailboxTables>,
+        backfills: &AccountSetBackfills,
         clock: &ClockHandle,
+        membership_limits: MembershipLimits,
     ) -> Self {
         let repo = AccountSetRepo::new(pool, publisher);
         Self {
-            set_graph_cache: SetGraphCache::new(repo.clone()),
+            set_graph_cache: SetGraphCache::new(repo.clone(), membership_limits, clock),
             repo,
             accounts: accounts.clone(),
             balances: balances.clone(),
             account_set_members: account_set_members.clone(),
+            ec_rollup: ec_rollup.clone(),
+            backfills: backfills.clone(),
             clock: clock.clone(),
         }
     }
+
+    /// Load the set-graph cache and check the stored graph against the
+    /// configured [`MembershipLimits`]. Runs on startup; calling it again
+    /// reloads the cache from the committed graph.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.warm_graph_cache",
+        skip(self),
+        err(level = "warn")
+    )]
+    pub async fn warm_graph_cache(&self) -> Result<(), AccountSetError> {
+        self.set_graph_cache.warm().await
+    }
+
+    /// Drop this process's set-graph cache. Postings and membership changes
+    /// fall back to walking the graph in SQL until the first of them
+    /// refreshes it; see [`Self::warm_graph_cache`] to reload it eagerly.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.invalidate_graph_cache",
+        skip(self)
+    )]
+    pub fn invalidate_graph_cache(&self) {
+        self.set_graph_cache.invalidate()
+    }
+
+    /// Hit and fallback counters of this process's set-graph cache, with
+    /// the epoch, size and age of its installed snapshot.
+    pub fn graph_cache_stats(&self) -> GraphCacheStats {
+        self.set_graph_cache.stats()
+    }
+
     #[instrument(level = "debug", name = "cala_ledger.account_sets.create", skip(self))]
     pub async fn create(
         &self,
@@ -208,7 +273,7 @@ impl AccountSets {
                 tracing::Span::current().record("is_account", true);
                 tracing::Span::current().record("is_account_set", false);
                 tracing::Span::current().record("member_id", tracing::field::display(&id));
-                let set = self.repo.find_by_id_in_op(&mut *op, account_set_id).await?;
+                let set = self.find_in_op(&mut *op, account_set_id).await?;
                 (set, id)
             }
             AccountSetMemberId::AccountSet(id) => {
@@ -216,7 +281,6 @@ impl AccountSets {
                 tracing::Span::current().record("is_account_set", true);
                 tracing::Span::current().record("member_id", tracing::field::display(&id));
                 let mut sets = self
-                    .repo
                     .find_all_in_op::<AccountSet>(&mut *op, &[account_set_id, id])
                     .await?;
                 let target = sets
@@ -244,18 +308,12 @@ impl AccountSets {
 
         match member {
             AccountSetMemberId::Account(id) => {
-                self.repo.lock_graph_shared_in_op(&mut *op).await?;
-                self.account_set_members
-                    .lock_members_in_op(&mut *op, &[id])
-                    .await?;
-                self.set_graph_cache
-                    .assert_no_double_membership_in_op(
-                        op,
-                        &[AccountMembership {
-                            account_set_id,
-                            account_id: id,
-                        }],
-                    )
+                let membership = [AccountMembership {
+                    account_set_id,
+                    account_id: id,
+                }];
+                self.lock_account_memberships_in_op(op, &membership).await?;
+                self.assert_account_memberships_in_op(op, &membership)
                     .await?;
                 self.account_set_members
                     .add_in_op(&mut *op, &[(account_set_id, id)])
@@ -346,14 +404,8 @@ impl AccountSets {
             });
         }
 
-        let account_ids: Vec<AccountId> = members.iter().map(|m| m.account_id).collect();
-        self.repo.lock_graph_shared_in_op(op).await?;
-        self.account_set_members
-            .lock_members_in_op(op, &account_ids)
-            .await?;
-        self.set_graph_cache
-            .assert_no_double_membership_in_op(op, &members)
-            .await?;
+        self.lock_account_memberships_in_op(op, &members).await?;
+        self.assert_account_memberships_in_op(op, &members).await?;
         let pairs: Vec<(AccountSetId, AccountId)> = members
             .iter()
             .map(|m| (m.account_set_id, m.account_id))
@@ -478,6 +530,111 @@ impl AccountSets {
         Ok(())
     }
 
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.import_tree",
+        skip_all,
+        fields(account_set_id = %spec.account_set.id)
+    )]
+    pub async fn import_tree(
+        &self,
+        spec: AccountSetTreeSpec,
+    ) -> Result<ImportedAccountSetTree, AccountSetError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let imported = self.import_tree_in_op(&mut op, spec).await?;
+        op.commit().await?;
+        Ok(imported)
+    }
+
+    /// Create a whole tree of new sets and accounts, with its memberships,
+    /// in `op`. The tree is validated in memory first — journal, cycles,
+    /// repeated paths, depth and the configured [`MembershipLimits`] — so a
+    /// rejected tree touches nothing. Then the sets are created in one
+    /// batch, the set edges inserted in one statement under the exclusive
+    /// membership-graph lock, and the accounts created in one batch that
+    /// attaches each to its set through the create-inside-set fast path.
+    /// Nothing in the tree exists beforehand, so the checks against the
+    /// stored graph that the individual calls run have nothing to find.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.import_tree_in_op",
+        skip_all,
+        fields(account_set_id = %spec.account_set.id, sets_count, accounts_count),
+        err(level = "warn")
+    )]
+    pub async fn import_tree_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        spec: AccountSetTreeSpec,
+    ) -> Result<ImportedAccountSetTree, AccountSetError> {
+        let TreeImport {
+            account_sets,
+            edges,
+            accounts,
+            ..
+        } = {
+            let import = TreeImport::flatten(spec);
+            import.validate(self.set_graph_cache.limits())?;
+            import
+        };
+        let span = tracing::Span::current();
+        span.record("sets_count", account_sets.len());
+        span.record("accounts_count", accounts.len());
+
+        let account_sets = self.create_all_in_op(op, account_sets).await?;
+        if !edges.is_empty() {
+            self.repo.lock_for_set_membership_op(op).await?;
+            self.repo.insert_member_sets(op, &edges).await?;
+        }
+        let accounts = self.accounts.create_all_in_op(op, accounts).await?;
+
+        Ok(ImportedAccountSetTree {
+            account_sets,
+            accounts,
+        })
+    }
+
+    /// Take the locks an account attach runs under: the coarse SHARED
+    /// graph lock and the per-member lock on every account. With a
+    /// member cap configured the target sets are locked in the same
+    /// namespace too, so concurrent attaches to one set count its members
+    /// one after the other.
+    async fn lock_account_memberships_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        members: &[AccountMembership],
+    ) -> Result<(), AccountSetError> {
+        self.repo.lock_graph_shared_in_op(op).await?;
+        let mut lock_ids: Vec<AccountId> = members.iter().map(|m| m.account_id).collect();
+        if self.set_graph_cache.limits().max_members_per_set.is_some() {
+            lock_ids.extend(members.iter().map(|m| AccountId::from(m.account_set_id)));
+        }
+        self.account_set_members
+            .lock_members_in_op(op, &lock_ids)
+            .await?;
+        Ok(())
+    }
+
+    /// The checks an account attach must pass under the locks of
+    /// [`Self::lock_account_memberships_in_op`]: the member cap, then the
+    /// ancestor cap and path uniqueness.
+    async fn assert_account_memberships_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        members: &[AccountMembership],
+    ) -> Result<(), AccountSetError> {
+        if let Some(max) = self.set_graph_cache.limits().max_members_per_set {
+            let mut set_ids: Vec<AccountSetId> = members.iter().map(|m| m.account_set_id).collect();
+            set_ids.sort_unstable();
+            set_ids.dedup();
+            let existing = self.repo.count_direct_members_in_op(op, &set_ids).await?;
+            validate_member_counts(&existing, members.iter().map(|m| m.account_set_id), max)?;
+        }
+        self.set_graph_cache
+            .assert_no_double_membership_in_op(op, members)
+            .await
+    }
+
     /// `cala_balance_history` row in `journal_id`. Folding existing
     /// balance into a parent set after the fact is unsafe: the streaming
     /// rollup only folds in a member's activity from the point it joins,
@@ -510,6 +667,43 @@ impl AccountSets {
         Ok(())
     }
 
+    /// Make sure the streaming rollup applied every entry of `member_id`
+    /// before its balances are carried into other sets. With `witness_ids`
+    /// — EC accounts the rollup already folds the member into — progress is
+    /// read off their history and a lagging rollup is an error. Without,
+    /// nothing records it, so wait (up to `EC_ROLLUP_TIMEOUT`) for the
+    /// rollup to pass everything published so far. Either way the caller's
+    /// membership guard keeps new entries of the member out meanwhile.
+    async fn await_member_rolled_up_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        member_id: AccountId,
+        member_of: Option<AccountSetId>,
+        witness_ids: &[AccountId],
+    ) -> Result<(), AccountSetError> {
+        if witness_ids.is_empty() {
+            self.await_ec_rollup().await?;
+        } else if self
+            .balances
+            .ec_rollup_pending_in_op(op, journal_id, member_id, member_of, witness_ids)
+            .await?
+        {
+            return Err(crate::balance::error::BalanceError::EcRollupPending(member_id).into());
+        }
+        Ok(())
+    }
+
+    /// Wait (up to `EC_ROLLUP_TIMEOUT`) for the streaming rollup to pass
+    /// everything published so far.
+    async fn await_ec_rollup(&self) -> Result<(), AccountSetError> {
+        let frontier = self.ec_rollup.load().await?.frontier();
+        self.ec_rollup
+            .await_sequence(frontier, EC_ROLLUP_TIMEOUT)
+            .await?;
+        Ok(())
+    }
+
     #[instrument(level = "debug", name = "cala_ledger.account_sets.remove_member", skip(self, member), fields(account_set_id = %account_set_id))]
     pub async fn remove_member(
         &self,
@@ -541,12 +735,11 @@ impl AccountSets {
 
         let (account_set, member_id) = match member {
             AccountSetMemberId::Account(id) => {
-                let set = self.repo.find_by_id_in_op(&mut *op, account_set_id).await?;
+                let set = self.find_in_op(&mut *op, account_set_id).await?;
                 (set, id)
             }
             AccountSetMemberId::AccountSet(id) => {
                 let mut sets = self
-                    .repo
                     .find_all_in_op::<AccountSet>(&mut *op, &[account_set_id, id])
                     .await?;
                 let target = sets
@@ -590,12 +783,798 @@ impl AccountSets {
         Ok(account_set)
     }
 
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.move_member",
+        skip(self, member),
+        fields(from_account_set_id = %from_account_set_id, to_account_set_id = %to_account_set_id)
+    )]
+    pub async fn move_member(
+        &self,
+        member: impl Into<AccountId>,
+        from_account_set_id: AccountSetId,
+        to_account_set_id: AccountSetId,
+        effective: NaiveDate,
+    ) -> Result<AccountSet, AccountSetError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let account_set = self
+            .move_member_in_op(
+                &mut op,
+                member,
+                from_account_set_id,
+                to_account_set_id,
+                effective,
+            )
+            .await?;
+        op.commit().await?;
+        Ok(account_set)
+    }
+
+    /// Move an account — balance history and all — from one direct
+    /// parent set to another. The account's balances leave every ancestor
+    /// of `from_account_set_id` that is not also an ancestor of
+    /// `to_account_set_id` and join the new ones: current balances in
+    /// full, cumulative effective balances from `effective` onwards.
+    /// Returns the set the account moved to.
+    ///
+    /// Runs under the account-member lock protocol of `add_member_in_op`,
+    /// with the membership guard doubling as the fence against postings to
+    /// the account. Only accounts can move: a set's balance is derived from
+    /// many members, none of whose postings that guard fences.
+    ///
+    /// A move into eventually-consistent sets waits (up to
+    /// `EC_ROLLUP_TIMEOUT`, and before it takes any lock) for the streaming
+    /// rollup to apply the account's earlier entries, which it would
+    /// otherwise fold into the new sets a second time. When the account already rolls up into EC balances, the
+    /// rollup's progress is instead read off those balances, and a move is
+    /// refused with
+    /// [`BalanceError::EcRollupPending`](crate::balance::error::BalanceError::EcRollupPending)
+    /// while it lags.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.move_member_in_op",
+        skip(self, op, member),
+        fields(
+            from_account_set_id = %from_account_set_id,
+            to_account_set_id = %to_account_set_id,
+            member_id = tracing::field::Empty,
+        ),
+        err(level = "warn")
+    )]
+    pub async fn move_member_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        member: impl Into<AccountId>,
+        from_account_set_id: AccountSetId,
+        to_account_set_id: AccountSetId,
+        effective: NaiveDate,
+    ) -> Result<AccountSet, AccountSetError> {
+        let member_id = member.into();
+        tracing::Span::current().record("member_id", tracing::field::display(&member_id));
+
+        let mut sets = self
+            .find_all_in_op::<AccountSet>(&mut *op, &[from_account_set_id, to_account_set_id])
+            .await?;
+        let to_set = sets
+            .remove(&to_account_set_id)
+            .ok_or(AccountSetError::CouldNotFindById(to_account_set_id))?;
+        let journal_id = to_set.values().journal_id;
+        if from_account_set_id != to_account_set_id {
+            let from_set = sets
+                .remove(&from_account_set_id)
+                .ok_or(AccountSetError::CouldNotFindById(from_account_set_id))?;
+            if from_set.values().journal_id != journal_id {
+                return Err(AccountSetError::JournalIdMismatch);
+            }
+        }
+
+        // Catch up before taking any lock, as `update_balance_rollup_in_op`
+        // does: the rollup blocks on the membership guard taken below, so
+        // only what posts meanwhile is left to wait for under it. The
+        // ancestors are read again once the graph is locked.
+        let (removed_from, added_to) = self
+            .moved_ancestors_in_op(op, from_account_set_id, to_account_set_id)
+            .await?;
+        if !self
+            .balances
+            .find_eventually_consistent_in_op(
+                op,
+                &Self::move_involved(member_id, &removed_from, &added_to),
+            )
+            .await?
+            .is_empty()
+        {
+            self.await_ec_rollup().await?;
+        }
+
+        let has_history = self
+            .balances
+            .member_has_balance_history_in_op(op, journal_id, member_id)
+            .await?;
+        let membership = [AccountMembership {
+            account_set_id: to_account_set_id,
+            account_id: member_id,
+        }];
+        self.lock_account_memberships_in_op(op, &membership).await?;
+
+        let probe = self
+            .repo
+            .probe_direct_memberships_in_op(op, &[member_id])
+            .await?;
+        if !probe
+            .seeds
+            .iter()
+            .any(|seed| seed.account_set_id == from_account_set_id)
+        {
+            return Err(AccountSetError::NotAMember {
+                account_set_id: from_account_set_id,
+                member_id,
+            });
+        }
+
+        let (removed_from, added_to) = self
+            .moved_ancestors_in_op(op, from_account_set_id, to_account_set_id)
+            .await?;
+
+        self.account_set_members
+            .remove_in_op(op, from_account_set_id, member_id)
+            .await?;
+        self.assert_account_memberships_in_op(op, &membership)
+            .await?;
+        self.account_set_members
+            .add_in_op(op, &[(to_account_set_id, member_id)])
+            .await?;
+
+        if !has_history || (removed_from.is_empty() && added_to.is_empty()) {
+            return Ok(to_set);
+        }
+
+        let eventually_consistent = self
+            .balances
+            .find_eventually_consistent_in_op(
+                op,
+                &Self::move_involved(member_id, &removed_from, &added_to),
+            )
+            .await?;
+        // A move out of EC sets — or of an EC account — needs the rollup
+        // to have applied the member's entries too: any it has left would
+        // be folded into the new ancestors only, after the old ones already
+        // lost them.
+        let witness_ids: Vec<AccountId> = removed_from
+            .iter()
+            .map(AccountId::from)
+            .chain(std::iter::once(member_id))
+            .filter(|id| eventually_consistent.contains(id))
+            .collect();
+        let joins_ec = added_to
+            .iter()
+            .any(|id| eventually_consistent.contains(&AccountId::from(id)));
+        if joins_ec || !witness_ids.is_empty() {
+            self.await_member_rolled_up_in_op(
+                op,
+                journal_id,
+                member_id,
+                Some(from_account_set_id),
+                &witness_ids,
+            )
+            .await?;
+        }
+
+        let balances = self
+            .balances
+            .move_member_in_op(
+                op,
+                journal_id,
+                member_id,
+                &removed_from,
+                &added_to,
+                &eventually_consistent,
+                effective,
+                self.clock.now(),
+            )
+            .await?;
+        self.repo
+            .publish_member_moved_in_op(
+                op,
+                OutboxEventPayload::AccountSetMemberMoved {
+                    journal_id,
+                    member_id,
+                    from_account_set_id,
+                    to_account_set_id,
+                    effective,
+                    balances,
+                },
+            )
+            .await?;
+
+        Ok(to_set)
+    }
+
+    /// The ancestors a member of `from_account_set_id` leaves and the ones
+    /// it joins when it moves to `to_account_set_id`, each sorted.
+    async fn moved_ancestors_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        from_account_set_id: AccountSetId,
+        to_account_set_id: AccountSetId,
+    ) -> Result<(Vec<AccountSetId>, Vec<AccountSetId>), AccountSetError> {
+        let ancestors = self
+            .repo
+            .fetch_ancestors_in_op(op, &[from_account_set_id, to_account_set_id])
+            .await?;
+        let old: HashSet<AccountSetId> = ancestors[&from_account_set_id].iter().copied().collect();
+        let new: HashSet<AccountSetId> = ancestors[&to_account_set_id].iter().copied().collect();
+        let mut removed_from: Vec<AccountSetId> = old.difference(&new).copied().collect();
+        let mut added_to: Vec<AccountSetId> = new.difference(&old).copied().collect();
+        removed_from.sort_unstable();
+        added_to.sort_unstable();
+        Ok((removed_from, added_to))
+    }
+
+    /// The accounts whose balances a move writes or reads: the moved
+    /// ancestors and the member itself.
+    fn move_involved(
+        member_id: AccountId,
+        removed_from: &[AccountSetId],
+        added_to: &[AccountSetId],
+    ) -> Vec<AccountId> {
+        removed_from
+            .iter()
+            .chain(added_to.iter())
+            .map(AccountId::from)
+            .chain(std::iter::once(member_id))
+            .collect()
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.add_member_with_backfill",
+        skip(self, member),
+        fields(account_set_id = %account_set_id)
+    )]
+    pub async fn add_member_with_backfill(
+        &self,
+        account_set_id: AccountSetId,
+        member: impl Into<AccountId>,
+    ) -> Result<AccountSet, AccountSetError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let account_set = self
+            .add_member_with_backfill_in_op(&mut op, account_set_id, member)
+            .await?;
+        op.commit().await?;
+        Ok(account_set)
+    }
+
+    /// Add an account to an eventually-consistent set even though it
+    /// already has balance history, which [`Self::add_member_in_op`]
+    /// refuses. The membership takes effect at once — the streaming rollup
+    /// folds the account's new entries into the set from here on — while a
+    /// job replays its earlier entries into the set's balances and
+    /// effective balances in bounded, resumable chunks. Until the job is
+    /// done the set, and every set above it, reports as backfilling
+    /// ([`Self::is_backfilling`]) and its balances are incomplete.
+    ///
+    /// The set and all of its ancestors must be eventually consistent: the
+    /// history is only replayed into balances the rollup maintains. Like a
+    /// member move, joining waits for the rollup to apply the account's
+    /// earlier entries first, before taking any lock, so none of them is
+    /// folded in twice. An eventually-consistent account the rollup is still
+    /// behind on is refused with `EcRollupPending` instead.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.add_member_with_backfill_in_op",
+        skip(self, op, member),
+        fields(account_set_id = %account_set_id, member_id = tracing::field::Empty),
+        err(level = "warn")
+    )]
+    pub async fn add_member_with_backfill_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        member: impl Into<AccountId>,
+    ) -> Result<AccountSet, AccountSetError> {
+        let member_id = member.into();
+        tracing::Span::current().record("member_id", tracing::field::display(&member_id));
+
+        let account_set = self.find_in_op(&mut *op, account_set_id).await?;
+        let journal_id = account_set.values().journal_id;
+
+        // Catch up before taking any lock, as `move_member_in_op` does: the
+        // rollup blocks on the member lock taken below. An EC member is its
+        // own witness and is checked under the locks instead.
+        let member_is_ec = !self
+            .balances
+            .find_eventually_consistent_in_op(op, &[member_id])
+            .await?
+            .is_empty();
+        if !member_is_ec {
+            self.await_ec_rollup().await?;
+        }
+
+        self.balances.lock_postings_in_op(op, &[member_id]).await?;
+        let membership = [AccountMembership {
+            account_set_id,
+            account_id: member_id,
+        }];
+        self.lock_account_memberships_in_op(op, &membership).await?;
+        let has_history = self
+            .balances
+            .member_has_balance_history_in_op(op, journal_id, member_id)
+            .await?;
+
+        let mut ancestor_ids = self
+            .repo
+            .fetch_ancestors_in_op(op, &[account_set_id])
+            .await?
+            .remove(&account_set_id)
+            .unwrap_or_default();
+        ancestor_ids.sort_unstable();
+        let involved: Vec<AccountId> = ancestor_ids
+            .iter()
+            .map(AccountId::from)
+            .chain(std::iter::once(member_id))
+            .collect();
+        let eventually_consistent = self
+            .balances
+            .find_eventually_consistent_in_op(op, &involved)
+            .await?;
+        if let Some(id) = ancestor_ids
+            .into_iter()
+            .find(|id| !eventually_consistent.contains(&AccountId::from(id)))
+        {
+            return Err(AccountSetError::BackfillRequiresEventuallyConsistentSet(id));
+        }
+
+        self.assert_account_memberships_in_op(op, &membership)
+            .await?;
+        self.account_set_members
+            .add_in_op(op, &[(account_set_id, member_id)])
+            .await?;
+
+        if has_history {
+            if eventually_consistent.contains(&member_id)
+                && self
+                    .balances
+                    .ec_rollup_pending_in_op(op, journal_id, member_id, None, &[member_id])
+                    .await?
+            {
+                return Err(crate::balance::error::BalanceError::EcRollupPending(member_id).into());
+            }
+            self.backfills
+                .spawn_in_op(op, journal_id, account_set_id, member_id)
+                .await?;
+        }
+
+        Ok(account_set)
+    }
+
+    /// Whether a backfill into this set, or into a set below it, still has
+    /// entries to replay — while it does, the set's balances are
+    /// incomplete. See [`Self::add_member_with_backfill`].
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.is_backfilling",
+        skip(self),
+        err(level = "warn")
+    )]
+    pub async fn is_backfilling(
+        &self,
+        account_set_id: AccountSetId,
+    ) -> Result<bool, AccountSetError> {
+        self.backfills.is_backfilling(account_set_id).await
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.update_balance_rollup",
+        skip(self)
+    )]
+    pub async fn update_balance_rollup(
+        &self,
+        account_set_id: AccountSetId,
+        balance_rollup: BalanceRollup,
+    ) -> Result<AccountSet, AccountSetError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let account_set = self
+            .update_balance_rollup_in_op(&mut op, account_set_id, balance_rollup)
+            .await?;
+        op.commit().await?;
+        Ok(account_set)
+    }
+
+    /// Switch an existing set between [`BalanceRollup::Synchronous`] and
+    /// [`BalanceRollup::EventuallyConsistent`]. The set keeps its balances
+    /// and effective balances — postings and the streaming rollup write
+    /// the same rows — so from the op's commit on they are simply
+    /// maintained the other way. Records an `Updated` event with field
+    /// `balance_rollup`, published as
+    /// [`OutboxEventPayload::AccountSetUpdated`].
+    ///
+    /// A posting decides at post time whether it updates the set inline,
+    /// the rollup when it applies the transaction, so none may straddle
+    /// the switch: every posting to an account below the set is held off
+    /// (the EXCLUSIVE EC-set lock on each of them), new members are kept
+    /// out (the coarse graph lock and the set rows), and the rollup must
+    /// catch up within `EC_ROLLUP_TIMEOUT`. Making a set synchronous is
+    /// refused with [`AccountSetError::BackfillInProgress`] while
+    /// [`Self::is_backfilling`].
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.update_balance_rollup_in_op",
+        skip(self, op),
+        err(level = "warn")
+    )]
+    pub async fn update_balance_rollup_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        balance_rollup: BalanceRollup,
+    ) -> Result<AccountSet, AccountSetError> {
+        let mut account_set = self.find_in_op(&mut *op, account_set_id).await?;
+        if account_set.values().balance_rollup == balance_rollup {
+            return Ok(account_set);
+        }
+
+        // Catch up before taking any lock: the rollup blocks on the EC
+        // accounts locked below, so only what posts meanwhile is left to
+        // wait for under them.
+        self.await_ec_rollup().await?;
+
+        // Posting locks first, like a member move, then the graph; a
+        // member that joined in between is locked once the rows are held.
+        let sets = self
+            .repo
+            .fetch_subtree_sets_in_op(op, account_set_id, false)
+            .await?;
+        let mut fenced = self.subtree_accounts_in_op(op, &sets).await?;
+        self.balances.lock_postings_in_op(op, &fenced).await?;
+        self.repo.lock_for_set_membership_op(op).await?;
+        let sets = self
+            .repo
+            .fetch_subtree_sets_in_op(op, account_set_id, true)
+            .await?;
+        let joined: Vec<AccountId> = self
+            .subtree_accounts_in_op(op, &sets)
+            .await?
+            .into_iter()
+            .filter(|id| !fenced.contains(id))
+            .collect();
+        if !joined.is_empty() {
+            self.balances.lock_postings_in_op(op, &joined).await?;
+            fenced.extend(joined);
+        }
+
+        if balance_rollup == BalanceRollup::Synchronous
+            && self.backfills.is_backfilling(account_set_id).await?
+        {
+            return Err(AccountSetError::BackfillInProgress(account_set_id));
+        }
+        self.await_ec_rollup().await?;
+
+        self.accounts
+            .update_eventually_consistent_in_op(
+                op,
+                account_set_id.into(),
+                balance_rollup == BalanceRollup::EventuallyConsistent,
+            )
+            .await?;
+        if account_set
+            .update_balance_rollup(balance_rollup)
+            .did_execute()
+        {
+            self.repo.update_in_op(op, &mut account_set).await?;
+        }
+        // The set-graph caches hold each set's policy.
+        self.repo.bump_set_graph_epoch_in_op(op).await?;
+
+        Ok(account_set)
+    }
+
+    /// The member accounts of `set_ids`, sorted.
+    async fn subtree_accounts_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        set_ids: &[AccountSetId],
+    ) -> Result<Vec<AccountId>, AccountSetError> {
+        let mut account_ids: Vec<AccountId> = self
+            .repo
+            .fetch_member_accounts_in_op(op, set_ids)
+            .await?
+            .into_values()
+            .flatten()
+            .collect();
+        account_ids.sort_unstable();
+        account_ids.dedup();
+        Ok(account_ids)
+    }
+
+    /// The whole tree below `root`: its member sets, recursively, and
+    /// every set's member accounts as leaves. With `with_balances` each
+    /// node carries its current balances in the root's journal, so a
+    /// chart of accounts renders from one call.
+    #[instrument(level = "debug", name = "cala_ledger.account_sets.tree", skip(self))]
+    pub async fn tree(
+        &self,
+        root: AccountSetId,
+        with_balances: bool,
+    ) -> Result<AccountSetTreeNode, AccountSetError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let tree = self.tree_in_op(&mut op, root, with_balances).await?;
+        op.commit().await?;
+        Ok(tree)
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.tree_in_op",
+        skip(self, op)
+    )]
+    pub async fn tree_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        root: AccountSetId,
+        with_balances: bool,
+    ) -> Result<AccountSetTreeNode, AccountSetError> {
+        self.subtree_in_op(op, root, None, with_balances).await
+    }
+
+    /// The members of `account_set_id` as trees, down to `max_depth`
+    /// levels below it (`Some(1)` is just the direct members, `None` is
+    /// everything). Depths and paths are relative to `account_set_id`.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.descendants",
+        skip(self)
+    )]
+    pub async fn descendants(
+        &self,
+        account_set_id: AccountSetId,
+        max_depth: Option<usize>,
+        with_balances: bool,
+    ) -> Result<Vec<AccountSetTreeNode>, AccountSetError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let descendants = self
+            .descendants_in_op(&mut op, account_set_id, max_depth, with_balances)
+            .await?;
+        op.commit().await?;
+        Ok(descendants)
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.descendants_in_op",
+        skip(self, op)
+    )]
+    pub async fn descendants_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        max_depth: Option<usize>,
+        with_balances: bool,
+    ) -> Result<Vec<AccountSetTreeNode>, AccountSetError> {
+        Ok(self
+            .subtree_in_op(op, account_set_id, max_depth, with_balances)
+            .await?
+            .members)
+    }
+
+    /// Every set `member` belongs to, directly or through other sets,
+    /// nearest first. With `with_balances` each set carries its current
+    /// balances in its own journal.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.ancestors",
+        skip(self)
+    )]
+    pub async fn ancestors(
+        &self,
+        member: impl Into<AccountSetMemberId> + std::fmt::Debug,
+        with_balances: bool,
+    ) -> Result<Vec<AccountSetAncestor>, AccountSetError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let ancestors = self.ancestors_in_op(&mut op, member, with_balances).await?;
+        op.commit().await?;
+        Ok(ancestors)
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.ancestors_in_op",
+        skip(self, op)
+    )]
+    pub async fn ancestors_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        member: impl Into<AccountSetMemberId> + std::fmt::Debug,
+        with_balances: bool,
+    ) -> Result<Vec<AccountSetAncestor>, AccountSetError> {
+        let graph = self.set_graph_cache.snapshot_in_op(op).await?;
+        let mut direct: Vec<AccountSetId> = match member.into() {
+            AccountSetMemberId::Account(account_id) => self
+                .repo
+                .probe_direct_memberships_in_op(op, &[account_id])
+                .await?
+                .seeds
+                .into_iter()
+                .map(|seed| seed.account_set_id)
+                .collect(),
+            AccountSetMemberId::AccountSet(account_set_id) => graph
+                .parents
+                .get(&account_set_id)
+                .cloned()
+                .unwrap_or_default(),
+        };
+        direct.sort();
+        let found = tree::ancestor_sets(&direct, &graph.parents);
+        let set_ids: Vec<AccountSetId> = found.iter().map(|(id, _, _)| *id).collect();
+        let sets: HashMap<AccountSetId, AccountSet> =
+            self.repo.find_all_in_op(&mut *op, &set_ids).await?;
+
+        let mut balances = None;
+        if with_balances {
+            let mut per_journal: HashMap<JournalId, Vec<AccountId>> = HashMap::new();
+            for set in sets.values() {
+                per_journal
+                    .entry(set.values().journal_id)
+                    .or_default()
+                    .push(set.id().into());
+            }
+            let mut found_balances = HashMap::new();
+            for (journal_id, account_ids) in per_journal {
+                found_balances.extend(
+                    self.current_balances_in_op(op, journal_id, &account_ids)
+                        .await?,
+                );
+            }
+            balances = Some(found_balances);
+        }
+
+        let mut ancestors: Vec<AccountSetAncestor> = found
+            .into_iter()
+            .map(|(account_set_id, depth, path)| AccountSetAncestor {
+                name: sets
+                    .get(&account_set_id)
+                    .map(|set| set.values().name.clone())
+                    .unwrap_or_default(),
+                balances: balances.as_mut().map(|balances| {
+                    balances
+                        .remove(&AccountId::from(account_set_id))
+                        .unwrap_or_default()
+                }),
+                account_set_id,
+                depth,
+                path,
+            })
+            .collect();
+        ancestors.sort_by(|a, b| {
+            (a.depth, &a.name, a.account_set_id).cmp(&(b.depth, &b.name, b.account_set_id))
+        });
+        Ok(ancestors)
+    }
+
+    /// Assemble the tree below `root` from the set-graph snapshot plus
+    /// three flat reads: member accounts, names, and (optionally)
+    /// balances.
+    async fn subtree_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        root: AccountSetId,
+        max_depth: Option<usize>,
+        with_balances: bool,
+    ) -> Result<AccountSetTreeNode, AccountSetError> {
+        let root_set = self.repo.find_by_id_in_op(&mut *op, root).await?;
+        let graph = self.set_graph_cache.snapshot_in_op(op).await?;
+        let mut set_ids = tree::descendant_sets(root, max_depth, &graph.children);
+        set_ids.push(root);
+        let member_accounts = self.repo.fetch_member_accounts_in_op(op, &set_ids).await?;
+        let mut account_ids: Vec<AccountId> = member_accounts.values().flatten().copied().collect();
+        account_ids.sort();
+        account_ids.dedup();
+
+        let sets: HashMap<AccountSetId, AccountSet> =
+            self.repo.find_all_in_op(&mut *op, &set_ids).await?;
+        let accounts: HashMap<AccountId, Account> =
+            self.accounts.find_all_in_op(op, &account_ids).await?;
+        let mut names: HashMap<AccountId, String> = sets
+            .into_iter()
+            .map(|(id, set)| (AccountId::from(id), set.into_values().name))
+            .collect();
+        names.extend(
+            accounts
+                .into_iter()
+                .map(|(id, account)| (id, account.into_values().name)),
+        );
+
+        let balances = if with_balances {
+            account_ids.extend(set_ids.iter().map(AccountId::from));
+            Some(
+                self.current_balances_in_op(op, root_set.values().journal_id, &account_ids)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        Ok(tree::TreeParts {
+            member_sets: &graph.children,
+            member_accounts,
+            names,
+            balances,
+        }
+        .build(root, max_depth, 0))
+    }
+
+    /// Every current balance of `account_ids` in `journal_id`, read page
+    /// by page.
+    async fn current_balances_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_ids: &[AccountId],
+    ) -> Result<HashMap<AccountId, Vec<AccountBalance>>, AccountSetError> {
+        let mut balances: HashMap<AccountId, Vec<AccountBalance>> = HashMap::new();
+        let mut after = None;
+        loop {
+            let page = self
+                .balances
+                .list_for_accounts_in_op(
+                    op,
+                    journal_id,
+                    account_ids,
+                    es_entity::PaginatedQueryArgs { first: 100, after },
+                )
+                .await?;
+            for balance in page.entities {
+                balances
+                    .entry(balance.details.account_id)
+                    .or_default()
+                    .push(balance);
+            }
+            if !page.has_next_page {
+                break;
+            }
+            after = page.end_cursor;
+        }
+        Ok(balances)
+    }
+
+    /// Fill in the balance rollup policy of loaded sets from their account
+    /// rows' `eventually_consistent` flag, which is authoritative: events
+    /// written before the policy could change don't carry it, and stored
+    /// events are never rewritten.
+    async fn fill_balance_rollup<'a>(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        sets: impl IntoIterator<Item = &'a mut AccountSet>,
+    ) -> Result<(), AccountSetError> {
+        let mut sets: Vec<&mut AccountSet> = sets.into_iter().collect();
+        if sets.is_empty() {
+            return Ok(());
+        }
+        let set_ids: Vec<AccountSetId> = sets.iter().map(|set| set.id).collect();
+        let eventually_consistent = self
+            .repo
+            .fetch_eventually_consistent_in_op(op, &set_ids)
+            .await?;
+        for set in sets.iter_mut() {
+            set.fill_balance_rollup(eventually_consistent.contains(&set.id));
+        }
+        Ok(())
+    }
+
     #[instrument(level = "debug", name = "cala_ledger.account_sets.find_all", skip(self, account_set_ids), fields(account_set_ids_count = account_set_ids.len()))]
     pub async fn find_all<T: From<AccountSet>>(
         &self,
         account_set_ids: &[AccountSetId],
     ) -> Result<HashMap<AccountSetId, T>, AccountSetError> {
-        Ok(self.repo.find_all(account_set_ids).await?)
+        let mut sets: HashMap<AccountSetId, AccountSet> =
+            self.repo.find_all(account_set_ids).await?;
+        self.fill_balance_rollup(self.repo.pool(), sets.values_mut())
+            .await?;
+        Ok(sets
+            .into_iter()
+            .map(|(id, set)| (id, T::from(set)))
+            .collect())
     }
 
     #[instrument(level = "debug", name = "cala_ledger.account_sets.find_all_in_op", skip(self, op, account_set_ids), fields(account_set_ids_count = account_set_ids.len()))]
@@ -604,12 +1583,21 @@ impl AccountSets {
         op: &mut impl es_entity::AtomicOperation,
         account_set_ids: &[AccountSetId],
     ) -> Result<HashMap<AccountSetId, T>, AccountSetError> {
-        Ok(self.repo.find_all_in_op(op, account_set_ids).await?)
+        let mut sets: HashMap<AccountSetId, AccountSet> =
+            self.repo.find_all_in_op(&mut *op, account_set_ids).await?;
+        self.fill_balance_rollup(op, sets.values_mut()).await?;
+        Ok(sets
+            .into_iter()
+            .map(|(id, set)| (id, T::from(set)))
+            .collect())
     }
 
     #[instrument(level = "debug", name = "cala_ledger.account_sets.find", skip(self))]
     pub async fn find(&self, account_set_id: AccountSetId) -> Result<AccountSet, AccountSetError> {
-        Ok(self.repo.find_by_id(account_set_id).await?)
+        let mut account_set = self.repo.find_by_id(account_set_id).await?;
+        self.fill_balance_rollup(self.repo.pool(), [&mut account_set])
+            .await?;
+        Ok(account_set)
     }
 
     #[instrument(
@@ -622,7 +1610,9 @@ impl AccountSets {
         op: &mut impl es_entity::AtomicOperation,
         account_set_id: AccountSetId,
     ) -> Result<AccountSet, AccountSetError> {
-        Ok(self.repo.find_by_id_in_op(op, account_set_id).await?)
+        let mut account_set = self.repo.find_by_id_in_op(&mut *op, account_set_id).await?;
+        self.fill_balance_rollup(op, [&mut account_set]).await?;
+        Ok(account_set)
     }
 
     #[instrument(
@@ -634,7 +1624,10 @@ impl AccountSets {
         &self,
         external_id: String,
     ) -> Result<AccountSet, AccountSetError> {
-        Ok(self.repo.find_by_external_id(Some(external_id)).await?)
+        let mut account_set = self.repo.find_by_external_id(Some(external_id)).await?;
+        self.fill_balance_rollup(self.repo.pool(), [&mut account_set])
+            .await?;
+        Ok(account_set)
     }
 
     #[instrument(
@@ -648,18 +1641,21 @@ impl AccountSets {
         query: es_entity::PaginatedQueryArgs<AccountSetByNameCursor>,
     ) -> Result<es_entity::PaginatedQueryRet<AccountSet, AccountSetByNameCursor>, AccountSetError>
     {
-        match member.into() {
+        let mut ret = match member.into() {
             AccountSetMemberId::Account(account_id) => {
                 self.repo
                     .find_where_account_is_member(account_id, query)
-                    .await
+                    .await?
             }
             AccountSetMemberId::AccountSet(account_set_id) => {
                 self.repo
                     .find_where_account_set_is_member(account_set_id, query)
-                    .await
+                    .await?
             }
-        }
+        };
+        self.fill_balance_rollup(self.repo.pool(), ret.entities.iter_mut())
+            .await?;
+        Ok(ret)
     }
 
     #[instrument(
@@ -675,10 +1671,13 @@ impl AccountSets {
         es_entity::PaginatedQueryRet<AccountSet, AccountSetByCreatedAtCursor>,
         AccountSetError,
     > {
-        Ok(self
+        let mut ret = self
             .repo
             .list_for_name_by_created_at(name, args, Default::default())
-            .await?)
+            .await?;
+        self.fill_balance_rollup(self.repo.pool(), ret.entities.iter_mut())
+            .await?;
+        Ok(ret)
     }
 
     #[instrument(
@@ -695,10 +1694,13 @@ impl AccountSets {
         es_entity::PaginatedQueryRet<AccountSet, AccountSetByCreatedAtCursor>,
         AccountSetError,
     > {
-        Ok(self
+        let mut ret = self
             .repo
-            .list_for_name_by_created_at_in_op(op, name, args, Default::default())
-            .await?)
+            .list_for_name_by_created_at_in_op(&mut *op, name, args, Default::default())
+            .await?;
+        self.fill_balance_rollup(op, ret.entities.iter_mut())
+            .await?;
+        Ok(ret)
     }
 
     #[instrument(
@@ -713,18 +1715,90 @@ impl AccountSets {
         query: es_entity::PaginatedQueryArgs<AccountSetByNameCursor>,
     ) -> Result<es_entity::PaginatedQueryRet<AccountSet, AccountSetByNameCursor>, AccountSetError>
     {
-        match member.into() {
+        let mut ret = match member.into() {
             AccountSetMemberId::Account(account_id) => {
                 self.repo
-                    .find_where_account_is_member_in_op(op, account_id, query)
-                    .await
+                    .find_where_account_is_member_in_op(&mut *op, account_id, query)
+                    .await?
             }
             AccountSetMemberId::AccountSet(account_set_id) => {
                 self.repo
-                    .find_where_account_set_is_member_in_op(op, account_set_id, query)
-                    .await
+                    .find_where_account_set_is_member_in_op(&mut *op, account_set_id, query)
+                    .await?
             }
-        }
+        };
+        self.fill_balance_rollup(op, ret.entities.iter_mut())
+            .await?;
+        Ok(ret)
+    }
+
+    /// The sets `member` was a direct member of at `as_of`, each with the
+    /// period it was a member for. Sets it has since left are included;
+    /// sets it joined later are not.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.find_where_member_as_of",
+        skip(self)
+    )]
+    pub async fn find_where_member_as_of(
+        &self,
+        member: impl Into<AccountSetMemberId> + std::fmt::Debug,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, AccountSetError> {
+        let member_id = match member.into() {
+            AccountSetMemberId::Account(id) => id,
+            AccountSetMemberId::AccountSet(id) => AccountId::from(id),
+        };
+        Ok(self
+            .account_set_members
+            .find_where_member_as_of(member_id, as_of)
+            .await?)
+    }
+
+    pub async fn find_where_member_as_of_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        member: impl Into<AccountSetMemberId> + std::fmt::Debug,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, AccountSetError> {
+        let member_id = match member.into() {
+            AccountSetMemberId::Account(id) => id,
+            AccountSetMemberId::AccountSet(id) => AccountId::from(id),
+        };
+        Ok(self
+            .account_set_members
+            .find_where_member_as_of_in_op(op, member_id, as_of)
+            .await?)
+    }
+
+    /// The direct members `account_set_id` had at `as_of`, each with the
+    /// period it was a member for, oldest first.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.account_sets.list_members_as_of",
+        skip(self)
+    )]
+    pub async fn list_members_as_of(
+        &self,
+        account_set_id: AccountSetId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, AccountSetError> {
+        Ok(self
+            .account_set_members
+            .list_as_of(account_set_id, as_of)
+            .await?)
+    }
+
+    pub async fn list_members_as_of_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, AccountSetError> {
+        Ok(self
+            .account_set_members
+            .list_as_of_in_op(op, account_set_id, as_of)
+            .await?)
     }
 
     pub async fn list_members_by_created_at(
@@ -5,6 +5,7 @@ use tracing::instrument;
 use std::collections::{HashMap, HashSet};
 
 use crate::{
+    account_set_member::membership_time,
     outbox::OutboxPublisher,
     primitives::{AccountId, JournalId},
 };
@@ -47,7 +48,8 @@ use super::{
 ///   reads, and read the member rows that account-member mutations
 ///   write, so they must exclude everything.
 /// - Account-member mutations (`AccountSets::add_member(s)_in_op` /
-///   `remove_member_in_op`'s account arms) take this lock SHARED
+///   `remove_member_in_op`'s account arms, `move_member_in_op`) take
+///   this lock SHARED
 ///   ([`Self::lock_graph_shared_in_op`]) plus an EXCLUSIVE per-member
 ///   lock (`MEMBER_LOCK_CLASS`, `crate::account_set_member` — the
 ///   module that owns the member-edge table's writes and keyed on the
@@ -67,14 +69,17 @@ use super::{
 ///   `crate::account_set_member`. Sound only because the account is
 ///   created in the same op with exactly one membership; the invariant
 ///   argument lives on the `NewAccount::initial_account_set` field
-///   docs.
+///   docs. With an ancestor or member cap configured it takes this lock
+///   SHARED first, like an account-member mutation, so the caps are
+///   counted against a stable graph (`crate::account_set_member` mirrors
+///   the two constants below for that).
 ///
 /// Ordering: the coarse lock is always acquired before the per-member
 /// lock. An operation must never wait on the coarse lock while holding
 /// a per-member lock — under PostgreSQL's FIFO lock queueing that can
 /// form a wait cycle with a queued exclusive (structure) waiter. (The
-/// fast path never touches the coarse lock at all, so it cannot
-/// violate this.)
+/// fast path takes the coarse lock, when it does, before any of its
+/// per-member locks.)
 ///
 /// Key-space hygiene: the lock lives in the 2-arg advisory key space
 /// under its own `classid` ([`GRAPH_LOCK_CLASS`]). The 1-arg space is
@@ -279,6 +284,198 @@ impl AccountSetRepo {
         Ok(rows.into_iter().map(SetMembership::from).collect())
     }
 
+    /// The direct member accounts of each of `set_ids`, for the tree
+    /// queries. One flat indexed read; sets without member accounts have
+    /// no entry.
+    pub(super) async fn fetch_member_accounts_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        set_ids: &[AccountSetId],
+    ) -> Result<HashMap<AccountSetId, Vec<AccountId>>, AccountSetError> {
+        let rows: Vec<(AccountSetId, AccountId)> = sqlx::query_as(
+            r#"
+          SELECT account_set_id, member_account_id
+          FROM cala_account_set_member_accounts
+          WHERE account_set_id = ANY($1)
+          "#,
+        )
+        .bind(set_ids)
+        .fetch_all(db.as_executor())
+        .await?;
+        let mut members: HashMap<AccountSetId, Vec<AccountId>> = HashMap::new();
+        for (account_set_id, account_id) in rows {
+            members.entry(account_set_id).or_default().push(account_id);
+        }
+        Ok(members)
+    }
+
+    /// Those of `set_ids` whose account row is flagged
+    /// `eventually_consistent` — the set's balance rollup policy.
+    pub(super) async fn fetch_eventually_consistent_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        set_ids: &[AccountSetId],
+    ) -> Result<HashSet<AccountSetId>, AccountSetError> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+                r#"
+          SELECT id AS "id: AccountSetId"
+          FROM cala_accounts
+          WHERE id = ANY($1) AND eventually_consistent
+          "#,
+                set_ids as &[AccountSetId],
+            ))
+            .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// `account_set_id` and every set below it. With `for_update` the set
+    /// rows are locked, in id order: that blocks every member insert into
+    /// them until the op ends — the edge tables' foreign keys take
+    /// `FOR KEY SHARE` on the set row — the create-inside-set fast path
+    /// included, which takes no graph lock.
+    pub(super) async fn fetch_subtree_sets_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        for_update: bool,
+    ) -> Result<Vec<AccountSetId>, AccountSetError> {
+        let query = if for_update {
+            r#"
+          WITH RECURSIVE subtree AS (
+              SELECT $1::uuid AS id
+              UNION
+              SELECT e.member_account_set_id
+              FROM subtree t
+              JOIN cala_account_set_member_account_sets e ON e.account_set_id = t.id
+          )
+          SELECT s.id
+          FROM cala_account_sets s
+          JOIN subtree t ON t.id = s.id
+          ORDER BY s.id
+          FOR UPDATE OF s
+          "#
+        } else {
+            r#"
+          WITH RECURSIVE subtree AS (
+              SELECT $1::uuid AS id
+              UNION
+              SELECT e.member_account_set_id
+              FROM subtree t
+              JOIN cala_account_set_member_account_sets e ON e.account_set_id = t.id
+          )
+          SELECT id FROM subtree
+          "#
+        };
+        let rows: Vec<(AccountSetId,)> = sqlx::query_as(query)
+            .bind(account_set_id)
+            .fetch_all(db.as_executor())
+            .await?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Invalidate every set-graph cache: the next posting resolves its
+    /// ancestors from the database, and the caches reload.
+    pub(super) async fn bump_set_graph_epoch_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+    ) -> Result<(), AccountSetError> {
+        sqlx::query!("UPDATE cala_account_set_graph_epoch SET epoch = epoch + 1")
+            .execute(db.as_executor())
+            .await?;
+        Ok(())
+    }
+
+    /// The direct members, accounts and sets together, of each of
+    /// `set_ids`. Sets without members have no entry.
+    pub(super) async fn count_direct_members_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        set_ids: &[AccountSetId],
+    ) -> Result<HashMap<AccountSetId, usize>, AccountSetError> {
+        let rows: Vec<(AccountSetId, i64)> = sqlx::query_as(
+            r#"
+          SELECT account_set_id, COUNT(*)
+          FROM (
+              SELECT account_set_id
+              FROM cala_account_set_member_accounts
+              WHERE account_set_id = ANY($1)
+              UNION ALL
+              SELECT account_set_id
+              FROM cala_account_set_member_account_sets
+              WHERE account_set_id = ANY($1)
+          ) m
+          GROUP BY account_set_id
+          "#,
+        )
+        .bind(set_ids)
+        .fetch_all(db.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(account_set_id, count)| (account_set_id, count as usize))
+            .collect())
+    }
+
+    /// The set with the most direct members, if it has more than `max`.
+    /// Committed data only; the set-graph cache's warm-up check.
+    pub(super) async fn find_set_over_member_limit(
+        &self,
+        max: usize,
+    ) -> Result<Option<(AccountSetId, usize)>, AccountSetError> {
+        let row: Option<(AccountSetId, i64)> = sqlx::query_as(
+            r#"
+          SELECT account_set_id, COUNT(*)
+          FROM (
+              SELECT account_set_id FROM cala_account_set_member_accounts
+              UNION ALL
+              SELECT account_set_id FROM cala_account_set_member_account_sets
+          ) m
+          GROUP BY account_set_id
+          HAVING COUNT(*) > $1
+          ORDER BY COUNT(*) DESC, account_set_id
+          LIMIT 1
+          "#,
+        )
+        .bind(max as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(account_set_id, count)| (account_set_id, count as usize)))
+    }
+
+    /// The account belonging to the most sets, if more than `max`, given
+    /// how many sets an account directly in each set inherits. Committed
+    /// data only; the set-graph cache's warm-up check.
+    pub(super) async fn find_account_over_ancestor_limit(
+        &self,
+        ancestor_counts: &[(AccountSetId, usize)],
+        max: usize,
+    ) -> Result<Option<(AccountId, usize)>, AccountSetError> {
+        let (set_ids, counts): (Vec<AccountSetId>, Vec<i64>) = ancestor_counts
+            .iter()
+            .map(|(account_set_id, count)| (*account_set_id, *count as i64))
+            .unzip();
+        let row: Option<(AccountId, i64)> = sqlx::query_as(
+            r#"
+          SELECT m.member_account_id, SUM(c.ancestors)::BIGINT
+          FROM cala_account_set_member_accounts m
+          JOIN UNNEST($1::uuid[], $2::bigint[]) AS c(account_set_id, ancestors)
+            ON c.account_set_id = m.account_set_id
+          GROUP BY m.member_account_id
+          HAVING SUM(c.ancestors) > $3
+          ORDER BY 2 DESC, m.member_account_id
+          LIMIT 1
+          "#,
+        )
+        .bind(&set_ids)
+        .bind(&counts)
+        .bind(max as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(account_id, count)| (account_id, count as usize)))
+    }
+
     /// Load only direct account memberships that can participate in a conflict
     /// introduced by `members` against the supplied existing edge graph.
     pub(super) async fn fetch_affected_account_memberships_in_op(
@@ -383,18 +580,27 @@ impl AccountSetRepo {
             .iter()
             .map(|edge| edge.member_account_set_id)
             .collect();
+        let joined_at = membership_time(db);
 
         sqlx::query(
             r#"
-          INSERT INTO cala_account_set_member_account_sets
-            (account_set_id, member_account_set_id)
-          SELECT account_set_id, member_account_set_id
-          FROM UNNEST($1::uuid[], $2::uuid[])
-            AS proposed(account_set_id, member_account_set_id)
+          WITH inserted AS (
+            INSERT INTO cala_account_set_member_account_sets
+              (account_set_id, member_account_set_id)
+            SELECT account_set_id, member_account_set_id
+            FROM UNNEST($1::uuid[], $2::uuid[])
+              AS proposed(account_set_id, member_account_set_id)
+            RETURNING account_set_id, member_account_set_id
+          )
+          INSERT INTO cala_account_set_member_history
+            (account_set_id, member_id, member_is_account_set, joined_at)
+          SELECT account_set_id, member_account_set_id, TRUE, $3
+          FROM inserted
           "#,
         )
         .bind(&account_set_ids)
         .bind(&member_account_set_ids)
+        .bind(joined_at)
         .execute(db.as_executor())
         .await?;
 
@@ -419,6 +625,19 @@ impl AccountSetRepo {
         Ok(())
     }
 
+    /// Publish the balance side of a member move. The membership rows
+    /// themselves publish their own removed/created events.
+    pub(super) async fn publish_member_moved_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        moved: crate::outbox::OutboxEventPayload,
+    ) -> Result<(), AccountSetError> {
+        self.publisher
+            .publish_all(db, std::iter::once(moved))
+            .await?;
+        Ok(())
+    }
+
     #[instrument(
         level = "debug",
         name = "account_set.remove_member_set",
@@ -439,15 +658,25 @@ impl AccountSetRepo {
         )
         .execute(db.as_executor())
         .await?;
-        // Delete the single direct set->set edge. There are no
-        // materialized ancestor/member rows to scrub.
+        // Delete the single direct set->set edge and close its history
+        // period. There are no materialized ancestor/member rows to scrub.
         sqlx::query!(
             r#"
-          DELETE FROM cala_account_set_member_account_sets
-          WHERE account_set_id = $1 AND member_account_set_id = $2
+          WITH removed AS (
+            DELETE FROM cala_account_set_member_account_sets
+            WHERE account_set_id = $1 AND member_account_set_id = $2
+            RETURNING account_set_id, member_account_set_id
+          )
+          UPDATE cala_account_set_member_history h
+          SET left_at = $3
+          FROM removed r
+          WHERE h.member_id = r.member_account_set_id
+            AND h.account_set_id = r.account_set_id
+            AND h.left_at IS NULL
           "#,
             account_set_id as AccountSetId,
             member_account_set_id as AccountSetId,
+            membership_time(db),
         )
         .execute(db.as_executor())
         .await?;
@@ -781,6 +1010,43 @@ impl AccountSetRepo {
         Ok(())
     }
 
+    /// Each of `set_ids` together with its ancestor sets. Reads only the
+    /// membership graph, so the caller holds the coarse graph lock (at
+    /// least SHARED) to keep the edges stable for the rest of the op.
+    pub(super) async fn fetch_ancestors_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        set_ids: &[AccountSetId],
+    ) -> Result<HashMap<AccountSetId, Vec<AccountSetId>>, AccountSetError> {
+        let rows = sqlx::query!(
+            r#"
+          WITH RECURSIVE ancestors AS (
+              SELECT s.id AS seed_id, s.id AS account_set_id
+              FROM cala_account_sets s
+              WHERE s.id = ANY($1)
+              UNION
+              SELECT a.seed_id, e.account_set_id
+              FROM ancestors a
+              JOIN cala_account_set_member_account_sets e
+                ON e.member_account_set_id = a.account_set_id
+          )
+          SELECT seed_id AS "seed_id!: AccountSetId", account_set_id AS "set_id!: AccountSetId"
+          FROM ancestors
+          "#,
+            set_ids as &[AccountSetId],
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        let mut ancestors = HashMap::new();
+        for row in rows {
+            ancestors
+                .entry(row.seed_id)
+                .or_insert_with(Vec::new)
+                .push(row.set_id);
+        }
+        Ok(ancestors)
+    }
+
     /// Meta + upward edges for specific sets, on the op executor (sees
     /// the op's own uncommitted set creations). The set-graph cache's
     /// op-local supplement for seed ids unknown to its shared snapshot.
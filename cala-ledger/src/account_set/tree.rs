@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{balance::AccountBalance, primitives::*};
+
+use super::entity::AccountSetMemberId;
+
+/// A node of an account-set tree: a set together with its members, or a
+/// leaf account. Returned by [`AccountSets::tree`](super::AccountSets::tree)
+/// and [`AccountSets::descendants`](super::AccountSets::descendants).
+#[derive(Debug, Clone)]
+pub struct AccountSetTreeNode {
+    pub member: AccountSetMemberId,
+    pub name: String,
+    /// Levels below the root; the root itself is at 0.
+    pub depth: usize,
+    /// The sets from the root down to this node's parent, root first.
+    pub path: Vec<AccountSetId>,
+    /// Current balances, one per currency, when they were asked for.
+    pub balances: Option<Vec<AccountBalance>>,
+    /// Member sets, then member accounts, each ordered by name. Empty for
+    /// accounts and for sets at the depth limit.
+    pub members: Vec<AccountSetTreeNode>,
+}
+
+/// A set above a member. Returned by
+/// [`AccountSets::ancestors`](super::AccountSets::ancestors), nearest first.
+#[derive(Debug, Clone)]
+pub struct AccountSetAncestor {
+    pub account_set_id: AccountSetId,
+    pub name: String,
+    /// Levels above the member; its direct parents are at 1.
+    pub depth: usize,
+    /// The sets from the member's direct parent up to this set's child,
+    /// nearest first.
+    pub path: Vec<AccountSetId>,
+    /// Current balances, one per currency, when they were asked for.
+    pub balances: Option<Vec<AccountBalance>>,
+}
+
+/// The sets below `root` down to `max_depth` levels (all of them when
+/// `None`), breadth first and without `root` itself, following the
+/// downward edges in `member_sets`.
+pub(super) fn descendant_sets(
+    root: AccountSetId,
+    max_depth: Option<usize>,
+    member_sets: &HashMap<AccountSetId, Vec<AccountSetId>>,
+) -> Vec<AccountSetId> {
+    let mut visited = HashSet::from([root]);
+    let mut queue = VecDeque::from([(root, 0)]);
+    let mut ret = Vec::new();
+    while let Some((set_id, depth)) = queue.pop_front() {
+        if max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+        for member_id in member_sets.get(&set_id).into_iter().flatten() {
+            if visited.insert(*member_id) {
+                ret.push(*member_id);
+                queue.push_back((*member_id, depth + 1));
+            }
+        }
+    }
+    ret
+}
+
+/// Everything a tree is assembled from, read for the sets
+/// [`descendant_sets`] returned.
+pub(super) struct TreeParts<'a> {
+    pub member_sets: &'a HashMap<AccountSetId, Vec<AccountSetId>>,
+    pub member_accounts: HashMap<AccountSetId, Vec<AccountId>>,
+    pub names: HashMap<AccountId, String>,
+    pub balances: Option<HashMap<AccountId, Vec<AccountBalance>>>,
+}
+
+impl TreeParts<'_> {
+    pub fn build(
+        &self,
+        root: AccountSetId,
+        max_depth: Option<usize>,
+        root_depth: usize,
+    ) -> AccountSetTreeNode {
+        self.node(
+            AccountSetMemberId::AccountSet(root),
+            root_depth,
+            Vec::new(),
+            max_depth,
+        )
+    }
+
+    fn node(
+        &self,
+        member: AccountSetMemberId,
+        depth: usize,
+        path: Vec<AccountSetId>,
+        max_depth: Option<usize>,
+    ) -> AccountSetTreeNode {
+        let account_id = match member {
+            AccountSetMemberId::Account(id) => id,
+            AccountSetMemberId::AccountSet(id) => AccountId::from(id),
+        };
+        let mut members = Vec::new();
+        if let AccountSetMemberId::AccountSet(set_id) = member {
+            if max_depth.is_none_or(|max| depth < max) {
+                let mut member_path = path.clone();
+                member_path.push(set_id);
+                let mut sets: Vec<AccountSetId> = self
+                    .member_sets
+                    .get(&set_id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|id| !member_path.contains(id))
+                    .copied()
+                    .collect();
+                sets.sort_by_cached_key(|id| (self.name_of(AccountId::from(id)), *id));
+                let mut accounts = self
+                    .member_accounts
+                    .get(&set_id)
+                    .cloned()
+                    .unwrap_or_default();
+                accounts.sort_by_cached_key(|id| (self.name_of(*id), *id));
+                members.extend(sets.into_iter().map(|id| {
+                    self.node(
+                        AccountSetMemberId::AccountSet(id),
+                        depth + 1,
+                        member_path.clone(),
+                        max_depth,
+                    )
+                }));
+                members.extend(accounts.into_iter().map(|id| {
+                    self.node(
+                        AccountSetMemberId::Account(id),
+                        depth + 1,
+                        member_path.clone(),
+                        max_depth,
+                    )
+                }));
+            }
+        }
+        AccountSetTreeNode {
+            member,
+            name: self.name_of(account_id),
+            depth,
+            path,
+            balances: self.balances_of(account_id),
+            members,
+        }
+    }
+
+    fn name_of(&self, account_id: AccountId) -> String {
+        self.names.get(&account_id).cloned().unwrap_or_default()
+    }
+
+    fn balances_of(&self, account_id: AccountId) -> Option<Vec<AccountBalance>> {
+        self.balances
+            .as_ref()
+            .map(|balances| balances.get(&account_id).cloned().unwrap_or_default())
+    }
+}
+
+/// The sets above a member whose direct parents are `direct`, breadth
+/// first over the upward edges in `parent_sets`, each with its depth and
+/// the path it was first reached by.
+pub(super) fn ancestor_sets(
+    direct: &[AccountSetId],
+    parent_sets: &HashMap<AccountSetId, Vec<AccountSetId>>,
+) -> Vec<(AccountSetId, usize, Vec<AccountSetId>)> {
+    let mut visited = HashSet::new();
+    let mut queue: VecDeque<(AccountSetId, usize, Vec<AccountSetId>)> =
+        direct.iter().map(|id| (*id, 1, Vec::new())).collect();
+    let mut ret = Vec::new();
+    while let Some((set_id, depth, path)) = queue.pop_front() {
+        if !visited.insert(set_id) {
+            continue;
+        }
+        let mut parent_path = path.clone();
+        parent_path.push(set_id);
+        for parent_id in parent_sets.get(&set_id).into_iter().flatten() {
+            queue.push_back((*parent_id, depth + 1, parent_path.clone()));
+        }
+        ret.push((set_id, depth, path));
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(pairs: &[(AccountSetId, AccountSetId)]) -> HashMap<AccountSetId, Vec<AccountSetId>> {
+        let mut ret: HashMap<AccountSetId, Vec<AccountSetId>> = HashMap::new();
+        for (from, to) in pairs {
+            ret.entry(*from).or_default().push(*to);
+        }
+        ret
+    }
+
+    #[test]
+    fn descendants_stop_at_max_depth() {
+        let [root, child, grandchild] = [(); 3].map(|_| AccountSetId::new());
+        let member_sets = edges(&[(root, child), (child, grandchild)]);
+        assert_eq!(descendant_sets(root, Some(1), &member_sets), vec![child]);
+        assert_eq!(
+            descendant_sets(root, None, &member_sets),
+            vec![child, grandchild]
+        );
+    }
+
+    #[test]
+    fn builds_nested_nodes_with_depth_and_path() {
+        let [root, child] = [(); 2].map(|_| AccountSetId::new());
+        let [first, second] = [(); 2].map(|_| AccountId::new());
+        let member_sets = edges(&[(root, child)]);
+        let parts = TreeParts {
+            member_sets: &member_sets,
+            member_accounts: HashMap::from([(root, vec![second]), (child, vec![first])]),
+            names: HashMap::from([
+                (AccountId::from(&child), "Child".to_string()),
+                (first, "B".to_string()),
+                (second, "A".to_string()),
+            ]),
+            balances: None,
+        };
+        let tree = parts.build(root, None, 0);
+        assert_eq!(tree.depth, 0);
+        let members: Vec<_> = tree.members.iter().map(|n| n.member).collect();
+        assert_eq!(
+            members,
+            vec![
+                AccountSetMemberId::AccountSet(child),
+                AccountSetMemberId::Account(second)
+            ]
+        );
+        let leaf = &tree.members[0].members[0];
+        assert_eq!(leaf.member, AccountSetMemberId::Account(first));
+        assert_eq!(leaf.depth, 2);
+        assert_eq!(leaf.path, vec![root, child]);
+        assert!(leaf.balances.is_none());
+
+        let truncated = parts.build(root, Some(1), 0);
+        assert!(truncated.members[0].members.is_empty());
+    }
+
+    #[test]
+    fn ancestors_are_listed_nearest_first() {
+        let [parent, grandparent, other] = [(); 3].map(|_| AccountSetId::new());
+        let parent_sets = edges(&[(parent, grandparent), (other, grandparent)]);
+        let ancestors = ancestor_sets(&[parent, other], &parent_sets);
+        assert_eq!(
+            ancestors,
+            vec![
+                (parent, 1, vec![]),
+                (other, 1, vec![]),
+                (grandparent, 2, vec![parent]),
+            ]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::primitives::AccountSetId;
+use crate::primitives::{AccountId, AccountSetId};
 
 /// Error type for [`super::AccountSetMembers::attach_new_accounts_in_op`] —
 /// the ONLY method on this module whose failure mode is a domain error
@@ -15,4 +15,22 @@ pub(crate) enum AccountSetMemberError {
     Sqlx(#[from] sqlx::Error),
     #[error("AccountSetMemberError - AccountSetsNotFound: {0:?}")]
     AccountSetsNotFound(Vec<AccountSetId>),
+    #[error(
+        "AccountSetMemberError - Account '{account_id}' would belong to {ancestors} account sets, \
+         exceeding the maximum of {max}"
+    )]
+    TooManyAncestors {
+        account_id: AccountId,
+        ancestors: usize,
+        max: usize,
+    },
+    #[error(
+        "AccountSetMemberError - Account set '{account_set_id}' would have {members} direct members, \
+         exceeding the maximum of {max}"
+    )]
+    TooManyMembers {
+        account_set_id: AccountSetId,
+        members: usize,
+        max: usize,
+    },
 }
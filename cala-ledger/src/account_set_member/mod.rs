@@ -10,7 +10,9 @@
 //!
 //! This module owns every write to the edge table (the class-2 per-member
 //! advisory lock, the insert, the delete) AND its public list reads — full
-//! ownership, not just the write primitive. It has two callers with
+//! ownership, not just the write primitive. That includes the membership
+//! history (`cala_account_set_member_history`) and its as-of reads; the
+//! set->set edge writes in `account_set` keep the same history table. It has two callers with
 //! different lock protocols:
 //!
 //! - the **classic attach/detach protocol** (`account_set` module):
@@ -22,7 +24,10 @@
 //!   `NewAccount::initial_account_set`): a freshly created account joining
 //!   exactly one set in the same atomic operation, fenced by the
 //!   per-member EXCLUSIVE alone (the invariant argument lives on
-//!   `NewAccount::initial_account_set`'s field docs).
+//!   `NewAccount::initial_account_set`'s field docs) — or, with an
+//!   ancestor or member cap configured, by the coarse SHARED and the
+//!   per-member EXCLUSIVE on the target sets too, so the caps are
+//!   counted under the same locks as the classic path.
 //!
 //! Crate-internal leaf w.r.t. the entity modules: this module imports only
 //! `cala_types`, `es_entity`, `sqlx`, and the outbox — never
@@ -32,6 +37,7 @@
 mod error;
 mod repo;
 
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
 use crate::{
@@ -41,17 +47,29 @@ use crate::{
 
 pub(crate) use error::AccountSetMemberError;
 pub use repo::members_cursor;
+pub(crate) use repo::membership_time;
 use repo::AccountSetMemberRepo;
 
+/// The ancestor and member caps of `account_set::MembershipLimits`, as
+/// the create-inside-set fast path enforces them. Carried as plain values
+/// so this module needs no import of `crate::account_set`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MembershipCaps {
+    pub max_ancestors_per_account: Option<usize>,
+    pub max_members_per_set: Option<usize>,
+}
+
 #[derive(Clone)]
 pub(crate) struct AccountSetMembers {
     repo: AccountSetMemberRepo,
+    caps: MembershipCaps,
 }
 
 impl AccountSetMembers {
-    pub(crate) fn new(pool: &PgPool, publisher: &OutboxPublisher) -> Self {
+    pub(crate) fn new(pool: &PgPool, publisher: &OutboxPublisher, caps: MembershipCaps) -> Self {
         Self {
             repo: AccountSetMemberRepo::new(pool, publisher),
+            caps,
         }
     }
 
@@ -76,7 +94,9 @@ impl AccountSetMembers {
         db: &mut impl es_entity::AtomicOperation,
         pairs: &[(AccountSetId, AccountId)],
     ) -> Result<(), AccountSetMemberError> {
-        self.repo.attach_new_accounts_in_op(db, pairs).await
+        self.repo
+            .attach_new_accounts_in_op(db, pairs, self.caps)
+            .await
     }
 
     pub(crate) async fn remove_in_op(
@@ -88,6 +108,42 @@ impl AccountSetMembers {
         self.repo.remove_in_op(db, account_set_id, account_id).await
     }
 
+    pub(crate) async fn list_as_of(
+        &self,
+        account_set_id: AccountSetId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<cala_types::account_set::AccountSetMembershipPeriod>, sqlx::Error> {
+        self.repo.list_as_of(account_set_id, as_of).await
+    }
+
+    pub(crate) async fn list_as_of_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        account_set_id: AccountSetId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<cala_types::account_set::AccountSetMembershipPeriod>, sqlx::Error> {
+        self.repo.list_as_of_in_op(op, account_set_id, as_of).await
+    }
+
+    pub(crate) async fn find_where_member_as_of(
+        &self,
+        member_id: AccountId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<cala_types::account_set::AccountSetMembershipPeriod>, sqlx::Error> {
+        self.repo.find_where_member_as_of(member_id, as_of).await
+    }
+
+    pub(crate) async fn find_where_member_as_of_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        member_id: AccountId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<cala_types::account_set::AccountSetMembershipPeriod>, sqlx::Error> {
+        self.repo
+            .find_where_member_as_of_in_op(op, member_id, as_of)
+            .await
+    }
+
     pub(crate) async fn list_by_created_at(
         &self,
         id: AccountSetId,
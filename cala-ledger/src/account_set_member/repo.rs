@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
 use crate::{
@@ -5,7 +8,7 @@ use crate::{
     primitives::{AccountId, AccountSetId},
 };
 
-use super::error::AccountSetMemberError;
+use super::{error::AccountSetMemberError, MembershipCaps};
 
 pub mod members_cursor {
     use cala_types::account_set::{
@@ -44,7 +47,9 @@ pub mod members_cursor {
     }
 }
 
-use cala_types::account_set::{AccountSetMember, AccountSetMemberByExternalId, AccountSetMemberId};
+use cala_types::account_set::{
+    AccountSetMember, AccountSetMemberByExternalId, AccountSetMemberId, AccountSetMembershipPeriod,
+};
 use members_cursor::*;
 
 /// `classid` namespace for the per-member advisory locks (2-arg form),
@@ -53,6 +58,14 @@ use members_cursor::*;
 /// (= 3, `account_set::repo`).
 pub(crate) const MEMBER_LOCK_CLASS: i32 = 2;
 
+/// The coarse membership-graph lock, `(GRAPH_LOCK_CLASS,
+/// ADDVISORY_LOCK_ID)` in `account_set::repo`, which owns its protocol.
+/// Mirrored here for the create-inside-set fast path's capped form (see
+/// [`AccountSetMemberRepo::assert_new_account_caps_in_op`]); must stay in
+/// sync with `account_set::repo`.
+const GRAPH_LOCK_CLASS: i32 = 3;
+const ADDVISORY_LOCK_ID: i32 = 123456;
+
 /// The account-member edge (`cala_account_set_member_accounts`): sole write
 /// authority (insert, delete, the class-2 lock) and the member-listing
 /// reads. NOT an `EsRepo` — the edge is a plain relation plus an outbox
@@ -120,7 +133,8 @@ impl AccountSetMemberRepo {
     }
 
     /// Insert the direct account-member edges for every `(account_set_id,
-    /// account_id)` pair — one statement — and publish one
+    /// account_id)` pair, with their opening history periods — one
+    /// statement — and publish one
     /// [`OutboxEventPayload::AccountSetMemberCreated`] per pair.
     ///
     /// Precondition (CLASSIC path only): the caller has taken the coarse
@@ -138,15 +152,24 @@ impl AccountSetMemberRepo {
         }
         let account_set_ids: Vec<AccountSetId> = pairs.iter().map(|(set_id, _)| *set_id).collect();
         let account_ids: Vec<AccountId> = pairs.iter().map(|(_, account_id)| *account_id).collect();
+        let joined_at = membership_time(db);
 
         sqlx::query!(
             r#"
-            INSERT INTO cala_account_set_member_accounts (account_set_id, member_account_id)
-            SELECT account_set_id, account_id
-            FROM UNNEST($1::uuid[], $2::uuid[]) AS v(account_set_id, account_id)
+            WITH inserted AS (
+                INSERT INTO cala_account_set_member_accounts (account_set_id, member_account_id)
+                SELECT account_set_id, account_id
+                FROM UNNEST($1::uuid[], $2::uuid[]) AS v(account_set_id, account_id)
+                RETURNING account_set_id, member_account_id
+            )
+            INSERT INTO cala_account_set_member_history
+                (account_set_id, member_id, member_is_account_set, joined_at)
+            SELECT account_set_id, member_account_id, FALSE, $3
+            FROM inserted
             "#,
             &account_set_ids as &[AccountSetId],
             &account_ids as &[AccountId],
+            joined_at,
         )
         .execute(db.as_executor())
         .await?;
@@ -160,7 +183,9 @@ impl AccountSetMemberRepo {
     ///
     /// Precondition: `pairs` names accounts created *in this same op* —
     /// no lock protocol beyond the class-2 lock this statement itself
-    /// takes, and no path-uniqueness check. The invariant argument for
+    /// takes (plus, with a cap configured, the locks of
+    /// [`Self::assert_new_account_caps_in_op`]), and no path-uniqueness
+    /// check. The invariant argument for
     /// why that is sound (k=1 memberships only) lives on
     /// `NewAccount::initial_account_set`'s field docs.
     ///
@@ -179,12 +204,15 @@ impl AccountSetMemberRepo {
         &self,
         db: &mut impl es_entity::AtomicOperation,
         pairs: &[(AccountSetId, AccountId)],
+        caps: MembershipCaps,
     ) -> Result<(), AccountSetMemberError> {
         if pairs.is_empty() {
             return Ok(());
         }
         let account_set_ids: Vec<AccountSetId> = pairs.iter().map(|(set_id, _)| *set_id).collect();
         let account_ids: Vec<AccountId> = pairs.iter().map(|(_, account_id)| *account_id).collect();
+        self.assert_new_account_caps_in_op(db, pairs, caps).await?;
+        let joined_at = membership_time(db);
 
         let result = sqlx::query!(
             r#"
@@ -196,14 +224,21 @@ impl AccountSetMemberRepo {
                 SELECT account_set_id, account_id,
                        pg_advisory_xact_lock($3, hashtext(account_id::text))
                 FROM ordered
+            ), inserted AS (
+                INSERT INTO cala_account_set_member_accounts (account_set_id, member_account_id)
+                SELECT account_set_id, account_id
+                FROM locked
+                RETURNING account_set_id, member_account_id
             )
-            INSERT INTO cala_account_set_member_accounts (account_set_id, member_account_id)
-            SELECT account_set_id, account_id
-            FROM locked
+            INSERT INTO cala_account_set_member_history
+                (account_set_id, member_id, member_is_account_set, joined_at)
+            SELECT account_set_id, member_account_id, FALSE, $4
+            FROM inserted
             "#,
             &account_set_ids as &[AccountSetId],
             &account_ids as &[AccountId],
             MEMBER_LOCK_CLASS,
+            joined_at,
         )
         .execute(db.as_executor())
         .await;
@@ -221,6 +256,111 @@ impl AccountSetMemberRepo {
         Ok(())
     }
 
+    /// Hold the fast path to the configured ancestor and member caps,
+    /// under the same locks the classic attach counts under: the coarse
+    /// SHARED graph lock, then — with a member cap — the class-2 lock on
+    /// every target set. Both are taken before the class-2 locks on the
+    /// new accounts, so the coarse-before-per-member ordering holds.
+    /// Fresh accounts belong to no set yet, so an account's ancestors are
+    /// exactly its target set's, the set itself included. A no-op, with
+    /// no statements, when neither cap is configured.
+    async fn assert_new_account_caps_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        pairs: &[(AccountSetId, AccountId)],
+        caps: MembershipCaps,
+    ) -> Result<(), AccountSetMemberError> {
+        if caps.max_ancestors_per_account.is_none() && caps.max_members_per_set.is_none() {
+            return Ok(());
+        }
+        let mut set_ids: Vec<AccountSetId> = pairs.iter().map(|(set_id, _)| *set_id).collect();
+        set_ids.sort_unstable();
+        set_ids.dedup();
+
+        sqlx::query("SELECT pg_advisory_xact_lock_shared($1, $2)")
+            .bind(GRAPH_LOCK_CLASS)
+            .bind(ADDVISORY_LOCK_ID)
+            .execute(db.as_executor())
+            .await?;
+
+        if let Some(max) = caps.max_members_per_set {
+            let lock_ids: Vec<AccountId> = set_ids.iter().map(|id| AccountId::from(*id)).collect();
+            self.lock_members_in_op(db, &lock_ids).await?;
+            let rows: Vec<(AccountSetId, i64)> = sqlx::query_as(
+                r#"
+              SELECT account_set_id, COUNT(*)
+              FROM (
+                  SELECT account_set_id
+                  FROM cala_account_set_member_accounts
+                  WHERE account_set_id = ANY($1)
+                  UNION ALL
+                  SELECT account_set_id
+                  FROM cala_account_set_member_account_sets
+                  WHERE account_set_id = ANY($1)
+              ) m
+              GROUP BY account_set_id
+              "#,
+            )
+            .bind(&set_ids)
+            .fetch_all(db.as_executor())
+            .await?;
+            let existing: HashMap<AccountSetId, usize> = rows
+                .into_iter()
+                .map(|(account_set_id, count)| (account_set_id, count as usize))
+                .collect();
+            for account_set_id in &set_ids {
+                let members = existing.get(account_set_id).copied().unwrap_or(0)
+                    + pairs.iter().filter(|(id, _)| id == account_set_id).count();
+                if members > max {
+                    return Err(AccountSetMemberError::TooManyMembers {
+                        account_set_id: *account_set_id,
+                        members,
+                        max,
+                    });
+                }
+            }
+        }
+
+        if let Some(max) = caps.max_ancestors_per_account {
+            let rows: Vec<(AccountSetId, i64)> = sqlx::query_as(
+                r#"
+              WITH RECURSIVE ancestors(seed, account_set_id) AS (
+                  SELECT id, id
+                  FROM UNNEST($1::uuid[]) AS v(id)
+                  UNION
+                  SELECT a.seed, e.account_set_id
+                  FROM ancestors a
+                  JOIN cala_account_set_member_account_sets e
+                    ON e.member_account_set_id = a.account_set_id
+              )
+              SELECT seed, COUNT(*)
+              FROM ancestors
+              GROUP BY seed
+              "#,
+            )
+            .bind(&set_ids)
+            .fetch_all(db.as_executor())
+            .await?;
+            let ancestors: HashMap<AccountSetId, usize> = rows
+                .into_iter()
+                .map(|(account_set_id, count)| (account_set_id, count as usize))
+                .collect();
+            if let Some((account_id, ancestors)) = pairs
+                .iter()
+                .map(|(set_id, account_id)| (*account_id, ancestors[set_id]))
+                .filter(|(_, ancestors)| *ancestors > max)
+                .min()
+            {
+                return Err(AccountSetMemberError::TooManyAncestors {
+                    account_id,
+                    ancestors,
+                    max,
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Deliberately takes NO operation/executor: this only ever runs
     /// after its caller's insert has already failed, so it reads
     /// committed state on `self.pool` — a connection independent of the
@@ -266,7 +406,8 @@ impl AccountSetMemberRepo {
             .await
     }
 
-    /// Delete the single direct edge (plus the outbox event).
+    /// Delete the single direct edge and close its history period (plus
+    /// the outbox event).
     ///
     /// Precondition: the caller has taken the coarse SHARED lock and
     /// [`Self::lock_members_in_op`] for `account_id` in this same op — the
@@ -277,13 +418,24 @@ impl AccountSetMemberRepo {
         account_set_id: AccountSetId,
         account_id: AccountId,
     ) -> Result<(), sqlx::Error> {
+        let left_at = membership_time(db);
         sqlx::query!(
             r#"
-            DELETE FROM cala_account_set_member_accounts
-            WHERE account_set_id = $1 AND member_account_id = $2
+            WITH removed AS (
+                DELETE FROM cala_account_set_member_accounts
+                WHERE account_set_id = $1 AND member_account_id = $2
+                RETURNING account_set_id, member_account_id
+            )
+            UPDATE cala_account_set_member_history h
+            SET left_at = $3
+            FROM removed r
+            WHERE h.member_id = r.member_account_id
+              AND h.account_set_id = r.account_set_id
+              AND h.left_at IS NULL
             "#,
             account_set_id as AccountSetId,
             account_id as AccountId,
+            left_at,
         )
         .execute(db.as_executor())
         .await?;
@@ -559,6 +711,108 @@ impl AccountSetMemberRepo {
             end_cursor,
         })
     }
+
+    pub(crate) async fn list_as_of(
+        &self,
+        account_set_id: AccountSetId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, sqlx::Error> {
+        self.list_as_of_in_op(&self.pool, account_set_id, as_of)
+            .await
+    }
+
+    /// The direct members `account_set_id` had at `as_of`, each with the
+    /// period it was a member for, oldest first.
+    pub(crate) async fn list_as_of_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        account_set_id: AccountSetId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, sqlx::Error> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query_as!(
+                MembershipPeriodRow,
+                r#"
+            SELECT account_set_id, member_id, member_is_account_set, joined_at, left_at
+            FROM cala_account_set_member_history
+            WHERE account_set_id = $1
+              AND joined_at <= $2
+              AND (left_at IS NULL OR left_at > $2)
+            ORDER BY joined_at, member_id
+            "#,
+                account_set_id as AccountSetId,
+                as_of,
+            ))
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(AccountSetMembershipPeriod::from)
+            .collect())
+    }
+
+    pub(crate) async fn find_where_member_as_of(
+        &self,
+        member_id: AccountId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, sqlx::Error> {
+        self.find_where_member_as_of_in_op(&self.pool, member_id, as_of)
+            .await
+    }
+
+    /// The sets `member_id` was a direct member of at `as_of`, each with
+    /// the period it was a member for, oldest first.
+    pub(crate) async fn find_where_member_as_of_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        member_id: AccountId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountSetMembershipPeriod>, sqlx::Error> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query_as!(
+                MembershipPeriodRow,
+                r#"
+            SELECT account_set_id, member_id, member_is_account_set, joined_at, left_at
+            FROM cala_account_set_member_history
+            WHERE member_id = $1
+              AND joined_at <= $2
+              AND (left_at IS NULL OR left_at > $2)
+            ORDER BY joined_at, account_set_id
+            "#,
+                member_id as AccountId,
+                as_of,
+            ))
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(AccountSetMembershipPeriod::from)
+            .collect())
+    }
+}
+
+struct MembershipPeriodRow {
+    account_set_id: uuid::Uuid,
+    member_id: uuid::Uuid,
+    member_is_account_set: bool,
+    joined_at: DateTime<Utc>,
+    left_at: Option<DateTime<Utc>>,
+}
+
+impl From<MembershipPeriodRow> for AccountSetMembershipPeriod {
+    fn from(row: MembershipPeriodRow) -> Self {
+        let member_id = if row.member_is_account_set {
+            AccountSetMemberId::AccountSet(AccountSetId::from(row.member_id))
+        } else {
+            AccountSetMemberId::Account(AccountId::from(row.member_id))
+        };
+        Self {
+            account_set_id: AccountSetId::from(row.account_set_id),
+            member_id,
+            joined_at: row.joined_at,
+            left_at: row.left_at,
+        }
+    }
 }
 
 /// `true` iff `err` is the FK violation on
@@ -570,6 +824,13 @@ impl AccountSetMemberRepo {
 /// `From<sqlx::Error>`) rather than the full name, so a future rename
 /// that keeps "account_set_id_fkey" as a suffix does not silently stop
 /// matching.
+/// The time membership periods open or close at: the operation's cached
+/// time, else the ledger clock, so history follows the same clock as the
+/// entries it is compared against.
+pub(crate) fn membership_time(db: &impl es_entity::AtomicOperation) -> DateTime<Utc> {
+    db.maybe_now().unwrap_or_else(|| db.clock().now())
+}
+
 fn is_account_set_fk_violation(err: &dyn sqlx::error::DatabaseError) -> bool {
     err.constraint()
         .is_some_and(|c| c.contains("member_accounts_account_set_id_fkey"))
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use cala_types::primitives::*;
+
+use super::account_balance::AccountBalance;
+
+/// A balance in one currency together with the rate converting it into the
+/// target currency of a [`ConsolidatedBalance`].
+#[derive(Debug, Clone)]
+pub struct ConsolidatedComponent {
+    pub balance: AccountBalance,
+    pub rate: Decimal,
+}
+
+/// The balances of an account in all of its currencies, converted into a
+/// single currency at the rates in effect on the `as_of` date.
+#[derive(Debug, Clone)]
+pub struct ConsolidatedBalance {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub as_of: DateTime<Utc>,
+    pub components: Vec<ConsolidatedComponent>,
+}
+
+impl ConsolidatedBalance {
+    fn convert(&self, amount: impl Fn(&AccountBalance) -> Decimal) -> Decimal {
+        self.components
+            .iter()
+            .map(|component| amount(&component.balance) * component.rate)
+            .sum()
+    }
+
+    pub fn pending(&self) -> Decimal {
+        self.convert(AccountBalance::pending)
+    }
+
+    pub fn settled(&self) -> Decimal {
+        self.convert(AccountBalance::settled)
+    }
+
+    pub fn encumbrance(&self) -> Decimal {
+        self.convert(AccountBalance::encumbrance)
+    }
+
+    pub fn available(&self, layer: Layer) -> Decimal {
+        self.convert(|balance| balance.available(layer))
+    }
+}
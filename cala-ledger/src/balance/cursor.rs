@@ -6,7 +6,7 @@ use cala_types::{
     primitives::{AccountId, Currency},
 };
 
-use super::{AccountBalance, BalanceRange};
+use super::{AccountBalance, BalanceHistoryItem, BalanceRange};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AccountBalanceByCurrencyCursor {
@@ -74,3 +74,24 @@ impl From<&EffectiveBalanceSnapshot> for EffectiveBalancesModifiedCursor {
         }
     }
 }
+
+/// Keyset cursor for [`super::Balances::list_history`], on the snapshot
+/// version.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BalanceHistoryCursor {
+    pub version: u32,
+}
+
+impl From<&AccountBalance> for BalanceHistoryCursor {
+    fn from(balance: &AccountBalance) -> Self {
+        Self {
+            version: balance.details.version,
+        }
+    }
+}
+
+impl From<&BalanceHistoryItem> for BalanceHistoryCursor {
+    fn from(item: &BalanceHistoryItem) -> Self {
+        Self::from(&item.balance)
+    }
+}
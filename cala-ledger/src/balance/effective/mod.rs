@@ -16,6 +16,7 @@ use super::{
         AccountBalanceByCurrencyCursor, AccountBalanceCursor, EffectiveBalancesModifiedCursor,
     },
     error::BalanceError,
+    member_move::{delta_entries, MoveDirection},
 };
 
 use repo::*;
@@ -82,6 +83,25 @@ impl EffectiveBalances {
         self.repo.find_all(ids, date).await
     }
 
+    /// The cumulative balances as of `date` of every account (not account
+    /// set) below the sets `account_set_ids`, in every currency.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balance.effective.find_all_cumulative_for_set_members_in_op",
+        skip(self, op)
+    )]
+    pub async fn find_all_cumulative_for_set_members_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_set_ids: &[AccountSetId],
+        date: NaiveDate,
+    ) -> Result<Vec<AccountBalance>, BalanceError> {
+        self.repo
+            .find_all_for_set_members_in_op(op, journal_id, account_set_ids, date)
+            .await
+    }
+
     #[instrument(
         level = "debug",
         name = "cala_ledger.balance.effective.list_cumulative_for_account",
@@ -281,6 +301,83 @@ impl EffectiveBalances {
         Ok(())
     }
 
+    /// Carry the cumulative balances of `member_id` from `effective`
+    /// onwards out of `removed_from` and into `added_to`. Each dated change
+    /// of the member is replayed as synthetic entries through the same
+    /// update paths postings and the EC rollup use, which rewrite the later
+    /// dates of every ancestor on the way. Dates before `effective` stay
+    /// with the old ancestors.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balance.effective.move_member_in_op",
+        skip_all,
+        err(level = "warn")
+    )]
+    pub(crate) async fn move_member_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        member_id: AccountId,
+        effective: NaiveDate,
+        created_at: DateTime<Utc>,
+        removed_from: &[AccountSetId],
+        added_to: &[AccountSetId],
+    ) -> Result<(), BalanceError> {
+        let history = self
+            .repo
+            .find_cumulative_from_in_op(&mut *op, journal_id, member_id, effective)
+            .await?;
+        let no_leaves = HashSet::new();
+        let mut previous = None;
+        for (date, snapshot) in history.iter() {
+            if previous.is_some_and(|p: &cala_types::balance::BalanceSnapshot| {
+                p.currency != snapshot.currency
+            }) {
+                previous = None;
+            }
+            for (sets, direction) in [
+                (removed_from, MoveDirection::Out),
+                (added_to, MoveDirection::In),
+            ] {
+                let entries = delta_entries(previous, snapshot, direction);
+                if sets.is_empty() || entries.is_empty() {
+                    continue;
+                }
+                let mappings = HashMap::from([(member_id, sets.to_vec())]);
+                let balance_ids: (Vec<AccountId>, Vec<&str>) = sets
+                    .iter()
+                    .map(|id| (AccountId::from(id), snapshot.currency.code()))
+                    .unzip();
+                // Each path keeps only its own kind of set, so handing both
+                // every ancestor covers synchronous and EC sets alike.
+                self.update_cumulative_balances_in_op(
+                    op,
+                    journal_id,
+                    entries.clone(),
+                    *date,
+                    created_at,
+                    mappings.clone(),
+                    balance_ids.clone(),
+                )
+                .await?;
+                self.apply_ec_rollup_in_op(
+                    op,
+                    journal_id,
+                    entries,
+                    *date,
+                    created_at,
+                    mappings,
+                    balance_ids,
+                    &no_leaves,
+                )
+                .await?;
+            }
+            previous = Some(snapshot);
+        }
+        Ok(())
+    }
+
     /// EC counterpart of [`Self::update_cumulative_balances_in_op`] used by
     /// the streaming rollup: fans each entry into its EC ancestor sets and,
     /// for an entry whose leaf is an EC plain account (listed in
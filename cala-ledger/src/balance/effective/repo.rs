@@ -12,7 +12,7 @@ use crate::balance::{
 };
 use cala_types::{
     balance::{BalanceSnapshot, EffectiveBalanceSnapshot},
-    primitives::{AccountId, BalanceId, Currency, DebitOrCredit, EntryId, JournalId},
+    primitives::{AccountId, AccountSetId, BalanceId, Currency, DebitOrCredit, EntryId, JournalId},
 };
 
 use super::data::*;
@@ -232,6 +232,110 @@ impl EffectiveBalanceRepo {
         Ok(ret)
     }
 
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balances.effective.find_all_for_set_members",
+        skip_all
+    )]
+    pub(super) async fn find_all_for_set_members_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_set_ids: &[AccountSetId],
+        date: NaiveDate,
+    ) -> Result<Vec<AccountBalance>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE sets AS (
+                SELECT id FROM UNNEST($2::uuid[]) AS id
+                UNION
+                SELECT m.member_account_set_id
+                FROM sets s
+                JOIN cala_account_set_member_account_sets m ON m.account_set_id = s.id
+            ),
+            members AS (
+                SELECT DISTINCT m.member_account_id AS account_id
+                FROM sets s
+                JOIN cala_account_set_member_accounts m ON m.account_set_id = s.id
+            )
+            SELECT DISTINCT ON (b.account_id, b.currency)
+                b.values,
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+            FROM members
+            JOIN cala_accounts a ON a.id = members.account_id
+            JOIN cala_cumulative_effective_balances b
+              ON b.journal_id = $1
+             AND b.account_id = members.account_id
+             AND b.effective <= $3
+            ORDER BY b.account_id, b.currency, b.effective DESC, b.version DESC
+            "#,
+            journal_id as JournalId,
+            account_set_ids as &[AccountSetId],
+            date,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let details: BalanceSnapshot = serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot");
+                AccountBalance::new(row.normal_balance_type, details)
+            })
+            .collect())
+    }
+
+    /// The cumulative balances of `account_id` per currency from `from`
+    /// onwards, oldest first: the balance as of `from` (dated `from`),
+    /// followed by the latest version of every later effective date.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balances.effective.find_cumulative_from",
+        skip_all
+    )]
+    pub(super) async fn find_cumulative_from_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+        from: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, BalanceSnapshot)>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH opening AS (
+                SELECT DISTINCT ON (currency) currency, $3::date AS effective, values
+                FROM cala_cumulative_effective_balances
+                WHERE journal_id = $1 AND account_id = $2 AND effective <= $3
+                ORDER BY currency, effective DESC, version DESC
+            ),
+            later AS (
+                SELECT DISTINCT ON (currency, effective) currency, effective, values
+                FROM cala_cumulative_effective_balances
+                WHERE journal_id = $1 AND account_id = $2 AND effective > $3
+                ORDER BY currency, effective, version DESC
+            )
+            SELECT currency AS "currency!", effective AS "effective!", values AS "values!"
+            FROM (SELECT * FROM opening UNION ALL SELECT * FROM later) b
+            ORDER BY currency, effective
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            from,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let snapshot: BalanceSnapshot = serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot");
+                (row.effective, snapshot)
+            })
+            .collect())
+    }
+
     #[instrument(
         level = "debug",
         name = "cala_ledger.balances.effective.list_for_account",
@@ -8,10 +8,24 @@ pub enum BalanceError {
     Sqlx(#[from] sqlx::Error),
     #[error("BalanceError - NotFound: there is no balance recorded for journal {0}, account {1}, currency {2}")]
     NotFound(JournalId, AccountId, Currency),
+    #[error("BalanceError - EntryError: {0}")]
+    EntryError(#[from] crate::entry::error::EntryError),
+    #[error("BalanceError - EntryNotFound: entry {0} of a balance snapshot does not exist")]
+    EntryNotFound(EntryId),
+    #[error("BalanceError - FxRateProvider: {0}")]
+    FxRateProvider(#[source] super::FxRateError),
+    #[error("BalanceError - FxRateNotFound: there is no rate from {0} to {1} on {2}")]
+    FxRateNotFound(Currency, Currency, chrono::NaiveDate),
+    #[error(
+        "BalanceError - NonPositiveFxRate: the rate from {0} to {1} must be positive, got {2}"
+    )]
+    NonPositiveFxRate(Currency, Currency, rust_decimal::Decimal),
     #[error("BalanceError - JournalError: {0}")]
     JournalError(#[from] crate::journal::error::JournalError),
     #[error("BalanceError - JournalLocked: Cannot update balances. The journal {0} is locked")]
     JournalLocked(JournalId),
     #[error("BalanceError - AccountLocked: Cannot update balances. The account {0} is locked")]
     AccountLocked(AccountId),
+    #[error("BalanceError - EcRollupPending: the eventually consistent rollup has not applied every entry of account {0} yet")]
+    EcRollupPending(AccountId),
 }
@@ -0,0 +1,216 @@
+use chrono::NaiveDate;
+use futures::{future::BoxFuture, FutureExt};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use std::collections::{BTreeMap, HashMap};
+
+use cala_types::primitives::Currency;
+
+use super::error::BalanceError;
+
+pub type FxRateError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Source of the exchange rates used by [`super::Balances::find_consolidated`].
+pub trait FxRateProvider: std::fmt::Debug + Send + Sync {
+    /// How many units of `to` one unit of `from` is worth on `date`, or
+    /// `None` if no rate is known.
+    fn rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        date: NaiveDate,
+    ) -> BoxFuture<'_, Result<Option<Decimal>, FxRateError>>;
+}
+
+/// Rates held in memory. A rate applies from its date until the next one
+/// of the pair; the inverse pair is derived if only one direction is set.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryFxRates {
+    rates: HashMap<(&'static str, &'static str), BTreeMap<NaiveDate, Decimal>>,
+}
+
+impl InMemoryFxRates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the rate of the pair from `effective` on. Rates must be positive,
+    /// as the inverse pair is derived from them.
+    pub fn set_rate(
+        &mut self,
+        from: Currency,
+        to: Currency,
+        effective: NaiveDate,
+        rate: Decimal,
+    ) -> Result<&mut Self, BalanceError> {
+        if rate <= Decimal::ZERO {
+            return Err(BalanceError::NonPositiveFxRate(from, to, rate));
+        }
+        self.rates
+            .entry((from.code(), to.code()))
+            .or_default()
+            .insert(effective, rate);
+        Ok(self)
+    }
+
+    fn find(&self, from: Currency, to: Currency, date: NaiveDate) -> Option<(NaiveDate, Decimal)> {
+        self.rates
+            .get(&(from.code(), to.code()))
+            .and_then(|rates| rates.range(..=date).next_back())
+            .map(|(effective, rate)| (*effective, *rate))
+    }
+}
+
+impl FxRateProvider for InMemoryFxRates {
+    fn rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        date: NaiveDate,
+    ) -> BoxFuture<'_, Result<Option<Decimal>, FxRateError>> {
+        let direct = self.find(from, to, date);
+        let inverse = self.find(to, from, date);
+        let rate = match (direct, inverse) {
+            (Some((direct_date, rate)), Some((inverse_date, _))) if direct_date >= inverse_date => {
+                Some(rate)
+            }
+            (_, Some((_, rate))) => Some(Decimal::ONE / rate),
+            (Some((_, rate)), None) => Some(rate),
+            (None, None) => None,
+        };
+        futures::future::ready(Ok(rate)).boxed()
+    }
+}
+
+/// Rates stored in the `cala_fx_rates` table, the default provider of the
+/// ledger.
+#[derive(Debug, Clone)]
+pub struct DbFxRates {
+    pool: PgPool,
+}
+
+impl DbFxRates {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Record the rate of the pair from `effective` on, replacing any rate
+    /// recorded for that date.
+    pub async fn set_rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        effective: NaiveDate,
+        rate: Decimal,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_fx_rates (from_currency, to_currency, effective, rate)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (from_currency, to_currency, effective)
+            DO UPDATE SET rate = EXCLUDED.rate, created_at = NOW()
+            "#,
+            from.code(),
+            to.code(),
+            effective,
+            rate,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        date: NaiveDate,
+    ) -> Result<Option<Decimal>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT from_currency = $1 AS "direct!", rate
+            FROM cala_fx_rates
+            WHERE ((from_currency = $1 AND to_currency = $2)
+                OR (from_currency = $2 AND to_currency = $1))
+              AND effective <= $3
+            ORDER BY effective DESC, from_currency = $1 DESC
+            LIMIT 1
+            "#,
+            from.code(),
+            to.code(),
+            date,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| {
+            if row.direct {
+                row.rate
+            } else {
+                Decimal::ONE / row.rate
+            }
+        }))
+    }
+}
+
+impl FxRateProvider for DbFxRates {
+    fn rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        date: NaiveDate,
+    ) -> BoxFuture<'_, Result<Option<Decimal>, FxRateError>> {
+        self.find_rate(from, to, date)
+            .map(|res| res.map_err(FxRateError::from))
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_memory_rates_apply_until_replaced_and_invert() -> Result<(), FxRateError> {
+        let jan = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mid_jan = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        let feb = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        let mut rates = InMemoryFxRates::new();
+        rates
+            .set_rate(Currency::BTC, Currency::USD, jan, Decimal::from(40_000))?
+            .set_rate(Currency::USD, Currency::BTC, feb, Decimal::new(2, 5))?;
+
+        assert_eq!(
+            rates.rate(Currency::BTC, Currency::USD, mid_jan).await?,
+            Some(Decimal::from(40_000))
+        );
+        assert_eq!(
+            rates.rate(Currency::USD, Currency::BTC, mid_jan).await?,
+            Some(Decimal::ONE / Decimal::from(40_000))
+        );
+        assert_eq!(
+            rates.rate(Currency::BTC, Currency::USD, feb).await?,
+            Some(Decimal::from(50_000))
+        );
+        assert_eq!(
+            rates
+                .rate(Currency::BTC, Currency::USD, jan.pred_opt().unwrap())
+                .await?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn in_memory_rates_must_be_positive() {
+        let jan = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut rates = InMemoryFxRates::new();
+        for rate in [Decimal::ZERO, Decimal::NEGATIVE_ONE] {
+            assert!(matches!(
+                rates.set_rate(Currency::BTC, Currency::USD, jan, rate),
+                Err(BalanceError::NonPositiveFxRate(..))
+            ));
+        }
+        assert!(rates.find(Currency::BTC, Currency::USD, jan).is_none());
+    }
+}
@@ -0,0 +1,14 @@
+use cala_types::entry::EntryValues;
+
+use super::AccountBalance;
+
+/// One version of a balance, as listed by [`super::Balances::list_history`].
+#[derive(Debug, Clone)]
+pub struct BalanceHistoryItem {
+    /// The running balance after `entry` was applied.
+    pub balance: AccountBalance,
+    /// The entry that produced this version. For an account set this is the
+    /// member's entry that rolled up into it, and `None` when a member moving
+    /// in or out of the set produced it.
+    pub entry: Option<EntryValues>,
+}
@@ -0,0 +1,151 @@
+use rust_decimal::Decimal;
+
+use cala_types::{
+    balance::{BalanceAmount, BalanceSnapshot},
+    entry::EntryValues,
+    primitives::{DebitOrCredit, EntryId, Layer, TransactionId},
+};
+
+use super::snapshot::UNASSIGNED_ENTRY_ID;
+
+const MEMBER_MOVE_ENTRY_TYPE: &str = "MEMBER_MOVE";
+
+/// The entry id recorded by the ancestor balance versions a member move
+/// writes. No entry has it: such a version carries a member's whole balance
+/// rather than one entry, which is what history readers key on to tell it
+/// apart.
+pub(crate) const MEMBER_MOVE_ENTRY_ID: uuid::Uuid = uuid::Uuid::max();
+
+/// Whether a moved member's balance is taken out of an ancestor or added to
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum MoveDirection {
+    Out,
+    In,
+}
+
+/// Synthetic entries carrying the change of a member's balance from `from`
+/// (zero when `None`) to `to` into — or out of — its ancestors, so a move
+/// can reuse the snapshot folds the posting paths use.
+///
+/// Every layer and side moves separately, so the ancestors keep their
+/// debit and credit totals rather than only the net. Taking a balance out
+/// uses negative units on the original side. The entries carry
+/// [`MEMBER_MOVE_ENTRY_ID`], which the ancestors' new snapshots record.
+pub(super) fn delta_entries(
+    from: Option<&BalanceSnapshot>,
+    to: &BalanceSnapshot,
+    direction: MoveDirection,
+) -> Vec<EntryValues> {
+    let sign = match direction {
+        MoveDirection::Out => Decimal::NEGATIVE_ONE,
+        MoveDirection::In => Decimal::ONE,
+    };
+    let mut entries = Vec::new();
+    for layer in [Layer::Settled, Layer::Pending, Layer::Encumbrance] {
+        let before = from.map(|snapshot| layer_amount(snapshot, layer));
+        let after = layer_amount(to, layer);
+        let sides = [
+            (
+                DebitOrCredit::Debit,
+                after.dr_balance - before.map(|b| b.dr_balance).unwrap_or_default(),
+            ),
+            (
+                DebitOrCredit::Credit,
+                after.cr_balance - before.map(|b| b.cr_balance).unwrap_or_default(),
+            ),
+        ];
+        for (side, units) in sides {
+            if units.is_zero() {
+                continue;
+            }
+            entries.push(EntryValues {
+                id: EntryId::from(MEMBER_MOVE_ENTRY_ID),
+                version: 1,
+                transaction_id: TransactionId::from(UNASSIGNED_ENTRY_ID),
+                journal_id: to.journal_id,
+                account_id: to.account_id,
+                entry_type: MEMBER_MOVE_ENTRY_TYPE.to_string(),
+                sequence: entries.len() as u32 + 1,
+                layer,
+                units: units * sign,
+                currency: to.currency,
+                direction: side,
+                description: None,
+                metadata: None,
+            });
+        }
+    }
+    entries
+}
+
+fn layer_amount(snapshot: &BalanceSnapshot, layer: Layer) -> &BalanceAmount {
+    match layer {
+        Layer::Settled => &snapshot.settled,
+        Layer::Pending => &snapshot.pending,
+        Layer::Encumbrance => &snapshot.encumbrance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use cala_types::primitives::{AccountId, Currency, EntryId, JournalId};
+
+    use super::*;
+
+    fn snapshot(settled_dr: i64, settled_cr: i64, pending_dr: i64) -> BalanceSnapshot {
+        let now = Utc::now();
+        let amount = |dr: i64, cr: i64| BalanceAmount {
+            dr_balance: Decimal::from(dr),
+            cr_balance: Decimal::from(cr),
+            entry_id: EntryId::new(),
+            modified_at: now,
+        };
+        BalanceSnapshot {
+            journal_id: JournalId::new(),
+            account_id: AccountId::new(),
+            currency: Currency::USD,
+            version: 3,
+            created_at: now,
+            modified_at: now,
+            entry_id: EntryId::new(),
+            settled: amount(settled_dr, settled_cr),
+            pending: amount(pending_dr, 0),
+            encumbrance: amount(0, 0),
+        }
+    }
+
+    #[test]
+    fn moves_each_layer_and_side_separately() {
+        let balance = snapshot(100, 40, 5);
+        let entries = delta_entries(None, &balance, MoveDirection::In);
+        let moved: Vec<_> = entries
+            .iter()
+            .map(|e| (e.layer, e.direction, e.units))
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                (Layer::Settled, DebitOrCredit::Debit, Decimal::from(100)),
+                (Layer::Settled, DebitOrCredit::Credit, Decimal::from(40)),
+                (Layer::Pending, DebitOrCredit::Debit, Decimal::from(5)),
+            ]
+        );
+        assert!(entries
+            .iter()
+            .all(|e| e.id == EntryId::from(MEMBER_MOVE_ENTRY_ID)));
+    }
+
+    #[test]
+    fn takes_out_only_the_change_since_the_previous_snapshot() {
+        let before = snapshot(100, 40, 5);
+        let mut after = before.clone();
+        after.settled.cr_balance += Decimal::from(10);
+        let entries = delta_entries(Some(&before), &after, MoveDirection::Out);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].direction, DebitOrCredit::Credit);
+        assert_eq!(entries[0].units, Decimal::from(-10));
+    }
+}
@@ -35,15 +35,23 @@
 //! visibility its membership already exists.
 
 mod account_balance;
+mod consolidated;
 mod cursor;
 mod effective;
 pub mod error;
+mod fx;
+mod history;
+mod member_move;
 mod repo;
 mod snapshot;
 
 use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use sqlx::PgPool;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use tracing::instrument;
 
 pub use cala_types::{
@@ -52,14 +60,18 @@ pub use cala_types::{
 };
 use cala_types::{entry::EntryValues, primitives::*};
 
-use crate::{journal::Journals, primitives::JournalId};
+use crate::{entry::Entries, journal::Journals, primitives::JournalId};
 
 pub use account_balance::*;
+pub use consolidated::*;
 pub use cursor::*;
 #[cfg(feature = "fuzz")]
 pub use effective::fuzz_recalculate;
 use effective::*;
 use error::BalanceError;
+pub use fx::*;
+pub use history::*;
+pub(crate) use member_move::MEMBER_MOVE_ENTRY_ID;
 use repo::*;
 pub(crate) use snapshot::*;
 
@@ -76,16 +88,25 @@ pub(crate) struct EcRollupTxn {
 pub struct Balances {
     repo: BalanceRepo,
     journals: Journals,
+    entries: Entries,
     effective: EffectiveBalances,
+    fx_rates: Arc<dyn FxRateProvider>,
     _pool: PgPool,
 }
 
 impl Balances {
-    pub(crate) fn new(pool: &PgPool, journals: &Journals) -> Self {
+    pub(crate) fn new(
+        pool: &PgPool,
+        journals: &Journals,
+        entries: &Entries,
+        fx_rates: Arc<dyn FxRateProvider>,
+    ) -> Self {
         Self {
             repo: BalanceRepo::new(pool),
             effective: EffectiveBalances::new(pool),
             journals: journals.clone(),
+            entries: entries.clone(),
+            fx_rates,
             _pool: pool.clone(),
         }
     }
@@ -131,6 +152,127 @@ impl Balances {
         self.repo.find_all(ids).await
     }
 
+    /// The balance as it was recorded at `as_of`: the last snapshot whose
+    /// `modified_at` is not after it. Unlike the effective balances, which
+    /// are keyed by the business date of the transactions, this answers
+    /// what the ledger showed at a point in time. Account-set balances are
+    /// looked up by passing the set's id.
+    #[instrument(level = "debug", name = "cala_ledger.balance.find_as_of", skip(self))]
+    pub async fn find_as_of(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<AccountBalance, BalanceError> {
+        self.repo
+            .find_as_of(journal_id, account_id.into(), currency, as_of)
+            .await
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balance.find_as_of_in_op",
+        skip(self, op)
+    )]
+    pub async fn find_as_of_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<AccountBalance, BalanceError> {
+        self.repo
+            .find_as_of_in_op(op, journal_id, account_id.into(), currency, as_of)
+            .await
+    }
+
+    /// Batch variant of [`Self::find_as_of`]. Balances that had no snapshot
+    /// yet at `as_of` are absent from the result.
+    #[instrument(level = "debug", name = "cala_ledger.balance.find_all_as_of", skip(self, ids), fields(ids_count = ids.len()))]
+    pub async fn find_all_as_of(
+        &self,
+        ids: &[BalanceId],
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        self.repo.find_all_as_of(ids, as_of).await
+    }
+
+    #[instrument(level = "debug", name = "cala_ledger.balance.find_all_as_of_in_op", skip(self, op, ids), fields(ids_count = ids.len()))]
+    pub async fn find_all_as_of_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        ids: &[BalanceId],
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        self.repo.find_all_as_of_in_op(op, ids, as_of).await
+    }
+
+    /// The balances of an account in every currency as recorded at `as_of`
+    /// (see [`Self::find_as_of`]), converted into `currency` at the rates
+    /// the configured [`FxRateProvider`] has for the date of `as_of`.
+    ///
+    /// Both sides are keyed on `as_of`, not on effective dates: the
+    /// balances are those recorded by that instant, including entries
+    /// posted with an earlier or later effective date, and the rates are
+    /// those of its UTC calendar date.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balance.find_consolidated",
+        skip(self)
+    )]
+    pub async fn find_consolidated(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<ConsolidatedBalance, BalanceError> {
+        let account_id = account_id.into();
+        let balances = self
+            .repo
+            .find_all_currencies_as_of(journal_id, account_id, as_of)
+            .await?;
+        if balances.is_empty() {
+            return Err(BalanceError::NotFound(journal_id, account_id, currency));
+        }
+        let date = as_of.date_naive();
+        let mut components = Vec::with_capacity(balances.len());
+        for balance in balances {
+            let from = balance.details.currency;
+            let rate = if from == currency {
+                Decimal::ONE
+            } else {
+                self.fx_rates
+                    .rate(from, currency, date)
+                    .await
+                    .map_err(BalanceError::FxRateProvider)?
+                    .ok_or(BalanceError::FxRateNotFound(from, currency, date))?
+            };
+            components.push(ConsolidatedComponent { balance, rate });
+        }
+        Ok(ConsolidatedBalance {
+            journal_id,
+            account_id,
+            currency,
+            as_of,
+            components,
+        })
+    }
+
+    /// [`Self::find_consolidated`] for the balances an account set rolls up.
+    pub async fn find_consolidated_for_account_set(
+        &self,
+        journal_id: JournalId,
+        account_set_id: AccountSetId,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<ConsolidatedBalance, BalanceError> {
+        self.find_consolidated(journal_id, account_set_id, currency, as_of)
+            .await
+    }
+
     #[instrument(
         level = "debug",
         name = "cala_ledger.balance.list_for_account",
@@ -150,6 +292,56 @@ impl Balances {
             .await
     }
 
+    /// Every version of a balance, paginated on the snapshot version, each
+    /// with the entry that produced it — a running balance per entry, as on
+    /// a statement. Versions of an account set written by a member move have
+    /// no entry.
+    #[instrument(level = "debug", name = "cala_ledger.balance.list_history", skip(self))]
+    pub async fn list_history(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        currency: Currency,
+        args: es_entity::PaginatedQueryArgs<BalanceHistoryCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<es_entity::PaginatedQueryRet<BalanceHistoryItem, BalanceHistoryCursor>, BalanceError>
+    {
+        let balances = self
+            .repo
+            .list_history((journal_id, account_id.into(), currency), args, direction)
+            .await?;
+        let member_move = EntryId::from(MEMBER_MOVE_ENTRY_ID);
+        let entry_ids: Vec<_> = balances
+            .entities
+            .iter()
+            .map(|balance| balance.details.entry_id)
+            .filter(|entry_id| *entry_id != member_move)
+            .collect();
+        let mut entries = self.entries.find_all(&entry_ids).await?;
+        let entities = balances
+            .entities
+            .into_iter()
+            .map(|balance| {
+                let entry_id = balance.details.entry_id;
+                let entry = if entry_id == member_move {
+                    None
+                } else {
+                    let entry = entries
+                        .remove(&entry_id)
+                        .ok_or(BalanceError::EntryNotFound(entry_id))?;
+                    Some(entry.into_values())
+                };
+                Ok(BalanceHistoryItem { balance, entry })
+            })
+            .collect::<Result<Vec<_>, BalanceError>>()?;
+
+        Ok(es_entity::PaginatedQueryRet {
+            entities,
+            has_next_page: balances.has_next_page,
+            end_cursor: balances.end_cursor,
+        })
+    }
+
     #[instrument(level = "debug", name = "cala_ledger.balance.list_for_accounts", skip(self, account_ids), fields(account_ids_count = account_ids.len()))]
     pub async fn list_for_accounts(
         &self,
@@ -250,6 +442,176 @@ impl Balances {
             .await
     }
 
+    /// Of `account_ids`, the ones that are eventually consistent.
+    pub(crate) async fn find_eventually_consistent_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_ids: &[AccountId],
+    ) -> Result<HashSet<AccountId>, BalanceError> {
+        self.repo
+            .find_eventually_consistent_in_op(op, account_ids)
+            .await
+    }
+
+    /// Take the EXCLUSIVE EC-set lock on `account_ids`: no posting to
+    /// any of them is in flight once it returns, and none starts before
+    /// the op ends.
+    pub(crate) async fn lock_postings_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_ids: &[AccountId],
+    ) -> Result<(), BalanceError> {
+        self.repo.lock_exclusive_in_op(op, account_ids).await
+    }
+
+    /// Whether the streaming rollup has entries of `member_id` left to
+    /// apply, read off the history of `witness_ids` — EC accounts it folds
+    /// every entry of the member into. The caller holds the member's
+    /// EXCLUSIVE EC-set lock, so no new entries can join the backlog.
+    /// `member_of` is the set the member sits in below the witnesses, whose
+    /// history only holds the entries since it joined.
+    pub(crate) async fn ec_rollup_pending_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        member_id: AccountId,
+        member_of: Option<AccountSetId>,
+        witness_ids: &[AccountId],
+    ) -> Result<bool, BalanceError> {
+        self.repo
+            .ec_rollup_pending_in_op(op, journal_id, member_id, member_of, witness_ids)
+            .await
+    }
+
+    /// Move the balances of `member_id` out of the ancestor sets it leaves
+    /// (`removed_from`) and into the ones it joins (`added_to`) — its
+    /// current balances in full and, when the journal keeps them, its
+    /// cumulative effective balances from `effective` onwards. Returns the
+    /// latest ancestor balance written per `(set, currency)`.
+    ///
+    /// The caller holds the member's EXCLUSIVE EC-set lock (the membership
+    /// guard), so no posting to the member is in flight, and has made sure
+    /// the streaming rollup applied the member's entries (see
+    /// [`Self::ec_rollup_pending_in_op`]). The ancestor balances are then
+    /// locked against posters and the rollup
+    /// (`find_for_member_move_in_op`).
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balance.move_member_in_op",
+        skip(self, op, removed_from, added_to, eventually_consistent),
+        err(level = "warn")
+    )]
+    pub(crate) async fn move_member_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        member_id: AccountId,
+        removed_from: &[AccountSetId],
+        added_to: &[AccountSetId],
+        eventually_consistent: &HashSet<AccountId>,
+        effective: NaiveDate,
+        created_at: DateTime<Utc>,
+    ) -> Result<Vec<BalanceSnapshot>, BalanceError> {
+        let member_balances = self
+            .repo
+            .find_all_currencies_in_op(op, journal_id, member_id)
+            .await?;
+
+        let mut ec_set_ids: Vec<AccountId> = removed_from
+            .iter()
+            .chain(added_to.iter())
+            .map(AccountId::from)
+            .filter(|id| eventually_consistent.contains(id))
+            .collect();
+        ec_set_ids.sort_unstable();
+        let pairs: Vec<(AccountId, Currency)> = removed_from
+            .iter()
+            .chain(added_to.iter())
+            .flat_map(|id| {
+                member_balances
+                    .iter()
+                    .map(move |balance| (AccountId::from(id), balance.currency))
+            })
+            .collect();
+        let mut current_balances = self
+            .repo
+            .find_for_member_move_in_op(op, journal_id, &ec_set_ids, &pairs)
+            .await?;
+
+        let mut new_balances = Vec::new();
+        for (sets, direction) in [
+            (removed_from, member_move::MoveDirection::Out),
+            (added_to, member_move::MoveDirection::In),
+        ] {
+            let entries: Vec<EntryValues> = member_balances
+                .iter()
+                .flat_map(|balance| member_move::delta_entries(None, balance, direction))
+                .collect();
+            let set_balances = current_balances
+                .extract_if(|(account_id, _), _| {
+                    sets.iter().any(|id| AccountId::from(id) == *account_id)
+                })
+                .collect();
+            new_balances.extend(Snapshots::from_entries(
+                created_at,
+                set_balances,
+                &entries,
+                &HashMap::from([(member_id, sets.to_vec())]),
+            ));
+        }
+        if !new_balances.is_empty() {
+            self.repo
+                .insert_new_snapshots(op, journal_id, new_balances.clone())
+                .await?;
+        }
+
+        let journal = self.journals.find_in_op(&mut *op, journal_id).await?;
+        if journal.insert_effective_balances() {
+            self.effective
+                .move_member_in_op(
+                    op,
+                    journal_id,
+                    member_id,
+                    effective,
+                    created_at,
+                    removed_from,
+                    added_to,
+                )
+                .await?;
+        }
+
+        let mut latest: HashMap<(AccountId, Currency), BalanceSnapshot> = HashMap::new();
+        for snapshot in new_balances {
+            match latest.get(&(snapshot.account_id, snapshot.currency)) {
+                Some(existing) if existing.version >= snapshot.version => {}
+                _ => {
+                    latest.insert((snapshot.account_id, snapshot.currency), snapshot);
+                }
+            }
+        }
+        Ok(latest.into_values().collect())
+    }
+
+    /// The eventually-consistent sets `account_id` currently rolls up into.
+    pub(crate) async fn find_ec_ancestors(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+    ) -> Result<Vec<AccountId>, BalanceError> {
+        self.repo.find_ec_ancestors(journal_id, account_id).await
+    }
+
+    /// The balance versions `entry` produced, one per balance it stepped.
+    pub(crate) async fn find_snapshots_for_entry(
+        &self,
+        entry: &EntryValues,
+    ) -> Result<Vec<BalanceSnapshot>, BalanceError> {
+        self.repo
+            .find_snapshots_for_entry(entry.journal_id, entry.currency, entry.id)
+            .await
+    }
+
     /// Streaming EC rollup for a batch of committed transactions.
     ///
     /// Mirror of [`Self::update_balances_in_op`] but for the ancestor
@@ -292,6 +654,43 @@ impl Balances {
         Ok(())
     }
 
+    /// Fold earlier entries of `member_id` into the EC sets it joined with
+    /// a backfill (see [`crate::account_set::AccountSets::add_member_with_backfill`]).
+    ///
+    /// The fold of [`Self::apply_ec_rollup_in_op`], routed to `set_ids`
+    /// only: the member's other ancestors already hold these entries. The
+    /// sets are locked EXCLUSIVE first, serializing with the streaming
+    /// rollup, which keeps folding the member's new entries into them.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balance.apply_ec_backfill_in_op",
+        skip(self, op, set_ids, txns),
+        fields(txns_count = txns.len()),
+        err(level = "warn")
+    )]
+    pub(crate) async fn apply_ec_backfill_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        member_id: AccountId,
+        set_ids: &[AccountSetId],
+        txns: Vec<EcRollupTxn>,
+    ) -> Result<(), BalanceError> {
+        if set_ids.is_empty() || txns.is_empty() {
+            return Ok(());
+        }
+        let ec_set_ids: Vec<AccountId> = set_ids.iter().map(AccountId::from).collect();
+        self.repo.lock_exclusive_in_op(op, &ec_set_ids).await?;
+        self.fold_ec_group_in_op(
+            op,
+            journal_id,
+            txns,
+            HashMap::from([(member_id, set_ids.to_vec())]),
+            HashSet::new(),
+        )
+        .await
+    }
+
     async fn apply_ec_rollup_group_in_op(
         &self,
         op: &mut impl es_entity::AtomicOperation,
@@ -318,6 +717,21 @@ impl Balances {
             return Ok(());
         }
 
+        self.fold_ec_group_in_op(op, journal_id, group, ec_mappings, ec_leaves)
+            .await
+    }
+
+    /// Fold a group of one journal's transactions into the EC balances
+    /// `ec_mappings` routes their entries to, plus the own balances of
+    /// `ec_leaves`.
+    async fn fold_ec_group_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        group: Vec<EcRollupTxn>,
+        ec_mappings: HashMap<AccountId, Vec<AccountSetId>>,
+        ec_leaves: HashSet<AccountId>,
+    ) -> Result<(), BalanceError> {
         let empty = Vec::new();
         let mut involved: HashSet<(AccountId, Currency)> = HashSet::new();
         for entry in group.iter().flat_map(|tx| tx.entries.iter()) {
@@ -1,3 +1,4 @@
+use chrono::{DateTime, SubsecRound, Utc};
 use sqlx::PgPool;
 use tracing::instrument;
 
@@ -12,7 +13,7 @@ use cala_types::{
 
 use super::{
     account_balance::AccountBalance,
-    cursor::{AccountBalanceByCurrencyCursor, AccountBalanceCursor},
+    cursor::{AccountBalanceByCurrencyCursor, AccountBalanceCursor, BalanceHistoryCursor},
     error::BalanceError,
 };
 
@@ -25,6 +26,14 @@ const EC_SET_LOCK_CLASS: i32 = 1;
 /// statement's working set small so it cannot OOM-crash a Postgres backend.
 const INSERT_SNAPSHOT_BATCH_SIZE: usize = 5_000;
 
+/// `modified_at` is stored with nanosecond precision inside the snapshot
+/// JSON, which Postgres rounds to microseconds when casting it for
+/// comparison. Rounding the bound the same way keeps a lookup at exactly a
+/// snapshot's `modified_at` from missing that snapshot.
+fn round_to_postgres_precision(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.round_subsecs(6)
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct BalanceRepo {
     pool: PgPool,
@@ -185,6 +194,278 @@ impl BalanceRepo {
         Ok(ret)
     }
 
+    pub(super) async fn list_history(
+        &self,
+        id: BalanceId,
+        args: es_entity::PaginatedQueryArgs<BalanceHistoryCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<es_entity::PaginatedQueryRet<AccountBalance, BalanceHistoryCursor>, BalanceError>
+    {
+        self.list_history_in_op(&self.pool, id, args, direction)
+            .await
+    }
+
+    pub(super) async fn find_as_of(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<AccountBalance, BalanceError> {
+        self.find_as_of_in_op(&self.pool, journal_id, account_id, currency, as_of)
+            .await
+    }
+
+    pub(super) async fn find_all_as_of(
+        &self,
+        ids: &[BalanceId],
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        self.find_all_as_of_in_op(&self.pool, ids, as_of).await
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "balance.find_as_of_in_op",
+        skip_all,
+        err(level = "warn")
+    )]
+    pub(super) async fn find_as_of_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<AccountBalance, BalanceError> {
+        let row = op
+            .into_executor()
+            .fetch_optional(sqlx::query!(
+                r#"
+                SELECT h.values AS "values!", a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+                FROM cala_balance_history h
+                JOIN cala_accounts a
+                    ON h.account_id = a.id
+                WHERE h.journal_id = $1
+                  AND h.account_id = $2
+                  AND h.currency = $3
+                  AND (h.values->>'modified_at')::timestamptz <= $4
+                ORDER BY h.version DESC
+                LIMIT 1
+                "#,
+                journal_id as JournalId,
+                account_id as AccountId,
+                currency.code(),
+                round_to_postgres_precision(as_of),
+            ))
+            .await?;
+
+        if let Some(row) = row {
+            let details: BalanceSnapshot =
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot");
+            Ok(AccountBalance::new(row.normal_balance_type, details))
+        } else {
+            Err(BalanceError::NotFound(journal_id, account_id, currency))
+        }
+    }
+
+    /// The last snapshot recorded by `as_of` of each currency of an account.
+    #[instrument(
+        level = "debug",
+        name = "balance.find_all_currencies_as_of",
+        skip(self),
+        err(level = "warn")
+    )]
+    pub(super) async fn find_all_currencies_as_of(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountBalance>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT ON (h.currency)
+                h.values AS "values!",
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+            FROM cala_balance_history h
+            JOIN cala_accounts a
+                ON h.account_id = a.id
+            WHERE h.journal_id = $1
+              AND h.account_id = $2
+              AND (h.values->>'modified_at')::timestamptz <= $3
+            ORDER BY h.currency, h.version DESC
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            round_to_postgres_precision(as_of),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let details: BalanceSnapshot = serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot");
+                AccountBalance::new(row.normal_balance_type, details)
+            })
+            .collect())
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "balance.find_all_as_of_in_op",
+        skip_all,
+        err(level = "warn")
+    )]
+    pub(super) async fn find_all_as_of_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        ids: &[BalanceId],
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        let mut journal_ids = Vec::with_capacity(ids.len());
+        let mut account_ids = Vec::with_capacity(ids.len());
+        let mut currencies = Vec::with_capacity(ids.len());
+        for (journal_id, account_id, currency) in ids {
+            journal_ids.push(uuid::Uuid::from(journal_id));
+            account_ids.push(uuid::Uuid::from(account_id));
+            currencies.push(currency.code().to_string());
+        }
+
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+                r#"
+                WITH balance_ids AS (
+                    SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::text[])
+                    AS v(journal_id, account_id, currency)
+                )
+                SELECT
+                    h.values AS "values!",
+                    a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+                FROM balance_ids b
+                JOIN LATERAL (
+                    SELECT values
+                    FROM cala_balance_history
+                    WHERE journal_id = b.journal_id
+                      AND account_id = b.account_id
+                      AND currency = b.currency
+                      AND (values->>'modified_at')::timestamptz <= $4
+                    ORDER BY version DESC
+                    LIMIT 1
+                ) h ON TRUE
+                JOIN cala_accounts a
+                    ON b.account_id = a.id"#,
+                &journal_ids[..],
+                &account_ids[..],
+                &currencies[..],
+                round_to_postgres_precision(as_of),
+            ))
+            .await?;
+
+        let mut ret = HashMap::new();
+        for row in rows {
+            let details: BalanceSnapshot =
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot");
+            ret.insert(
+                (details.journal_id, details.account_id, details.currency),
+                AccountBalance::new(row.normal_balance_type, details),
+            );
+        }
+        Ok(ret)
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "balance.list_history_in_op",
+        skip_all,
+        err(level = "warn")
+    )]
+    pub(super) async fn list_history_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        (journal_id, account_id, currency): BalanceId,
+        args: es_entity::PaginatedQueryArgs<BalanceHistoryCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<es_entity::PaginatedQueryRet<AccountBalance, BalanceHistoryCursor>, BalanceError>
+    {
+        let es_entity::PaginatedQueryArgs { first, after } = args;
+        let after_version = after.map(|cursor| cursor.version as i32);
+
+        let executor = op.into_executor();
+        let values = match direction {
+            es_entity::ListDirection::Ascending => executor
+                .fetch_all(sqlx::query!(
+                    r#"
+                    SELECT
+                        h.values AS "values!",
+                        a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+                    FROM cala_balance_history h
+                    JOIN cala_accounts a
+                        ON h.account_id = a.id
+                    WHERE h.journal_id = $1
+                      AND h.account_id = $2
+                      AND h.currency = $3
+                      AND ($4::int4 IS NULL OR h.version > $4)
+                    ORDER BY h.version ASC
+                    LIMIT $5"#,
+                    journal_id as JournalId,
+                    account_id as AccountId,
+                    currency.code(),
+                    after_version,
+                    (first + 1) as i64,
+                ))
+                .await?
+                .into_iter()
+                .map(|row| (row.values, row.normal_balance_type))
+                .collect::<Vec<_>>(),
+            es_entity::ListDirection::Descending => executor
+                .fetch_all(sqlx::query!(
+                    r#"
+                    SELECT
+                        h.values AS "values!",
+                        a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+                    FROM cala_balance_history h
+                    JOIN cala_accounts a
+                        ON h.account_id = a.id
+                    WHERE h.journal_id = $1
+                      AND h.account_id = $2
+                      AND h.currency = $3
+                      AND ($4::int4 IS NULL OR h.version < $4)
+                    ORDER BY h.version DESC
+                    LIMIT $5"#,
+                    journal_id as JournalId,
+                    account_id as AccountId,
+                    currency.code(),
+                    after_version,
+                    (first + 1) as i64,
+                ))
+                .await?
+                .into_iter()
+                .map(|row| (row.values, row.normal_balance_type))
+                .collect::<Vec<_>>(),
+        };
+
+        let has_next_page = values.len() > first;
+        let entities = values
+            .into_iter()
+            .take(first)
+            .map(|(values, normal_balance_type)| {
+                let details: BalanceSnapshot =
+                    serde_json::from_value(values).expect("Failed to deserialize balance snapshot");
+                AccountBalance::new(normal_balance_type, details)
+            })
+            .collect::<Vec<_>>();
+        let end_cursor = entities.last().map(BalanceHistoryCursor::from);
+
+        Ok(es_entity::PaginatedQueryRet {
+            entities,
+            has_next_page,
+            end_cursor,
+        })
+    }
+
     #[instrument(
         level = "debug",
         name = "balance.list_for_account_in_op",
@@ -553,6 +834,83 @@ impl BalanceRepo {
         Ok(())
     }
 
+    /// The eventually-consistent sets `account_id` currently rolls up into
+    /// in `journal_id`: the balances of its entries the streaming rollup
+    /// rather than the poster writes.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balances.find_ec_ancestors",
+        skip_all
+    )]
+    pub(crate) async fn find_ec_ancestors(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+    ) -> Result<Vec<AccountId>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT m.account_set_id
+                FROM cala_account_set_member_accounts m
+                WHERE m.member_account_id = $2
+                UNION
+                SELECT e.account_set_id
+                FROM ancestors a
+                JOIN cala_account_set_member_account_sets e
+                  ON e.member_account_set_id = a.account_set_id
+            )
+            SELECT acc.id AS "id!: AccountId"
+            FROM ancestors a
+            JOIN cala_account_sets s
+              ON s.id = a.account_set_id AND s.journal_id = $1
+            JOIN cala_accounts acc
+              ON acc.id = a.account_set_id AND acc.eventually_consistent
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// The versions `entry_id` produced: one for each balance the entry
+    /// stepped, read off the history so that sets the account has left
+    /// since are included and sets it joined later are not.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balances.find_snapshots_for_entry",
+        skip_all
+    )]
+    pub(crate) async fn find_snapshots_for_entry(
+        &self,
+        journal_id: JournalId,
+        currency: Currency,
+        entry_id: EntryId,
+    ) -> Result<Vec<BalanceSnapshot>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT ON (account_id) values AS "values!"
+            FROM cala_balance_history
+            WHERE latest_entry_id = $3
+              AND journal_id = $1
+              AND currency = $2
+            ORDER BY account_id, version DESC
+            "#,
+            journal_id as JournalId,
+            currency.code(),
+            entry_id as EntryId,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot")
+            })
+            .collect())
+    }
+
     /// For each of `account_ids`, the **eventually-consistent** ancestor
     /// account sets that own it — the streaming rollup's targets. Mirrors
     /// the inline `AccountSetRepo::fetch_mappings_in_op` but keeps only EC
@@ -723,4 +1081,224 @@ impl BalanceRepo {
         }
         Ok(ret)
     }
+
+    /// Of `account_ids`, the ones that are eventually consistent — account
+    /// sets and plain accounts alike.
+    pub(super) async fn find_eventually_consistent_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_ids: &[AccountId],
+    ) -> Result<HashSet<AccountId>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT a.id AS "id!: AccountId"
+            FROM cala_accounts a
+            WHERE a.id = ANY($1)
+              AND a.eventually_consistent = TRUE
+            "#,
+            account_ids as &[AccountId],
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// Whether any entry of `member_id` is missing from the history of one
+    /// of the EC `witness_ids` — the accounts the streaming rollup folds
+    /// that member's entries into. The rollup writes one history row per
+    /// folded entry (`latest_entry_id`), so an entry without a row in every
+    /// witness has not been rolled up yet.
+    ///
+    /// Entries from before the member last joined `member_of` reached the
+    /// sets above it by a member move, as a whole balance rather than one
+    /// row per entry, and only count for the member itself.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balances.ec_rollup_pending_in_op",
+        skip_all,
+        err(level = "warn")
+    )]
+    pub(super) async fn ec_rollup_pending_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        member_id: AccountId,
+        member_of: Option<AccountSetId>,
+        witness_ids: &[AccountId],
+    ) -> Result<bool, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            WITH joined AS (
+                SELECT MAX(mh.joined_at) AS joined_at
+                FROM cala_account_set_member_history mh
+                WHERE mh.account_set_id = $4
+                  AND mh.member_id = $2
+            )
+            SELECT EXISTS (
+                SELECT 1
+                FROM cala_entries e
+                CROSS JOIN UNNEST($3::uuid[]) AS w(account_id)
+                CROSS JOIN joined j
+                WHERE e.journal_id = $1
+                  AND e.account_id = $2
+                  AND (w.account_id = $2 OR j.joined_at IS NULL OR e.created_at > j.joined_at)
+                  AND NOT EXISTS (
+                    SELECT 1 FROM cala_balance_history h
+                    WHERE h.journal_id = $1
+                      AND h.account_id = w.account_id
+                      AND h.latest_entry_id = e.id
+                  )
+            ) AS "pending!"
+            "#,
+            journal_id as JournalId,
+            member_id as AccountId,
+            witness_ids as &[AccountId],
+            member_of as Option<AccountSetId>,
+        )
+        .fetch_one(op.as_executor())
+        .await?;
+        Ok(row.pending)
+    }
+
+    /// The current balances of `account_id` in every currency.
+    pub(super) async fn find_all_currencies_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+    ) -> Result<Vec<BalanceSnapshot>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT latest_values
+            FROM cala_current_balances
+            WHERE journal_id = $1 AND account_id = $2
+            ORDER BY currency
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                serde_json::from_value::<BalanceSnapshot>(row.latest_values)
+                    .expect("Failed to deserialize balance snapshot")
+            })
+            .collect())
+    }
+
+    /// Take the EC-set lock on `account_ids` EXCLUSIVE, in canonical order.
+    /// On EC sets it keeps out the streaming rollup, which holds it SHARED
+    /// while it writes them (`find_ec_balances_for_update`); on leaf
+    /// accounts it keeps out posters, which hold it SHARED from their
+    /// first statement.
+    pub(super) async fn lock_exclusive_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_ids: &[AccountId],
+    ) -> Result<(), BalanceError> {
+        sqlx::query!(
+            r#"
+            SELECT pg_advisory_xact_lock($1::int4, hashtext(v.account_id::text))
+            FROM UNNEST($2::uuid[]) AS v(account_id)
+            ORDER BY v.account_id
+            "#,
+            EC_SET_LOCK_CLASS,
+            account_ids as &[AccountId],
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    /// Lock and read the ancestor balances a member move rewrites.
+    ///
+    /// EC sets (`ec_set_ids`) are locked EXCLUSIVE in the EC-set class so
+    /// the move serializes with the streaming rollup, which writes them
+    /// under SHARED (`find_ec_balances_for_update`). Every other pair takes
+    /// the poster's per-balance lock, in one Rust-sorted batch like
+    /// `AccountSetRepo::lock_resolved_ancestors_in_op`. The EC-class batch
+    /// always comes first; posters never wait on an EC-set key, so the two
+    /// batches cannot close a cycle with them.
+    #[instrument(
+        level = "debug",
+        name = "cala_ledger.balances.find_for_member_move_in_op",
+        skip_all,
+        err(level = "warn")
+    )]
+    pub(super) async fn find_for_member_move_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        ec_set_ids: &[AccountId],
+        pairs: &[(AccountId, Currency)],
+    ) -> Result<HashMap<(AccountId, Currency), Option<BalanceSnapshot>>, BalanceError> {
+        self.lock_exclusive_in_op(op, ec_set_ids).await?;
+
+        let mut sync_pairs: Vec<(AccountId, &str)> = pairs
+            .iter()
+            .filter(|(account_id, _)| !ec_set_ids.contains(account_id))
+            .map(|(account_id, currency)| (*account_id, currency.code()))
+            .collect();
+        sync_pairs.sort_unstable();
+        sync_pairs.dedup();
+        let (sync_ids, sync_currencies): (Vec<AccountId>, Vec<&str>) =
+            sync_pairs.into_iter().unzip();
+        sqlx::query!(
+            r#"
+            SELECT pg_advisory_xact_lock(
+                hashtext(concat($1::text, v.account_id::text, v.currency))
+            )
+            FROM UNNEST($2::uuid[], $3::text[]) AS v(account_id, currency)
+            "#,
+            journal_id as JournalId,
+            &sync_ids as &[AccountId],
+            &sync_currencies as &[&str],
+        )
+        .execute(op.as_executor())
+        .await?;
+
+        let (account_ids, currencies): (Vec<AccountId>, Vec<&str>) = pairs
+            .iter()
+            .map(|(account_id, currency)| (*account_id, currency.code()))
+            .unzip();
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                v.account_id AS "account_id!: AccountId",
+                v.currency AS "currency!",
+                b.latest_values AS "latest_values?"
+            FROM UNNEST($2::uuid[], $3::text[]) AS v(account_id, currency)
+            LEFT JOIN cala_current_balances b
+                ON b.journal_id = $1
+                AND b.account_id = v.account_id
+                AND b.currency = v.currency
+            "#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str],
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let snapshot = row.latest_values.map(|v| {
+                    serde_json::from_value::<BalanceSnapshot>(v)
+                        .expect("Failed to deserialize balance snapshot")
+                });
+                (
+                    (
+                        row.account_id,
+                        row.currency.parse().expect("Could not parse currency"),
+                    ),
+                    snapshot,
+                )
+            })
+            .collect())
+    }
 }
@@ -0,0 +1,184 @@
+//! Live balance updates derived from the outbox.
+//!
+//! Every `EntryCreated` event is resolved to the balances the entry stepped
+//! — its account's and each set it rolled up into at the time, as recorded
+//! in the balance history — and the version the entry produced for each
+//! balance the subscriber asked for is emitted. Reading
+//! the version by entry rather than the current balance means a subscriber
+//! replaying from an old [`EventSequence`] sees the balances as they were
+//! at each event, not the latest one repeated.
+//!
+//! Balances maintained inline by the poster are committed with the entry,
+//! so they are readable as soon as the event is. Eventually-consistent ones
+//! are only written once the streaming rollup (see [`crate::ec_rollup`])
+//! has applied the event, so their update is held until then.
+//!
+//! A member move (`AccountSetMemberMoved`) carries the ancestor balances it
+//! rewrote, which are emitted as they are. Those versions record no entry
+//! (`MEMBER_MOVE_ENTRY_ID`), so no `EntryCreated` event resolves to them.
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use obix::{
+    out::{PersistentOutboxEvent, RegisteredEventHandler},
+    EventSequence,
+};
+
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use cala_types::balance::BalanceSnapshot;
+
+use crate::{
+    balance::Balances,
+    ledger::error::LedgerError,
+    outbox::{CalaMailboxTables, OutboxEventPayload},
+    primitives::{AccountId, AccountSetId, JournalId},
+};
+
+/// How long an update of an eventually-consistent balance waits for the
+/// rollup before it is surfaced as [`LedgerError::EcCaughtUpTimeout`].
+const EC_ROLLUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The balances a subscription follows.
+#[derive(Debug, Clone)]
+pub enum BalanceSubscriptionFilter {
+    /// Every balance in the journal, account sets included.
+    Journal(JournalId),
+    Accounts {
+        journal_id: JournalId,
+        account_ids: Vec<AccountId>,
+    },
+    AccountSets {
+        journal_id: JournalId,
+        account_set_ids: Vec<AccountSetId>,
+    },
+}
+
+impl BalanceSubscriptionFilter {
+    fn journal_id(&self) -> JournalId {
+        match self {
+            Self::Journal(journal_id) => *journal_id,
+            Self::Accounts { journal_id, .. } | Self::AccountSets { journal_id, .. } => *journal_id,
+        }
+    }
+
+    fn account_ids(&self) -> Option<HashSet<AccountId>> {
+        match self {
+            Self::Journal(_) => None,
+            Self::Accounts { account_ids, .. } => Some(account_ids.iter().copied().collect()),
+            Self::AccountSets {
+                account_set_ids, ..
+            } => Some(account_set_ids.iter().map(AccountId::from).collect()),
+        }
+    }
+}
+
+/// A new version of a followed balance.
+#[derive(Debug, Clone)]
+pub struct BalanceUpdate {
+    /// The outbox position of the entry that produced the version — pass it
+    /// as `start_after` to resume after a reconnect.
+    pub sequence: EventSequence,
+    pub recorded_at: DateTime<Utc>,
+    pub snapshot: BalanceSnapshot,
+}
+
+pub(crate) fn subscribe(
+    events: impl Stream<
+            Item = Result<
+                Arc<PersistentOutboxEvent<OutboxEventPayload>>,
+                obix::out::UndecodableEventError,
+            >,
+        > + Send
+        + 'static,
+    balances: &Balances,
+    ec_rollup: &RegisteredEventHandler<OutboxEventPayload, CalaMailboxTables>,
+    filter: BalanceSubscriptionFilter,
+) -> impl Stream<Item = Result<BalanceUpdate, LedgerError>> + Send + 'static {
+    let subscription = Arc::new(BalanceSubscription {
+        balances: balances.clone(),
+        ec_rollup: ec_rollup.clone(),
+        journal_id: filter.journal_id(),
+        account_ids: filter.account_ids(),
+    });
+    events
+        .filter_map(|event| async move {
+            event
+                .inspect_err(|e| tracing::warn!(error = %e, "skipping undecodable outbox event"))
+                .ok()
+        })
+        .then(move |event| {
+            let subscription = Arc::clone(&subscription);
+            async move { subscription.updates_for(&event).await }
+        })
+        .flat_map(|updates| {
+            futures::stream::iter(match updates {
+                Ok(updates) => updates.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            })
+        })
+}
+
+struct BalanceSubscription {
+    balances: Balances,
+    ec_rollup: RegisteredEventHandler<OutboxEventPayload, CalaMailboxTables>,
+    journal_id: JournalId,
+    /// `None` follows every balance in the journal.
+    account_ids: Option<HashSet<AccountId>>,
+}
+
+impl BalanceSubscription {
+    async fn updates_for(
+        &self,
+        event: &PersistentOutboxEvent<OutboxEventPayload>,
+    ) -> Result<Vec<BalanceUpdate>, LedgerError> {
+        let entry = match &event.payload {
+            Some(OutboxEventPayload::EntryCreated { entry }) => entry,
+            Some(OutboxEventPayload::AccountSetMemberMoved {
+                journal_id,
+                balances,
+                ..
+            }) if *journal_id == self.journal_id => {
+                return Ok(balances
+                    .iter()
+                    .filter(|snapshot| {
+                        self.account_ids
+                            .as_ref()
+                            .is_none_or(|ids| ids.contains(&snapshot.account_id))
+                    })
+                    .map(|snapshot| BalanceUpdate {
+                        sequence: event.sequence,
+                        recorded_at: event.recorded_at,
+                        snapshot: snapshot.clone(),
+                    })
+                    .collect());
+            }
+            _ => return Ok(Vec::new()),
+        };
+        if entry.journal_id != self.journal_id {
+            return Ok(Vec::new());
+        }
+
+        let follows = |id: &AccountId| self.account_ids.as_ref().is_none_or(|ids| ids.contains(id));
+        let ec_ancestors = self
+            .balances
+            .find_ec_ancestors(entry.journal_id, entry.account_id)
+            .await?;
+        if ec_ancestors.iter().any(follows) {
+            self.ec_rollup
+                .await_sequence(event.sequence, EC_ROLLUP_TIMEOUT)
+                .await?;
+        }
+
+        let snapshots = self.balances.find_snapshots_for_entry(entry).await?;
+        Ok(snapshots
+            .into_iter()
+            .filter(|snapshot| follows(&snapshot.account_id))
+            .map(|snapshot| BalanceUpdate {
+                sequence: event.sequence,
+                recorded_at: event.recorded_at,
+                snapshot,
+            })
+            .collect())
+    }
+}
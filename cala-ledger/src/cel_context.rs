@@ -1,10 +1,30 @@
-pub use cel_interpreter::CelContext;
+pub use cel_interpreter::{CelContext, CelEvaluationLimits, CelFunctionRegistry};
+use cel_interpreter::{CelExpression, CelType, CelTypeEnv, CelTypeError};
 use es_entity::clock::ClockHandle;
 use tracing::instrument;
 
 #[instrument(level = "debug", name = "cel_context.initialize", skip(clock))]
 pub(crate) fn initialize(clock: ClockHandle) -> CelContext {
+    initialize_with_functions(clock, &CelFunctionRegistry::default(), None)
+}
+
+/// Like [`initialize`], additionally exposing the application functions and
+/// enforcing the evaluation limits configured via [`crate::CalaLedgerConfig`].
+#[instrument(
+    level = "debug",
+    name = "cel_context.initialize_with_functions",
+    skip_all
+)]
+pub(crate) fn initialize_with_functions(
+    clock: ClockHandle,
+    functions: &CelFunctionRegistry,
+    limits: Option<&CelEvaluationLimits>,
+) -> CelContext {
     let mut ctx = CelContext::new_with_clock(clock);
+    ctx.add_functions(functions);
+    if let Some(limits) = limits {
+        ctx.set_evaluation_limits(limits.clone());
+    }
     ctx.add_variable("SETTLED", "SETTLED");
     ctx.add_variable("PENDING", "PENDING");
     ctx.add_variable("ENCUMBRANCE", "ENCUMBRANCE");
@@ -12,3 +32,44 @@ pub(crate) fn initialize(clock: ClockHandle) -> CelContext {
     ctx.add_variable("CREDIT", "CREDIT");
     ctx
 }
+
+/// The type environment matching [`initialize_with_functions`], for
+/// checking expressions before they are stored. Callers add the variables
+/// they bind on top (`params`, `context`).
+pub(crate) fn type_env(functions: &CelFunctionRegistry) -> CelTypeEnv {
+    let mut env = CelTypeEnv::new();
+    functions.declare(&mut env);
+    for name in ["SETTLED", "PENDING", "ENCUMBRANCE", "DEBIT", "CREDIT"] {
+        env.add_variable(name, CelType::String);
+    }
+    env
+}
+
+/// The first of `expressions` that fails to type-check in `env` or whose
+/// result is not usable as its paired type, together with the problems
+/// found.
+pub(crate) fn find_ill_typed<'a>(
+    env: &CelTypeEnv,
+    expressions: impl IntoIterator<Item = (&'a str, CelType)>,
+) -> Option<(String, Vec<CelTypeError>)> {
+    expressions.into_iter().find_map(|(source, expected)| {
+        let errors = CelExpression::try_from(source)
+            .ok()?
+            .check_as(env, expected)
+            .err()?;
+        Some((source.to_string(), errors))
+    })
+}
+
+/// The first of `sources` whose estimated evaluation cost exceeds
+/// `max_cost`, together with that cost.
+pub(crate) fn find_too_expensive<'a>(
+    max_cost: Option<u64>,
+    sources: impl IntoIterator<Item = &'a str>,
+) -> Option<(String, u64)> {
+    let max_cost = max_cost?;
+    sources.into_iter().find_map(|source| {
+        let cost = CelExpression::try_from(source).ok()?.estimated_cost();
+        (cost > max_cost).then(|| (source.to_string(), cost))
+    })
+}
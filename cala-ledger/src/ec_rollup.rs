@@ -41,7 +41,16 @@
 //! - **No membership trigger.** A member can only join/leave an EC set
 //!   while it has no balance history (`MemberHasBalanceHistory`), so
 //!   membership carries no balance to seed/unfold — the live closure alone
-//!   routes future entries.
+//!   routes future entries. The exceptions carry the history explicitly:
+//!   `move_member` moves it in the same transaction, and
+//!   `add_member_with_backfill` hands it to a backfill job
+//!   ([`crate::account_set`]) that writes the set under the EXCLUSIVE
+//!   EC-set lock. Both first make sure this job applied the member's
+//!   entries, so none is folded in twice.
+//! - **Policy changes.** `update_balance_rollup` flips a set between
+//!   synchronous and EC with every posting below it held off and only
+//!   once this job has caught up, so each transaction reaches the set
+//!   exactly once — inline or here, never both or neither.
 
 use chrono::{DateTime, NaiveDate, Utc};
 
@@ -59,6 +68,7 @@ use obix::{
 use cala_types::entry::EntryValues;
 
 use crate::{
+    account_set::AccountSetBackfillRepo,
     balance::{Balances, EcRollupTxn},
     entry::{Entries, Entry},
     ledger::error::LedgerError,
@@ -306,30 +316,45 @@ pub use __fuzz::fuzz_batch;
 /// `frontier` is pinned at construction. [`refresh`](Self::refresh) advances
 /// `applied` against that same fence, so [`lag`](Self::lag) drains toward it
 /// instead of chasing a frontier that new postings keep moving.
+///
+/// Backfills of members that joined EC sets with their history
+/// (`add_member_with_backfill`) run outside the stream, so they are counted
+/// separately: a caught-up rollup still leaves backfilling sets incomplete
+/// until `backfills_pending` drops to zero.
 #[derive(Debug, Clone)]
 pub struct EcRollupStatus {
     /// The rollup job's committed checkpoint.
     pub applied: EventSequence,
     /// The outbox frontier pinned when this snapshot was taken.
     pub frontier: EventSequence,
+    /// Member backfills not yet complete.
+    pub backfills_pending: u64,
+    /// Entries those backfills have left to replay.
+    pub backfill_entries_pending: u64,
     handle: RegisteredEventHandler<OutboxEventPayload, CalaMailboxTables>,
+    backfills: AccountSetBackfillRepo,
 }
 
 impl EcRollupStatus {
-    pub(crate) fn new(
-        status: HandlerStreamStatus,
+    pub(crate) async fn load(
         handle: RegisteredEventHandler<OutboxEventPayload, CalaMailboxTables>,
-    ) -> Self {
-        Self {
+        backfills: AccountSetBackfillRepo,
+    ) -> Result<Self, LedgerError> {
+        let status: HandlerStreamStatus = handle.load().await?.stream_status();
+        let (backfills_pending, backfill_entries_pending) = backfills.count_pending().await?;
+        Ok(Self {
             applied: status.checkpoint,
             frontier: status.frontier,
+            backfills_pending,
+            backfill_entries_pending,
             handle,
-        }
+            backfills,
+        })
     }
 
-    /// Re-read the committed checkpoint, keeping the pinned `frontier`, so
-    /// repeated calls watch the lag drain toward the fence this snapshot
-    /// captured.
+    /// Re-read the committed checkpoint and the backfill counts, keeping
+    /// the pinned `frontier`, so repeated calls watch the lag drain toward
+    /// the fence this snapshot captured.
     #[tracing::instrument(
         level = "debug",
         name = "cala_ledger.ec_rollup_status.refresh",
@@ -338,6 +363,8 @@ impl EcRollupStatus {
     )]
     pub async fn refresh(&mut self) -> Result<(), LedgerError> {
         self.applied = self.handle.load().await?.checkpoint();
+        (self.backfills_pending, self.backfill_entries_pending) =
+            self.backfills.count_pending().await?;
 
         let span = tracing::Span::current();
         span.record("applied", u64::from(self.applied));
@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+use cala_types::primitives::ParseCurrencyError;
+
+#[derive(Error, Debug)]
+pub enum IntegrityError {
+    #[error("IntegrityError - Sqlx: {0}")]
+    Sqlx(#[from] sqlx::Error),
+    #[error("IntegrityError - Job: {0}")]
+    Job(#[from] job::JobError),
+    #[error("IntegrityError - ParseCurrency: {0}")]
+    ParseCurrency(#[from] ParseCurrencyError),
+}
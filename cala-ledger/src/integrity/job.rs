@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use job::{CurrentJob, Job, JobCompletion, JobInitializer, JobRunner, JobSpawner, JobType, Jobs};
+use serde::{Deserialize, Serialize};
+
+use super::{IntegrityScope, IntegrityVerifier};
+
+const INTEGRITY_VERIFICATION_JOB: JobType = JobType::new("cala.integrity_verification");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct IntegrityVerificationConfig {
+    pub scope: IntegrityScope,
+}
+
+/// Register the integrity verification job. Each run stores its
+/// [`super::IntegrityReport`] as the job's result.
+///
+/// Must be called before [`Jobs::start_poll`].
+pub(crate) fn register_integrity_verification(
+    jobs: &mut Jobs,
+    verifier: &IntegrityVerifier,
+) -> JobSpawner<IntegrityVerificationConfig> {
+    jobs.add_initializer(IntegrityVerificationInit {
+        verifier: verifier.clone(),
+    })
+}
+
+struct IntegrityVerificationInit {
+    verifier: IntegrityVerifier,
+}
+
+impl JobInitializer for IntegrityVerificationInit {
+    type Config = IntegrityVerificationConfig;
+
+    fn job_type(&self) -> JobType {
+        INTEGRITY_VERIFICATION_JOB
+    }
+
+    fn init(
+        &self,
+        job: &Job,
+        _: JobSpawner<Self::Config>,
+    ) -> Result<Box<dyn JobRunner>, Box<dyn std::error::Error>> {
+        let config: IntegrityVerificationConfig = job.config()?;
+        Ok(Box::new(IntegrityVerificationRunner {
+            verifier: self.verifier.clone(),
+            scope: config.scope,
+        }))
+    }
+}
+
+struct IntegrityVerificationRunner {
+    verifier: IntegrityVerifier,
+    scope: IntegrityScope,
+}
+
+#[async_trait]
+impl JobRunner for IntegrityVerificationRunner {
+    async fn run(
+        &self,
+        current_job: CurrentJob,
+    ) -> Result<JobCompletion, Box<dyn std::error::Error>> {
+        let report = self.verifier.verify(self.scope.clone()).await?;
+        current_job.set_result(&report).await?;
+        Ok(JobCompletion::Complete)
+    }
+}
@@ -0,0 +1,237 @@
+//! Re-derivation of balances from the entries they were folded from.
+//!
+//! Every balance version records the entry that produced it, so each
+//! version can be checked to equal its predecessor with that entry folded
+//! in by [`Snapshots`] — the fold posting uses. Chaining the checks from
+//! version 1 recomputes the balance from its entries alone; the result is
+//! then compared against the current balance, the number of contributing
+//! entries and the latest cumulative effective balance.
+//!
+//! A version an account set got from a member move records no entry: it
+//! carries the member's whole balance, so the recomputation resumes from
+//! the balance it recorded and it is not counted as an entry.
+//!
+//! Balances of eventually-consistent accounts and account sets are only
+//! complete once the streaming rollup caught up, so callers wanting a clean
+//! report should fence on
+//! [`CalaLedger::ec_rollup_status`](crate::CalaLedger::ec_rollup_status)
+//! first.
+
+pub mod error;
+mod job;
+mod repo;
+mod report;
+
+use sqlx::PgPool;
+use tracing::instrument;
+
+use cala_types::{balance::BalanceSnapshot, primitives::EntryId};
+
+use crate::balance::{Snapshots, MEMBER_MOVE_ENTRY_ID};
+
+use error::IntegrityError;
+pub(crate) use job::*;
+use repo::*;
+pub use report::*;
+
+/// Balances loaded per round trip.
+const KEYS_PER_BATCH: i64 = 100;
+/// History versions, and their entries, loaded per round trip.
+const VERSIONS_PER_BATCH: i64 = 1_000;
+
+#[derive(Clone)]
+pub struct IntegrityVerifier {
+    repo: IntegrityRepo,
+}
+
+impl IntegrityVerifier {
+    pub(crate) fn new(pool: &PgPool) -> Self {
+        Self {
+            repo: IntegrityRepo::new(pool),
+        }
+    }
+
+    #[instrument(name = "cala_ledger.integrity.verify", skip(self))]
+    pub async fn verify(&self, scope: IntegrityScope) -> Result<IntegrityReport, IntegrityError> {
+        let mut report = IntegrityReport::new(scope.clone());
+        let mut after = None;
+        loop {
+            let keys = self
+                .repo
+                .list_balance_keys(
+                    scope.journal_id(),
+                    scope.account_id(),
+                    after,
+                    KEYS_PER_BATCH,
+                )
+                .await?;
+            let Some(last) = keys.last() else {
+                break;
+            };
+            after = Some((
+                last.current.journal_id,
+                last.current.account_id,
+                last.current.currency,
+            ));
+            for key in keys.iter() {
+                self.verify_balance(key, &mut report).await?;
+            }
+        }
+
+        for key in self
+            .repo
+            .list_unbalanced_keys(scope.journal_id(), scope.account_id())
+            .await?
+        {
+            report.discrepancies.push(Discrepancy {
+                journal_id: key.journal_id,
+                account_id: key.account_id,
+                currency: key.currency,
+                kind: DiscrepancyKind::MissingBalance {
+                    entries: key.entries,
+                },
+            });
+        }
+
+        if !report.is_consistent() {
+            tracing::warn!(
+                discrepancies = report.discrepancies.len(),
+                "ledger integrity discrepancies found"
+            );
+        }
+        Ok(report)
+    }
+
+    async fn verify_balance(
+        &self,
+        key: &BalanceKey,
+        report: &mut IntegrityReport,
+    ) -> Result<(), IntegrityError> {
+        let current = &key.current;
+        let mut recomputed: Option<BalanceSnapshot> = None;
+        let mut versions = 0;
+        let mut moves = 0;
+        let mut diverged = false;
+        loop {
+            let steps = self
+                .repo
+                .list_history_steps(
+                    current.journal_id,
+                    current.account_id,
+                    current.currency,
+                    versions,
+                    VERSIONS_PER_BATCH,
+                )
+                .await?;
+            if steps.is_empty() {
+                break;
+            }
+            for step in steps {
+                versions += 1;
+                report.versions_checked += 1;
+                if step.recorded.version != versions {
+                    report.push(
+                        current,
+                        DiscrepancyKind::MissingVersion { version: versions },
+                    );
+                    versions = step.recorded.version;
+                }
+                if step.entry_id == EntryId::from(MEMBER_MOVE_ENTRY_ID) {
+                    moves += 1;
+                    recomputed = Some(step.recorded);
+                    continue;
+                }
+                let entry = match step.entry {
+                    Some(entry)
+                        if entry.journal_id == current.journal_id
+                            && entry.currency == current.currency
+                            && (key.is_account_set || entry.account_id == current.account_id) =>
+                    {
+                        entry
+                    }
+                    _ => {
+                        report.push(
+                            current,
+                            DiscrepancyKind::UnknownEntry {
+                                version: versions,
+                                entry_id: step.entry_id,
+                            },
+                        );
+                        continue;
+                    }
+                };
+                let time = step.recorded.modified_at;
+                let next = match recomputed.take() {
+                    Some(balance) => Snapshots::update_snapshot(time, balance, &entry),
+                    None => Snapshots::new_snapshot(time, current.account_id, &entry),
+                };
+                let expected = BalanceTotals::from(&next);
+                let recorded = BalanceTotals::from(&step.recorded);
+                if !diverged && expected != recorded {
+                    diverged = true;
+                    report.push(
+                        current,
+                        DiscrepancyKind::HistoryVersion {
+                            version: versions,
+                            expected,
+                            recorded,
+                        },
+                    );
+                }
+                recomputed = Some(next);
+            }
+        }
+        report.balances_checked += 1;
+
+        let expected = recomputed
+            .as_ref()
+            .map(BalanceTotals::from)
+            .unwrap_or_default();
+        let recorded = BalanceTotals::from(current);
+        if expected != recorded || versions != current.version {
+            report.push(
+                current,
+                DiscrepancyKind::CurrentBalance {
+                    version: current.version,
+                    expected,
+                    recorded,
+                },
+            );
+        }
+
+        let entries = self
+            .repo
+            .count_entries(
+                current.journal_id,
+                current.account_id,
+                current.currency,
+                key.is_account_set,
+            )
+            .await?;
+        let entry_versions = current.version.saturating_sub(moves);
+        if entries != u64::from(entry_versions) {
+            report.push(
+                current,
+                DiscrepancyKind::EntryCount {
+                    entries,
+                    versions: entry_versions,
+                },
+            );
+        }
+
+        if let Some(effective) = self
+            .repo
+            .find_latest_effective(current.journal_id, current.account_id, current.currency)
+            .await?
+        {
+            let recorded = BalanceTotals::from(&effective);
+            if recorded != expected {
+                report.push(
+                    current,
+                    DiscrepancyKind::EffectiveBalance { expected, recorded },
+                );
+            }
+        }
+        Ok(())
+    }
+}
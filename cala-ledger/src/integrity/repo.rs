@@ -0,0 +1,259 @@
+use sqlx::PgPool;
+use tracing::instrument;
+
+use cala_types::{balance::BalanceSnapshot, entry::EntryValues, primitives::*};
+
+use super::error::IntegrityError;
+
+pub(super) struct BalanceKey {
+    pub is_account_set: bool,
+    pub current: BalanceSnapshot,
+}
+
+pub(super) struct HistoryStep {
+    pub recorded: BalanceSnapshot,
+    pub entry_id: EntryId,
+    pub entry: Option<EntryValues>,
+}
+
+pub(super) struct UnbalancedKey {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub entries: u64,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct IntegrityRepo {
+    pool: PgPool,
+}
+
+impl IntegrityRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    #[instrument(name = "integrity.list_balance_keys", skip(self), err(level = "warn"))]
+    pub async fn list_balance_keys(
+        &self,
+        journal_id: Option<JournalId>,
+        account_id: Option<AccountId>,
+        after: Option<(JournalId, AccountId, Currency)>,
+        limit: i64,
+    ) -> Result<Vec<BalanceKey>, IntegrityError> {
+        let (after_journal_id, after_account_id, after_currency) = match after {
+            Some((journal_id, account_id, currency)) => {
+                (Some(journal_id), Some(account_id), Some(currency.code()))
+            }
+            None => (None, None, None),
+        };
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.latest_values AS "values!", a.is_account_set AS "is_account_set!"
+            FROM cala_current_balances c
+            JOIN cala_accounts a ON a.id = c.account_id
+            WHERE ($1::uuid IS NULL OR c.journal_id = $1)
+              AND ($2::uuid IS NULL OR c.account_id = $2)
+              AND ($3::uuid IS NULL OR (c.journal_id, c.account_id, c.currency) > ($3, $4, $5))
+            ORDER BY c.journal_id, c.account_id, c.currency
+            LIMIT $6
+            "#,
+            journal_id as Option<JournalId>,
+            account_id as Option<AccountId>,
+            after_journal_id as Option<JournalId>,
+            after_account_id as Option<AccountId>,
+            after_currency,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| BalanceKey {
+                is_account_set: row.is_account_set,
+                current: serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot"),
+            })
+            .collect())
+    }
+
+    /// History versions after `after_version`, each with the entry that
+    /// produced it.
+    #[instrument(name = "integrity.list_history_steps", skip(self), err(level = "warn"))]
+    pub async fn list_history_steps(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        after_version: u32,
+        limit: i64,
+    ) -> Result<Vec<HistoryStep>, IntegrityError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                h.values AS "values!",
+                h.latest_entry_id AS "entry_id!: EntryId",
+                ev.event->'values' AS "entry?"
+            FROM cala_balance_history h
+            LEFT JOIN cala_entry_events ev
+                ON ev.id = h.latest_entry_id AND ev.sequence = 1
+            WHERE h.journal_id = $1
+              AND h.account_id = $2
+              AND h.currency = $3
+              AND h.version > $4
+            ORDER BY h.version
+            LIMIT $5
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+            after_version as i32,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| HistoryStep {
+                recorded: serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot"),
+                entry_id: row.entry_id,
+                entry: row.entry.map(|entry| {
+                    serde_json::from_value(entry).expect("Failed to deserialize entry")
+                }),
+            })
+            .collect())
+    }
+
+    /// Number of entries posted to the account in the currency or, for an
+    /// account set, number of distinct entries of the journal and currency
+    /// its history applied. Sets are counted from their own history rather
+    /// than their current members, who may have joined with backfilled
+    /// history or left since.
+    #[instrument(name = "integrity.count_entries", skip(self), err(level = "warn"))]
+    pub async fn count_entries(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        is_account_set: bool,
+    ) -> Result<u64, IntegrityError> {
+        let count = if is_account_set {
+            sqlx::query_scalar!(
+                r#"
+                SELECT COUNT(DISTINCT e.id) AS "count!"
+                FROM cala_balance_history h
+                JOIN cala_entries e ON e.id = h.latest_entry_id
+                JOIN cala_entry_events ev ON ev.id = e.id AND ev.sequence = 1
+                WHERE h.journal_id = $1
+                  AND h.account_id = $2
+                  AND h.currency = $3
+                  AND e.journal_id = $1
+                  AND ev.event->'values'->>'currency' = $3
+                "#,
+                journal_id as JournalId,
+                account_id as AccountId,
+                currency.code(),
+            )
+            .fetch_one(&self.pool)
+            .await?
+        } else {
+            sqlx::query_scalar!(
+                r#"
+                SELECT COUNT(*) AS "count!"
+                FROM cala_entries e
+                JOIN cala_entry_events ev ON ev.id = e.id AND ev.sequence = 1
+                WHERE e.journal_id = $1
+                  AND e.account_id = $2
+                  AND ev.event->'values'->>'currency' = $3
+                "#,
+                journal_id as JournalId,
+                account_id as AccountId,
+                currency.code(),
+            )
+            .fetch_one(&self.pool)
+            .await?
+        };
+        Ok(count as u64)
+    }
+
+    #[instrument(
+        name = "integrity.find_latest_effective",
+        skip(self),
+        err(level = "warn")
+    )]
+    pub async fn find_latest_effective(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<Option<BalanceSnapshot>, IntegrityError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT values
+            FROM cala_cumulative_effective_balances
+            WHERE journal_id = $1
+              AND account_id = $2
+              AND currency = $3
+            ORDER BY effective DESC, version DESC
+            LIMIT 1
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| {
+            serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot")
+        }))
+    }
+
+    /// Accounts with entries in a currency but no balance for it.
+    #[instrument(
+        name = "integrity.list_unbalanced_keys",
+        skip(self),
+        err(level = "warn")
+    )]
+    pub async fn list_unbalanced_keys(
+        &self,
+        journal_id: Option<JournalId>,
+        account_id: Option<AccountId>,
+    ) -> Result<Vec<UnbalancedKey>, IntegrityError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                e.journal_id AS "journal_id!: JournalId",
+                e.account_id AS "account_id!: AccountId",
+                ev.event->'values'->>'currency' AS "currency!",
+                COUNT(*) AS "entries!"
+            FROM cala_entries e
+            JOIN cala_entry_events ev ON ev.id = e.id AND ev.sequence = 1
+            WHERE ($1::uuid IS NULL OR e.journal_id = $1)
+              AND ($2::uuid IS NULL OR e.account_id = $2)
+              AND NOT EXISTS (
+                  SELECT 1 FROM cala_current_balances c
+                  WHERE c.journal_id = e.journal_id
+                    AND c.account_id = e.account_id
+                    AND c.currency = ev.event->'values'->>'currency'
+              )
+            GROUP BY 1, 2, 3
+            ORDER BY 1, 2, 3
+            "#,
+            journal_id as Option<JournalId>,
+            account_id as Option<AccountId>,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(UnbalancedKey {
+                    journal_id: row.journal_id,
+                    account_id: row.account_id,
+                    currency: row.currency.parse()?,
+                    entries: row.entries as u64,
+                })
+            })
+            .collect()
+    }
+}
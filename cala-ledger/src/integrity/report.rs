@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use cala_types::{balance::BalanceSnapshot, primitives::*};
+
+use crate::reports::DebitCreditTotals;
+
+/// What [`super::IntegrityVerifier::verify`] checks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IntegrityScope {
+    All,
+    Journal {
+        journal_id: JournalId,
+    },
+    /// The balances of one account or account set.
+    Account {
+        journal_id: JournalId,
+        account_id: AccountId,
+    },
+}
+
+impl IntegrityScope {
+    pub(super) fn journal_id(&self) -> Option<JournalId> {
+        match self {
+            Self::All => None,
+            Self::Journal { journal_id } | Self::Account { journal_id, .. } => Some(*journal_id),
+        }
+    }
+
+    pub(super) fn account_id(&self) -> Option<AccountId> {
+        match self {
+            Self::Account { account_id, .. } => Some(*account_id),
+            _ => None,
+        }
+    }
+}
+
+/// The debit and credit totals of every layer of a balance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceTotals {
+    pub settled: DebitCreditTotals,
+    pub pending: DebitCreditTotals,
+    pub encumbrance: DebitCreditTotals,
+}
+
+impl From<&BalanceSnapshot> for BalanceTotals {
+    fn from(snapshot: &BalanceSnapshot) -> Self {
+        Self {
+            settled: (&snapshot.settled).into(),
+            pending: (&snapshot.pending).into(),
+            encumbrance: (&snapshot.encumbrance).into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    /// A history version is not its predecessor with its entry folded in.
+    /// Only the first such version of a balance is reported.
+    HistoryVersion {
+        version: u32,
+        expected: BalanceTotals,
+        recorded: BalanceTotals,
+    },
+    /// A history version is missing from the chain.
+    MissingVersion { version: u32 },
+    /// A history version was produced by an entry that does not exist or
+    /// is in another currency or journal.
+    UnknownEntry { version: u32, entry_id: EntryId },
+    /// The current balance differs from the one recomputed from history.
+    CurrentBalance {
+        version: u32,
+        expected: BalanceTotals,
+        recorded: BalanceTotals,
+    },
+    /// A different number of entries contribute to the balance than it has
+    /// versions, not counting those written by member moves.
+    EntryCount { entries: u64, versions: u32 },
+    /// The latest cumulative effective balance differs from the recomputed
+    /// balance.
+    EffectiveBalance {
+        expected: BalanceTotals,
+        recorded: BalanceTotals,
+    },
+    /// Entries were posted but no balance was recorded for them.
+    MissingBalance { entries: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Discrepancy {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub kind: DiscrepancyKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub scope: IntegrityScope,
+    pub balances_checked: u64,
+    pub versions_checked: u64,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl IntegrityReport {
+    pub(super) fn new(scope: IntegrityScope) -> Self {
+        Self {
+            scope,
+            balances_checked: 0,
+            versions_checked: 0,
+            discrepancies: Vec::new(),
+        }
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    pub(super) fn push(&mut self, snapshot: &BalanceSnapshot, kind: DiscrepancyKind) {
+        self.discrepancies.push(Discrepancy {
+            journal_id: snapshot.journal_id,
+            account_id: snapshot.account_id,
+            currency: snapshot.currency,
+            kind,
+        });
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::NaiveDate;
+use derive_builder::Builder;
+
+use crate::{balance::AccountBalance, posting::PostingInput, primitives::*, tx_template::Params};
+
+/// Closing entries posted by [`crate::CalaLedger::close_period`] before the
+/// period is closed: one posting of `tx_template_code` per account and
+/// currency below `account_set_ids` (typically the revenue and expense sets)
+/// whose settled balance is not zero, moving that balance to
+/// `retained_earnings_account_id`.
+///
+/// The template is passed the params `journal_id`, `account_id`,
+/// `retained_earnings_account_id`, `currency`, `amount`, `effective`,
+/// `direction` (the side to post `amount` on `account_id` to bring it to zero)
+/// and `retained_earnings_direction` (the opposite side).
+#[derive(Debug, Clone, Builder)]
+pub struct ClosingEntries {
+    #[builder(setter(into))]
+    tx_template_code: String,
+    #[builder(setter(into))]
+    account_set_ids: Vec<AccountSetId>,
+    #[builder(setter(into))]
+    retained_earnings_account_id: AccountId,
+}
+
+impl ClosingEntries {
+    pub fn builder() -> ClosingEntriesBuilder {
+        ClosingEntriesBuilder::default()
+    }
+
+    pub(crate) fn account_set_ids(&self) -> &[AccountSetId] {
+        &self.account_set_ids
+    }
+
+    pub(crate) fn postings(
+        &self,
+        journal_id: JournalId,
+        closed_through: NaiveDate,
+        balances: Vec<AccountBalance>,
+    ) -> Vec<PostingInput> {
+        balances
+            .into_iter()
+            .filter(|balance| balance.details.account_id != self.retained_earnings_account_id)
+            .filter_map(|balance| {
+                let settled = &balance.details.settled;
+                let net = settled.cr_balance - settled.dr_balance;
+                if net.is_zero() {
+                    return None;
+                }
+                let (direction, retained_earnings_direction) = if net.is_sign_positive() {
+                    (DebitOrCredit::Debit, DebitOrCredit::Credit)
+                } else {
+                    (DebitOrCredit::Credit, DebitOrCredit::Debit)
+                };
+                let mut params = Params::new();
+                params.insert("journal_id", journal_id.to_string());
+                params.insert("account_id", balance.details.account_id);
+                params.insert(
+                    "retained_earnings_account_id",
+                    self.retained_earnings_account_id,
+                );
+                params.insert("currency", balance.details.currency);
+                params.insert("amount", net.abs());
+                params.insert("effective", closed_through);
+                params.insert("direction", direction);
+                params.insert("retained_earnings_direction", retained_earnings_direction);
+                Some(PostingInput::new(
+                    TransactionId::new(),
+                    self.tx_template_code.clone(),
+                    params,
+                ))
+            })
+            .collect()
+    }
+}
@@ -1,8 +1,11 @@
+use chrono::NaiveDate;
 use derive_builder::Builder;
 use es_entity::*;
 use serde::{Deserialize, Serialize};
 
 use crate::primitives::*;
+
+use super::error::JournalError;
 pub use cala_types::{journal::*, primitives::JournalId};
 
 #[derive(EsEvent, Debug, Serialize, Deserialize)]
@@ -17,6 +20,16 @@ pub enum JournalEvent {
         values: JournalValues,
         fields: Vec<String>,
     },
+    PeriodClosed {
+        values: JournalValues,
+        closed_through: NaiveDate,
+        closing_transaction_ids: Vec<TransactionId>,
+    },
+    PeriodReopened {
+        values: JournalValues,
+        previously_closed_through: NaiveDate,
+        reason: String,
+    },
 }
 
 #[derive(EsEntity, Builder)]
@@ -40,6 +53,79 @@ impl Journal {
         matches!(self.values.status, Status::Locked)
     }
 
+    /// The last effective date of the closed period, if any.
+    pub fn closed_through(&self) -> Option<NaiveDate> {
+        self.values.closed_through
+    }
+
+    /// Whether a posting effective on `effective` falls into a closed period.
+    pub fn is_closed_on(&self, effective: NaiveDate) -> bool {
+        self.values
+            .closed_through
+            .is_some_and(|closed_through| effective <= closed_through)
+    }
+
+    /// Close the period up to and including `closed_through`.
+    ///
+    /// A close can only move forward; use [`Self::reopen_period`] to move
+    /// it back.
+    pub fn close_period(
+        &mut self,
+        closed_through: NaiveDate,
+        closing_transaction_ids: Vec<TransactionId>,
+    ) -> Result<es_entity::Idempotent<()>, JournalError> {
+        match self.values.closed_through {
+            Some(current) if current == closed_through => {
+                return Ok(es_entity::Idempotent::AlreadyApplied)
+            }
+            Some(current) if current > closed_through => {
+                return Err(JournalError::PeriodAlreadyClosed(current))
+            }
+            _ => {}
+        }
+        self.values.closed_through = Some(closed_through);
+        self.events.push(JournalEvent::PeriodClosed {
+            values: self.values.clone(),
+            closed_through,
+            closing_transaction_ids,
+        });
+        Ok(es_entity::Idempotent::Executed(()))
+    }
+
+    /// Move the close back to `closed_through` (or remove it entirely when
+    /// `None`). The `reason` is recorded on the event.
+    pub fn reopen_period(
+        &mut self,
+        closed_through: Option<NaiveDate>,
+        reason: impl Into<String>,
+    ) -> Result<es_entity::Idempotent<()>, JournalError> {
+        let reason = reason.into();
+        if reason.trim().is_empty() {
+            return Err(JournalError::ReopenReasonMissing);
+        }
+        let Some(previously_closed_through) = self.values.closed_through else {
+            return Ok(es_entity::Idempotent::AlreadyApplied);
+        };
+        match closed_through {
+            Some(date) if date == previously_closed_through => {
+                return Ok(es_entity::Idempotent::AlreadyApplied)
+            }
+            Some(date) if date > previously_closed_through => {
+                return Err(JournalError::ReopenMustMoveBackwards(
+                    previously_closed_through,
+                ))
+            }
+            _ => {}
+        }
+        self.values.closed_through = closed_through;
+        self.events.push(JournalEvent::PeriodReopened {
+            values: self.values.clone(),
+            previously_closed_through,
+            reason,
+        });
+        Ok(es_entity::Idempotent::Executed(()))
+    }
+
     pub(crate) fn insert_effective_balances(&self) -> bool {
         self.values.config.enable_effective_balances
     }
@@ -144,7 +230,9 @@ impl TryFromEvents<JournalEvent> for Journal {
                 JournalEvent::Initialized { values } => {
                     builder = builder.id(values.id).values(values.clone());
                 }
-                JournalEvent::Updated { values, .. } => {
+                JournalEvent::Updated { values, .. }
+                | JournalEvent::PeriodClosed { values, .. }
+                | JournalEvent::PeriodReopened { values, .. } => {
                     builder = builder.values(values.clone());
                 }
             }
@@ -192,6 +280,7 @@ impl IntoEvents<JournalEvent> for NewJournal {
                     config: JournalConfig {
                         enable_effective_balances: self.enable_effective_balance,
                     },
+                    closed_through: None,
                 },
             }],
         )
@@ -214,6 +303,65 @@ mod tests {
         assert_eq!(new_journal.description, None);
     }
 
+    fn journal() -> Journal {
+        let new_journal = NewJournal::builder()
+            .id(JournalId::new())
+            .name("name")
+            .build()
+            .unwrap();
+        Journal::try_from_events(new_journal.into_events()).unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    #[test]
+    fn close_period_only_moves_forward() {
+        let mut journal = journal();
+        assert!(journal
+            .close_period(date(10), Vec::new())
+            .unwrap()
+            .did_execute());
+        assert!(journal.is_closed_on(date(10)));
+        assert!(!journal.is_closed_on(date(11)));
+        assert!(!journal
+            .close_period(date(10), Vec::new())
+            .unwrap()
+            .did_execute());
+        assert!(matches!(
+            journal.close_period(date(5), Vec::new()),
+            Err(JournalError::PeriodAlreadyClosed(d)) if d == date(10)
+        ));
+    }
+
+    #[test]
+    fn reopen_period_requires_reason_and_moves_backward() {
+        let mut journal = journal();
+        assert!(journal
+            .close_period(date(10), Vec::new())
+            .unwrap()
+            .did_execute());
+        assert!(matches!(
+            journal.reopen_period(Some(date(5)), " "),
+            Err(JournalError::ReopenReasonMissing)
+        ));
+        assert!(matches!(
+            journal.reopen_period(Some(date(15)), "audit adjustment"),
+            Err(JournalError::ReopenMustMoveBackwards(_))
+        ));
+        assert!(journal
+            .reopen_period(Some(date(5)), "audit adjustment")
+            .unwrap()
+            .did_execute());
+        assert_eq!(journal.closed_through(), Some(date(5)));
+        assert!(journal
+            .reopen_period(None, "audit adjustment")
+            .unwrap()
+            .did_execute());
+        assert!(!journal.is_closed_on(date(1)));
+    }
+
     #[test]
     fn fails_when_mandatory_fields_are_missing() {
         let new_account = NewJournal::builder().build();
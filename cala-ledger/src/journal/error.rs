@@ -18,6 +18,14 @@ pub enum JournalError {
     Query(#[from] JournalQueryError),
     #[error("JournalError - code '{0}' already exists")]
     CodeAlreadyExists(String),
+    #[error("JournalError - PeriodAlreadyClosed: the journal is already closed through {0}")]
+    PeriodAlreadyClosed(chrono::NaiveDate),
+    #[error("JournalError - ReopenMustMoveBackwards: the journal is closed through {0}, reopening must move the close to an earlier date")]
+    ReopenMustMoveBackwards(chrono::NaiveDate),
+    #[error("JournalError - ReopenReasonMissing: reopening a period requires a reason")]
+    ReopenReasonMissing,
+    #[error("JournalError - ClosingEntriesRequireEffectiveBalances: closing entries need effective balances enabled on journal {0}")]
+    ClosingEntriesRequireEffectiveBalances(crate::primitives::JournalId),
 }
 
 impl From<JournalCreateError> for JournalError {
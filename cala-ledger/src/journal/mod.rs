@@ -1,15 +1,18 @@
+mod closing;
 mod entity;
 pub mod error;
 mod repo;
 
+use chrono::NaiveDate;
 use es_entity::clock::ClockHandle;
 use sqlx::PgPool;
 use tracing::instrument;
 
 use std::collections::HashMap;
 
-use crate::outbox::*;
+use crate::{outbox::*, primitives::TransactionId};
 
+pub use closing::*;
 pub use entity::*;
 use error::*;
 use repo::*;
@@ -90,6 +93,76 @@ impl Journals {
         Ok(())
     }
 
+    /// Close the journal's period through `closed_through`. From then on any
+    /// posting effective on or before that date is rejected.
+    ///
+    /// Use [`crate::CalaLedger::close_period`] to post closing entries as
+    /// part of the close.
+    #[instrument(name = "cala_ledger.journals.close_period", skip(self))]
+    pub async fn close_period(
+        &self,
+        journal_id: JournalId,
+        closed_through: NaiveDate,
+    ) -> Result<Journal, JournalError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let journal = self
+            .close_period_in_op(&mut op, journal_id, closed_through, Vec::new())
+            .await?;
+        op.commit().await?;
+        Ok(journal)
+    }
+
+    /// Take the journal's period lock EXCLUSIVE for the rest of `db`,
+    /// waiting out postings to the journal in flight and holding off new
+    /// ones, which take it SHARED before they read the journal.
+    #[instrument(name = "cala_ledger.journals.lock_period_in_op", skip(self, db))]
+    pub(crate) async fn lock_period_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+    ) -> Result<(), JournalError> {
+        self.repo.lock_period_in_op(db, journal_id).await?;
+        Ok(())
+    }
+
+    #[instrument(name = "cala_ledger.journals.close_period_in_op", skip(self, db))]
+    pub(crate) async fn close_period_in_op(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        closed_through: NaiveDate,
+        closing_transaction_ids: Vec<TransactionId>,
+    ) -> Result<Journal, JournalError> {
+        self.lock_period_in_op(db, journal_id).await?;
+        let mut journal = self.repo.find_by_id_in_op(&mut *db, journal_id).await?;
+        if journal
+            .close_period(closed_through, closing_transaction_ids)?
+            .did_execute()
+        {
+            self.repo.update_in_op(db, &mut journal).await?;
+        }
+        Ok(journal)
+    }
+
+    /// Move the journal's close back to `closed_through`, or remove it when
+    /// `None`, so that postings into the reopened dates are accepted again.
+    /// The `reason` is recorded on the journal's events.
+    #[instrument(name = "cala_ledger.journals.reopen_period", skip(self))]
+    pub async fn reopen_period(
+        &self,
+        journal_id: JournalId,
+        closed_through: Option<NaiveDate>,
+        reason: impl Into<String> + std::fmt::Debug,
+    ) -> Result<Journal, JournalError> {
+        let mut op = self.repo.begin_op_with_clock(&self.clock).await?;
+        let mut journal = self.repo.find_by_id_in_op(&mut op, journal_id).await?;
+        if journal.reopen_period(closed_through, reason)?.did_execute() {
+            self.repo.update_in_op(&mut op, &mut journal).await?;
+        }
+        op.commit().await?;
+        Ok(journal)
+    }
+
     #[instrument(level = "debug", name = "cala_ledger.journal.find_by_code", skip(self))]
     pub async fn find_by_code(&self, code: String) -> Result<Journal, JournalError> {
         Ok(self.repo.find_by_code(Some(code)).await?)
@@ -106,6 +179,23 @@ impl From<&JournalEvent> for OutboxEventPayload {
                 journal: values.clone(),
                 fields: fields.clone(),
             },
+            JournalEvent::PeriodClosed {
+                values,
+                closed_through,
+                ..
+            } => OutboxEventPayload::JournalPeriodClosed {
+                journal: values.clone(),
+                closed_through: *closed_through,
+            },
+            JournalEvent::PeriodReopened {
+                values,
+                previously_closed_through,
+                reason,
+            } => OutboxEventPayload::JournalPeriodReopened {
+                journal: values.clone(),
+                previously_closed_through: *previously_closed_through,
+                reason: reason.clone(),
+            },
         }
     }
 }
@@ -5,6 +5,14 @@ use crate::outbox::OutboxPublisher;
 
 use super::entity::*;
 
+/// `classid` namespace for the per-journal period lock (2-arg form), keyed
+/// on `hashtext(<journal id>)`. Closing a period takes it EXCLUSIVE; the
+/// poster takes it SHARED before it reads the journal, so a posting either
+/// commits before the close reads the balances it closes or sees the close.
+/// Must stay disjoint from `EC_SET_LOCK_CLASS` (= 1), `MEMBER_LOCK_CLASS`
+/// (= 2) and `GRAPH_LOCK_CLASS` (= 3), and in sync with `posting::repo`.
+const PERIOD_LOCK_CLASS: i32 = 4;
+
 #[derive(EsRepo, Debug, Clone)]
 #[es_repo(
     entity = "Journal",
@@ -29,6 +37,21 @@ impl JournalRepo {
         }
     }
 
+    pub(super) async fn lock_period_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock($1, hashtext($2::text))",
+            PERIOD_LOCK_CLASS,
+            journal_id as JournalId,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
     async fn publish(
         &self,
         op: &mut impl es_entity::AtomicOperation,
@@ -1,6 +1,11 @@
+use cel_interpreter::{CelCompiledExpression, CelEvaluationLimits, CelFunctionRegistry};
 use derive_builder::Builder;
 use es_entity::clock::{Clock, ClockHandle};
 
+use std::sync::Arc;
+
+use crate::{account_set::MembershipLimits, balance::FxRateProvider};
+
 #[derive(Builder, Clone, Debug)]
 #[builder(build_fn(validate = "Self::validate"))]
 pub struct CalaLedgerConfig {
@@ -14,6 +19,33 @@ pub struct CalaLedgerConfig {
     pub(super) pool: Option<sqlx::PgPool>,
     #[builder(setter(into), default = "Clock::handle().clone()")]
     pub(super) clock: ClockHandle,
+    /// Reject tx templates and velocity limits containing an expression whose
+    /// [`cel_interpreter::CelExpression::estimated_cost`] exceeds this.
+    #[builder(setter(into, strip_option), default)]
+    pub(super) max_expression_cost: Option<u64>,
+    /// Budget enforced on every evaluation of a tx template, velocity limit
+    /// or control expression while posting. A posting whose evaluation
+    /// exceeds it is rejected.
+    #[builder(setter(into, strip_option), default)]
+    pub(super) cel_evaluation_limits: Option<CelEvaluationLimits>,
+    /// Application functions callable from tx template and velocity
+    /// expressions, in addition to the builtins.
+    #[builder(default)]
+    pub(super) cel_functions: CelFunctionRegistry,
+    /// Expressions loaded into the compile cache on init, typically exported
+    /// from a warm process via `CelCompileCache::export`, so that stored
+    /// templates and limits are not parsed again on first use.
+    #[builder(setter(into), default)]
+    pub(super) precompiled_expressions: Vec<CelCompiledExpression>,
+    /// Rates used to consolidate balances across currencies. Defaults to the
+    /// rates recorded in the database through `DbFxRates`.
+    #[builder(setter(custom), default)]
+    pub(super) fx_rates: Option<Arc<dyn FxRateProvider>>,
+    /// Limits on the depth of the account-set graph and on how many sets an
+    /// account may belong to and a set may hold. Checked on every
+    /// membership change and, on init, against the graph already stored.
+    #[builder(default)]
+    pub(super) membership_limits: MembershipLimits,
 }
 
 impl CalaLedgerConfig {
@@ -23,6 +55,11 @@ impl CalaLedgerConfig {
 }
 
 impl CalaLedgerConfigBuilder {
+    pub fn fx_rates(&mut self, fx_rates: impl FxRateProvider + 'static) -> &mut Self {
+        self.fx_rates = Some(Some(Arc::new(fx_rates)));
+        self
+    }
+
     fn validate(&self) -> Result<(), String> {
         match (self.pg_con.as_ref(), self.pool.as_ref()) {
             (None, None) | (Some(None), None) | (None, Some(None)) => {
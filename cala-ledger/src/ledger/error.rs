@@ -3,8 +3,8 @@ use thiserror::Error;
 
 use crate::{
     account::error::AccountError, account_set::error::AccountSetError,
-    balance::error::BalanceError, entry::error::EntryError, journal::error::JournalError,
-    posting::PostingError, transaction::error::TransactionError,
+    balance::error::BalanceError, entry::error::EntryError, integrity::error::IntegrityError,
+    journal::error::JournalError, posting::PostingError, transaction::error::TransactionError,
     tx_template::error::TxTemplateError, velocity::error::VelocityError,
 };
 
@@ -34,6 +34,8 @@ pub enum LedgerError {
     BalanceError(#[from] BalanceError),
     #[error("LedgerError - VelocityError: {0}")]
     VelocityError(#[from] VelocityError),
+    #[error("LedgerError - IntegrityError: {0}")]
+    IntegrityError(#[from] IntegrityError),
     #[error("LedgerError - PostingError: {0}")]
     PostingError(#[from] PostingError),
     #[error("LedgerError - EcRollupRegistration: {0}")]
@@ -1,6 +1,7 @@
 pub mod config;
 pub mod error;
 
+use cel_interpreter::CelCompileCache;
 use es_entity::clock::ClockHandle;
 use sqlx::PgPool;
 pub use tracing::instrument;
@@ -11,14 +12,19 @@ use error::*;
 
 use crate::{
     account::Accounts,
-    account_set::AccountSets,
+    account_set::{register_account_set_backfill, AccountSetBackfills, AccountSets},
     account_set_member::AccountSetMembers,
-    balance::Balances,
+    balance::{Balances, DbFxRates},
     entry::Entries,
-    journal::Journals,
+    integrity::{
+        register_integrity_verification, IntegrityReport, IntegrityScope,
+        IntegrityVerificationConfig, IntegrityVerifier,
+    },
+    journal::{error::JournalError, ClosingEntries, Journal, Journals},
     outbox::OutboxPublisher,
     posting::{PostingInput, Postings},
-    primitives::TransactionId,
+    primitives::{JournalId, TransactionId},
+    reports::Reports,
     transaction::{Transaction, Transactions},
     tx_template::{Params, TxTemplates},
     velocity::Velocities,
@@ -37,6 +43,10 @@ pub struct CalaLedger {
     velocities: Velocities,
     balances: Balances,
     postings: Postings,
+    reports: Reports,
+    integrity: IntegrityVerifier,
+    integrity_verification: job::JobSpawner<IntegrityVerificationConfig>,
+    backfills: AccountSetBackfills,
     publisher: OutboxPublisher,
     ec_rollup: obix::out::RegisteredEventHandler<
         crate::outbox::OutboxEventPayload,
@@ -75,24 +85,58 @@ impl CalaLedger {
                 .await?;
         }
 
+        if !config.precompiled_expressions.is_empty() {
+            let loaded = CelCompileCache::preload(config.precompiled_expressions);
+            tracing::info!(loaded, "preloaded compiled CEL expressions");
+        }
+
         let clock = config.clock;
         let publisher = OutboxPublisher::init(&pool, &clock).await?;
-        let account_set_members = AccountSetMembers::new(&pool, &publisher);
+        let account_set_members =
+            AccountSetMembers::new(&pool, &publisher, config.membership_limits.into());
         let accounts = Accounts::new(&pool, &publisher, &account_set_members, &clock);
         let journals = Journals::new(&pool, &publisher, &clock);
-        let tx_templates = TxTemplates::new(&pool, &publisher, &clock);
+        let tx_templates = TxTemplates::new(
+            &pool,
+            &publisher,
+            &clock,
+            &config.cel_functions,
+            config.max_expression_cost,
+            config.cel_evaluation_limits.clone(),
+        );
         let transactions = Transactions::new(&pool);
         let entries = Entries::new(&pool);
-        let balances = Balances::new(&pool, &journals);
-        let velocities = Velocities::new(&pool, &clock);
+        let fx_rates = config
+            .fx_rates
+            .unwrap_or_else(|| std::sync::Arc::new(DbFxRates::new(&pool)));
+        let balances = Balances::new(&pool, &journals, &entries, fx_rates);
+        let velocities = Velocities::new(
+            &pool,
+            &clock,
+            &config.cel_functions,
+            config.max_expression_cost,
+            config.cel_evaluation_limits.clone(),
+        );
+        let ec_rollup = crate::ec_rollup::register_ec_balance_rollup(
+            jobs,
+            publisher.inner(),
+            &balances,
+            &entries,
+        )
+        .await?;
+        let backfills = register_account_set_backfill(jobs, &pool, &balances, &entries);
         let account_sets = AccountSets::new(
             &pool,
             &publisher,
             &accounts,
             &balances,
             &account_set_members,
+            &ec_rollup,
+            &backfills,
             &clock,
+            config.membership_limits,
         );
+        account_sets.warm_graph_cache().await?;
         let postings = Postings::new(
             &publisher,
             &tx_templates,
@@ -101,19 +145,25 @@ impl CalaLedger {
             &velocities,
         );
 
-        let ec_rollup = crate::ec_rollup::register_ec_balance_rollup(
-            jobs,
-            publisher.inner(),
+        let reports = Reports::new(
+            &pool,
             &balances,
-            &entries,
-        )
-        .await?;
+            &clock,
+            &config.cel_functions,
+            config.cel_evaluation_limits.clone(),
+        );
+        let integrity = IntegrityVerifier::new(&pool);
+        let integrity_verification = register_integrity_verification(jobs, &integrity);
 
         Ok(Self {
             ec_rollup,
             accounts,
             account_sets,
             postings,
+            reports,
+            integrity,
+            integrity_verification,
+            backfills,
             journals,
             tx_templates,
             publisher,
@@ -173,6 +223,10 @@ impl CalaLedger {
         &self.transactions
     }
 
+    pub fn reports(&self) -> &Reports {
+        &self.reports
+    }
+
     #[instrument(
         name = "cala_ledger.post_transaction",
         skip(self, params),
@@ -276,25 +330,103 @@ impl CalaLedger {
         Ok(self.postings.post_all_in_op(db, batch).await?)
     }
 
+    /// Close `journal_id` through `closed_through`, rejecting any later
+    /// posting effective on or before that date.
+    ///
+    /// With `closing`, the [`ClosingEntries`] are posted effective
+    /// `closed_through` in the same operation just before the close, which
+    /// requires effective balances to be enabled on the journal. Closing a
+    /// journal again through the same date posts nothing.
+    ///
+    /// The close holds the journal's period lock throughout, so a posting
+    /// to the journal either commits before the closing balances are read
+    /// or is checked against the new close.
+    #[instrument(name = "cala_ledger.close_period", skip(self))]
+    pub async fn close_period(
+        &self,
+        journal_id: JournalId,
+        closed_through: chrono::NaiveDate,
+        closing: Option<ClosingEntries>,
+    ) -> Result<Journal, LedgerError> {
+        let mut db = es_entity::DbOp::init_with_clock(&self.pool, &self.clock).await?;
+        self.journals.lock_period_in_op(&mut db, journal_id).await?;
+        let journal = self.journals.find_in_op(&mut db, journal_id).await?;
+        let mut closing_transaction_ids = Vec::new();
+        if let Some(closing) = closing.filter(|_| !journal.is_closed_on(closed_through)) {
+            if !journal.insert_effective_balances() {
+                return Err(
+                    JournalError::ClosingEntriesRequireEffectiveBalances(journal_id).into(),
+                );
+            }
+            let balances = self
+                .balances
+                .effective()
+                .find_all_cumulative_for_set_members_in_op(
+                    &mut db,
+                    journal_id,
+                    closing.account_set_ids(),
+                    closed_through,
+                )
+                .await?;
+            let batch = closing.postings(journal_id, closed_through, balances);
+            closing_transaction_ids = batch.iter().map(|posting| posting.tx_id).collect();
+            self.postings.post_all_in_op(&mut db, batch).await?;
+        }
+        let journal = self
+            .journals
+            .close_period_in_op(&mut db, journal_id, closed_through, closing_transaction_ids)
+            .await?;
+        db.commit().await?;
+        Ok(journal)
+    }
+
+    /// Recompute the balances in `scope` from their entries and report where
+    /// they disagree with what the ledger recorded. See [`crate::integrity`].
+    #[instrument(name = "cala_ledger.verify_integrity", skip(self))]
+    pub async fn verify_integrity(
+        &self,
+        scope: IntegrityScope,
+    ) -> Result<IntegrityReport, LedgerError> {
+        Ok(self.integrity.verify(scope).await?)
+    }
+
+    /// Run [`Self::verify_integrity`] as a job. The [`IntegrityReport`] is
+    /// the result of the job's outcome.
+    #[instrument(name = "cala_ledger.spawn_integrity_verification", skip(self))]
+    pub async fn spawn_integrity_verification(
+        &self,
+        scope: IntegrityScope,
+    ) -> Result<job::JobId, LedgerError> {
+        let job = self
+            .integrity_verification
+            .spawn(job::JobId::new(), IntegrityVerificationConfig { scope })
+            .await
+            .map_err(crate::integrity::error::IntegrityError::from)?;
+        Ok(job.id)
+    }
+
     /// Snapshot the rollup's position, pinning the outbox frontier as a
     /// fence. Cheap and read-only — poll [`lag`](crate::EcRollupStatus::lag)
     /// as a stream-lag SLO metric, or block on the fence with
     /// [`await_completion`](crate::EcRollupStatus::await_completion). Works
-    /// from any node (both sides are read from the database).
+    /// from any node (both sides are read from the database). Also counts
+    /// the member backfills still replaying history outside the stream.
     #[instrument(
         level = "debug",
         name = "cala_ledger.ec_rollup_status",
         skip_all,
-        fields(applied, frontier, lag)
+        fields(applied, frontier, lag, backfills_pending)
     )]
     pub async fn ec_rollup_status(&self) -> Result<crate::EcRollupStatus, LedgerError> {
-        let snapshot = self.ec_rollup.load().await?;
-        let status = crate::EcRollupStatus::new(snapshot.stream_status(), self.ec_rollup.clone());
+        let status =
+            crate::EcRollupStatus::load(self.ec_rollup.clone(), self.backfills.repo().clone())
+                .await?;
 
         let span = tracing::Span::current();
         span.record("applied", u64::from(status.applied));
         span.record("frontier", u64::from(status.frontier));
         span.record("lag", status.lag());
+        span.record("backfills_pending", status.backfills_pending);
 
         Ok(status)
     }
@@ -329,6 +461,24 @@ impl CalaLedger {
         self.publisher.inner()
     }
 
+    /// Follow the balances selected by `filter` as entries post, starting
+    /// after `start_after` (or at the current end of the outbox). Updates
+    /// to eventually-consistent balances are emitted once the rollup has
+    /// applied them.
+    pub fn subscribe_balances(
+        &self,
+        filter: crate::BalanceSubscriptionFilter,
+        start_after: Option<obix::EventSequence>,
+    ) -> impl futures::Stream<Item = Result<crate::BalanceUpdate, LedgerError>> + Send + 'static
+    {
+        crate::balance_subscription::subscribe(
+            self.register_outbox_listener(start_after),
+            &self.balances,
+            &self.ec_rollup,
+            filter,
+        )
+    }
+
     pub fn register_outbox_listener(
         &self,
         start_after: Option<obix::EventSequence>,
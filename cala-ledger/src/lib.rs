@@ -136,6 +136,7 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(clippy::all))]
 
 mod account_set_member;
+mod balance_subscription;
 mod cel_context;
 mod ec_rollup;
 mod param;
@@ -144,13 +145,18 @@ pub mod account;
 pub mod account_set;
 pub mod balance;
 pub mod entry;
+pub mod integrity;
 pub mod journal;
 pub mod migrate;
 pub mod posting;
+pub mod reports;
 pub mod transaction;
 pub mod tx_template;
 pub mod velocity;
 
+// Re-exported so consumers can build a `CelFunctionRegistry` for
+// `CalaLedgerConfig` against the interpreter version cala-ledger uses.
+pub use cel_interpreter;
 pub use es_entity;
 // Re-exported so consumers can pass a `job::Jobs` of the same `job` version
 // cala-ledger links into `CalaLedger::init` for EC-rollup registration.
@@ -159,6 +165,7 @@ pub use job;
 mod ledger;
 pub mod outbox;
 
+pub use balance_subscription::{BalanceSubscriptionFilter, BalanceUpdate};
 pub use ec_rollup::EcRollupStatus;
 pub use ledger::*;
 
@@ -2,7 +2,7 @@ use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
 pub use cala_types::param::*;
-use cel_interpreter::CelExpression;
+use cel_interpreter::{CelExpression, CelType, CelTypeDecl};
 
 #[derive(Clone, Debug, Deserialize, Serialize, Builder)]
 #[builder(build_fn(validate = "Self::validate"))]
@@ -26,6 +26,23 @@ impl NewParamDefinition {
             .as_ref()
             .map(|v| v.parse().expect("Couldn't create default_expr"))
     }
+
+    /// The default expression with the type it must evaluate to. Defaults
+    /// are evaluated before `params` is bound, so they cannot reference it.
+    pub(crate) fn typed_default(&self) -> Option<(&str, CelType)> {
+        self.default
+            .as_deref()
+            .map(|default| (default, self.r#type.cel_type()))
+    }
+}
+
+/// The declaration of the `params` variable bound for `defs` by
+/// [`super::Params::into_context`].
+pub(crate) fn params_type_decl(defs: &[NewParamDefinition]) -> CelTypeDecl {
+    CelTypeDecl::fields(
+        defs.iter()
+            .map(|d| (d.name.clone(), CelTypeDecl::from(d.r#type.cel_type()))),
+    )
 }
 
 impl NewParamDefinitionBuilder {
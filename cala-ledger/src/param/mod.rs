@@ -1,7 +1,7 @@
 pub mod definition;
 pub mod error;
 
-use cel_interpreter::{CelContext, CelMap, CelValue};
+use cel_interpreter::{CelContext, CelEvaluationLimits, CelFunctionRegistry, CelMap, CelValue};
 use es_entity::clock::ClockHandle;
 use std::collections::HashMap;
 use tracing::instrument;
@@ -26,13 +26,16 @@ impl Params {
         self.values.insert(k.into(), v.into());
     }
 
-    #[instrument(level = "debug", name = "params.into_context", skip(self, clock, defs), fields(params_count = self.values.len()), err(level = tracing::Level::WARN))]
+    #[instrument(level = "debug", name = "params.into_context", skip(self, clock, functions, limits, defs), fields(params_count = self.values.len()), err(level = tracing::Level::WARN))]
     pub(crate) fn into_context(
         mut self,
         clock: &ClockHandle,
+        functions: &CelFunctionRegistry,
+        limits: Option<&CelEvaluationLimits>,
         defs: Option<&Vec<ParamDefinition>>,
     ) -> Result<CelContext, ParamError> {
-        let mut ctx = crate::cel_context::initialize(clock.clone());
+        let mut ctx =
+            crate::cel_context::initialize_with_functions(clock.clone(), functions, limits);
         if let Some(defs) = defs {
             let mut cel_map = CelMap::new();
             for d in defs {
@@ -4,7 +4,7 @@ use thiserror::Error;
 use crate::{
     account_set::error::AccountSetError,
     balance::error::BalanceError,
-    primitives::{AccountId, JournalId, TransactionId},
+    primitives::{AccountId, Currency, JournalId, Layer, TransactionId},
     tx_template::error::TxTemplateError,
     velocity::error::VelocityError,
 };
@@ -108,10 +108,26 @@ pub enum RejectionReason {
     JournalLocked(JournalId),
     #[error("journal {0} does not exist")]
     JournalNotFound(JournalId),
+    #[error("journal {journal_id} is closed through {closed_through}, the posting is effective {effective}")]
+    PeriodClosed {
+        journal_id: JournalId,
+        effective: chrono::NaiveDate,
+        closed_through: chrono::NaiveDate,
+    },
     #[error("duplicate transaction id {0} within the submitted batch")]
     DuplicateTransactionIdInBatch(TransactionId),
     #[error("duplicate external id `{0}` within the submitted batch")]
     DuplicateExternalIdInBatch(String),
+    #[error(
+        "account {account_id} would be left with {available} {currency} available on the \
+         {layer:?} layer, outside its balance constraint"
+    )]
+    BalanceConstraintViolated {
+        account_id: AccountId,
+        currency: Currency,
+        layer: Layer,
+        available: rust_decimal::Decimal,
+    },
 }
 
 /// The number of distinct `(journal, account, currency)` triples one batch may
@@ -83,7 +83,7 @@ use chrono::{DateTime, Utc};
 use es_entity::AtomicOperation;
 use tracing::instrument;
 
-use cala_types::{balance::BalanceSnapshot, entry::EntryValues};
+use cala_types::{balance::BalanceSnapshot, entry::EntryValues, journal::JournalValues};
 
 use crate::{
     account_set::AccountSets,
@@ -276,6 +276,7 @@ impl Postings {
         }
 
         let snapshots = self.fold_balances(&hydrated, &entry_values, &read, &mappings, now);
+        Self::enforce_balance_constraints(&batch, &entry_values, &snapshots, &read)?;
 
         // Velocity for the whole batch: one lock, one read, one write — or
         // nothing at all, when no limit's window matches (the common case for
@@ -387,6 +388,20 @@ impl Postings {
                         RejectionReason::JournalLocked(posting.journal_id),
                     ))
                 }
+                Some(JournalValues {
+                    closed_through: Some(closed_through),
+                    ..
+                }) if posting.effective <= *closed_through => {
+                    return Err(PostingError::rejected(
+                        index,
+                        tx_id,
+                        RejectionReason::PeriodClosed {
+                            journal_id: posting.journal_id,
+                            effective: posting.effective,
+                            closed_through: *closed_through,
+                        },
+                    ))
+                }
                 Some(_) => {}
             }
 
@@ -602,6 +617,80 @@ impl Postings {
         all
     }
 
+    /// Reject the earliest posting that takes a balance outside one of its
+    /// account's constraints, or further outside than it already was. A
+    /// balance left outside — say, by a floor added later — can still be
+    /// brought back toward its bounds.
+    ///
+    /// Every snapshot of the fold is checked against the one before it, not
+    /// just each balance's last against the stored one, so a posting cannot
+    /// borrow headroom from a later one in the batch — the same result as
+    /// posting them one at a time.
+    fn enforce_balance_constraints(
+        batch: &[PostingInput],
+        entry_values: &[Vec<EntryValues>],
+        snapshots: &[BalanceSnapshot],
+        read: &PostingState,
+    ) -> Result<(), PostingError> {
+        let folded: HashMap<_, &BalanceSnapshot> = snapshots
+            .iter()
+            .map(|s| ((s.journal_id, s.account_id, s.currency, s.version), s))
+            .collect();
+        let mut posting_of_entry = HashMap::new();
+        let mut violation = None;
+        for snapshot in snapshots {
+            let Some(meta) = read.accounts.get(&snapshot.account_id) else {
+                continue;
+            };
+            let key = (snapshot.journal_id, snapshot.account_id, snapshot.currency);
+            let before = folded
+                .get(&(key.0, key.1, key.2, snapshot.version - 1))
+                .copied()
+                .or_else(|| read.balances.get(&key));
+            for constraint in meta.balance_constraints.iter() {
+                if !constraint.applies_to(snapshot.currency) {
+                    continue;
+                }
+                let available =
+                    crate::balance::BalanceWithDirection::new(meta.normal_balance_type, snapshot)
+                        .available(constraint.layer);
+                let available_before = before.map_or(rust_decimal::Decimal::ZERO, |before| {
+                    crate::balance::BalanceWithDirection::new(meta.normal_balance_type, before)
+                        .available(constraint.layer)
+                });
+                if constraint.excess(available) <= constraint.excess(available_before) {
+                    continue;
+                }
+                if posting_of_entry.is_empty() {
+                    posting_of_entry = entry_values
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(index, values)| values.iter().map(move |e| (e.id, index)))
+                        .collect();
+                }
+                // Every snapshot of the fold is of an entry in the batch.
+                let Some(&index) = posting_of_entry.get(&snapshot.entry_id) else {
+                    continue;
+                };
+                if violation.as_ref().is_none_or(|(i, _)| index < *i) {
+                    violation = Some((
+                        index,
+                        RejectionReason::BalanceConstraintViolated {
+                            account_id: snapshot.account_id,
+                            currency: snapshot.currency,
+                            layer: constraint.layer,
+                            available,
+                        },
+                    ));
+                }
+            }
+        }
+        match violation {
+            Some((index, reason)) => Err(PostingError::rejected(index, batch[index].tx_id, reason)),
+            None => Ok(()),
+        }
+    }
+
     // ------------------------------------------------------------------
     // velocity + effective balances
     // ------------------------------------------------------------------
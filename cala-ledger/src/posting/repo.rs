@@ -42,6 +42,12 @@
 //! only the membership graph — never balance values — so lock-before-read still
 //! holds across statements.
 //!
+//! Period locks (`PERIOD_LOCK_CLASS`, keyed on the journal): SHARED = the
+//! poster, on every journal of the batch and ahead of its other locks, so the
+//! closed-through check of the read phase cannot race a close; EXCLUSIVE = a
+//! period close (`Journals::lock_period_in_op`), held while it reads the
+//! balances it closes and posts its closing entries.
+//!
 //! **Lock-ordering invariant.** The `ORDER BY` in
 //! [`PostingRepo::lock_balances_and_probe_templates_in_op`] is what makes
 //! acquisition order canonical — do not remove it. It is *required* because
@@ -71,7 +77,8 @@ use chrono::{DateTime, NaiveDate, Utc};
 use serde::Deserialize;
 
 use cala_types::{
-    balance::BalanceSnapshot, journal::JournalValues, velocity::VelocityContextAccountValues,
+    account::BalanceConstraint, balance::BalanceSnapshot, journal::JournalValues,
+    velocity::VelocityContextAccountValues,
 };
 
 use crate::{
@@ -86,6 +93,11 @@ use crate::{
 /// namespace. Must stay in sync with `BalanceRepo::EC_SET_LOCK_CLASS`.
 const EC_SET_LOCK_CLASS: i32 = 1;
 
+/// Advisory-lock class of the per-journal period lock, taken SHARED here
+/// and EXCLUSIVE by a period close. Must stay in sync with
+/// `JournalRepo::PERIOD_LOCK_CLASS`.
+const PERIOD_LOCK_CLASS: i32 = 4;
+
 /// Maximum balance snapshots written per `INSERT` in
 /// [`PostingRepo::insert_postings_and_balances_in_op`]. A large batch fanning
 /// into deep ancestor chains would otherwise become one multi-million-row
@@ -158,6 +170,8 @@ pub(super) struct AccountMeta {
     pub locked: bool,
     pub eventually_consistent: bool,
     pub is_account_set: bool,
+    pub normal_balance_type: DebitOrCredit,
+    pub balance_constraints: Vec<BalanceConstraint>,
 }
 
 /// Everything the flow reads, in one statement.
@@ -182,7 +196,14 @@ pub(super) struct AncestorState {
 struct SeedRow(AccountId, AccountSetId);
 
 #[derive(Deserialize)]
-struct AccountRow(AccountId, String, bool, bool);
+struct AccountRow(
+    AccountId,
+    String,
+    bool,
+    bool,
+    DebitOrCredit,
+    Vec<BalanceConstraint>,
+);
 
 #[derive(Deserialize)]
 struct ControlRow(
@@ -336,7 +357,9 @@ impl PostingRepo {
     /// and re-check the template versions preparation used — one statement.
     ///
     /// See the module doc for why the `ORDER BY` and `MATERIALIZED` are
-    /// load-bearing. The template probe rides along because it reads nothing
+    /// load-bearing. The journals' period locks come first — the balance
+    /// locks' input joins their count — matching a period close, which takes
+    /// its journal's lock before posting its closing entries. The template probe rides along because it reads nothing
     /// the fence protects: templates are independent of the balance rows being
     /// locked.
     #[tracing::instrument(
@@ -355,7 +378,12 @@ impl PostingRepo {
     ) -> Result<LockOutcome, sqlx::Error> {
         let rows = sqlx::query!(
             r#"
-            WITH locks AS MATERIALIZED (
+            WITH period_locks AS MATERIALIZED (
+                SELECT pg_advisory_xact_lock_shared($7::int4, hashtext(j.journal_id::text))
+                FROM (SELECT DISTINCT journal_id FROM UNNEST($2::uuid[]) AS journal_id) j
+                ORDER BY j.journal_id
+            ),
+            locks AS MATERIALIZED (
                 SELECT
                     pg_advisory_xact_lock_shared($1::int4, hashtext(v.account_id::text)),
                     CASE WHEN NOT a.eventually_consistent THEN
@@ -366,6 +394,7 @@ impl PostingRepo {
                 FROM UNNEST($2::uuid[], $3::uuid[], $4::text[])
                     AS v(journal_id, account_id, currency)
                 JOIN cala_accounts a ON a.id = v.account_id
+                CROSS JOIN (SELECT COUNT(*) FROM period_locks) AS p
                 ORDER BY v.account_id, v.currency, v.journal_id
             ),
             templates AS (
@@ -390,6 +419,7 @@ impl PostingRepo {
             &keys.currencies as &[&str],
             codes,
             manual_now,
+            PERIOD_LOCK_CLASS,
         )
         .fetch_all(op.as_executor())
         .await?;
@@ -443,7 +473,8 @@ impl PostingRepo {
                 ) AS "seeds",
                 (
                     SELECT jsonb_agg(jsonb_build_array(
-                        a.id, a.status::text, a.eventually_consistent, a.is_account_set
+                        a.id, a.status::text, a.eventually_consistent, a.is_account_set,
+                        a.normal_balance_type, a.balance_constraints
                     ))
                     FROM cala_accounts a
                     WHERE a.id = ANY($1::uuid[])
@@ -527,7 +558,8 @@ impl PostingRepo {
             SELECT
                 (
                     SELECT jsonb_agg(jsonb_build_array(
-                        a.id, a.status::text, a.eventually_consistent, a.is_account_set
+                        a.id, a.status::text, a.eventually_consistent, a.is_account_set,
+                        a.normal_balance_type, a.balance_constraints
                     ))
                     FROM cala_accounts a
                     WHERE a.id = ANY($1::uuid[])
@@ -788,13 +820,22 @@ impl PostingRepo {
     fn index_accounts(rows: Vec<AccountRow>) -> HashMap<AccountId, AccountMeta> {
         rows.into_iter()
             .map(
-                |AccountRow(id, status, eventually_consistent, is_account_set)| {
+                |AccountRow(
+                    id,
+                    status,
+                    eventually_consistent,
+                    is_account_set,
+                    normal_balance_type,
+                    balance_constraints,
+                )| {
                     (
                         id,
                         AccountMeta {
                             locked: status == "locked",
                             eventually_consistent,
                             is_account_set,
+                            normal_balance_type,
+                            balance_constraints,
                         },
                     )
                 },
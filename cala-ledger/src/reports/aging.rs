@@ -0,0 +1,209 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use cala_types::primitives::*;
+
+use super::error::ReportError;
+
+/// Options of a [`super::Reports::aging`].
+#[derive(Debug, Clone)]
+pub struct AgingArgs {
+    pub currency: Currency,
+    /// Age the balance available on this layer.
+    pub layer: Layer,
+    /// Effective date the ages are counted up to.
+    pub as_of: NaiveDate,
+    /// Inclusive upper bounds in days of every bucket but the last, in
+    /// strictly ascending order and below `u32::MAX`. The last bucket holds
+    /// everything older.
+    pub bucket_bounds: Vec<u32>,
+}
+
+impl AgingArgs {
+    /// Settled balance in the buckets 0–30, 31–60, 61–90 and 91+ days.
+    pub fn new(currency: Currency, as_of: NaiveDate) -> Self {
+        Self {
+            currency,
+            layer: Layer::Settled,
+            as_of,
+            bucket_bounds: vec![30, 60, 90],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgingBucket {
+    pub from_days: u32,
+    /// `None` for the last, open ended bucket.
+    pub to_days: Option<u32>,
+    pub amount: Decimal,
+    /// The debit entries whose outstanding part makes up `amount`, newest
+    /// first. The oldest of them may only be partly outstanding.
+    pub entry_ids: Vec<EntryId>,
+}
+
+/// How old the debit balance of an account is, or of the accounts below an
+/// account set.
+///
+/// Credits are matched against debits first in, first out, so whatever is
+/// outstanding is made up of the most recent debits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Aging {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub layer: Layer,
+    pub as_of: NaiveDate,
+    /// Sum of the buckets.
+    pub outstanding: Decimal,
+    /// Credits in excess of the debits of an account, not applied to any
+    /// bucket.
+    pub unapplied_credit: Decimal,
+    pub buckets: Vec<AgingBucket>,
+}
+
+impl Aging {
+    pub(super) fn new(
+        journal_id: JournalId,
+        account_id: AccountId,
+        args: &AgingArgs,
+    ) -> Result<Self, ReportError> {
+        let mut from_days = 0;
+        let mut buckets = Vec::with_capacity(args.bucket_bounds.len() + 1);
+        for &to_days in args.bucket_bounds.iter() {
+            if to_days < from_days || to_days == u32::MAX {
+                return Err(ReportError::InvalidBucketBounds(args.bucket_bounds.clone()));
+            }
+            buckets.push(AgingBucket {
+                from_days,
+                to_days: Some(to_days),
+                amount: Decimal::ZERO,
+                entry_ids: Vec::new(),
+            });
+            from_days = to_days + 1;
+        }
+        buckets.push(AgingBucket {
+            from_days,
+            to_days: None,
+            amount: Decimal::ZERO,
+            entry_ids: Vec::new(),
+        });
+        Ok(Self {
+            journal_id,
+            account_id,
+            currency: args.currency,
+            layer: args.layer,
+            as_of: args.as_of,
+            outstanding: Decimal::ZERO,
+            unapplied_credit: Decimal::ZERO,
+            buckets,
+        })
+    }
+
+    /// Age the net debit balance `outstanding` of one account against its
+    /// debits, newest first.
+    pub(super) fn add_account(
+        &mut self,
+        mut outstanding: Decimal,
+        debits: impl IntoIterator<Item = AgingDebit>,
+    ) {
+        if outstanding.is_sign_negative() {
+            self.unapplied_credit -= outstanding;
+            return;
+        }
+        for debit in debits {
+            if outstanding.is_zero() {
+                break;
+            }
+            let amount = debit.units.min(outstanding);
+            outstanding -= amount;
+            let days = (self.as_of - debit.effective).num_days().max(0) as u32;
+            let bucket = self
+                .buckets
+                .iter_mut()
+                .find(|bucket| bucket.to_days.is_none_or(|to_days| days <= to_days))
+                .expect("the last bucket is open ended");
+            bucket.amount += amount;
+            bucket.entry_ids.push(debit.entry_id);
+            self.outstanding += amount;
+        }
+    }
+}
+
+pub(super) struct AgingDebit {
+    pub entry_id: EntryId,
+    pub effective: NaiveDate,
+    pub units: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debit(effective: NaiveDate, units: i64) -> AgingDebit {
+        AgingDebit {
+            entry_id: EntryId::new(),
+            effective,
+            units: Decimal::from(units),
+        }
+    }
+
+    #[test]
+    fn matches_credits_against_the_oldest_debits() {
+        let as_of = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let args = AgingArgs::new(Currency::USD, as_of);
+        let mut aging = Aging::new(JournalId::new(), AccountId::new(), &args).unwrap();
+
+        // Debits of 100 at 10, 45 and 120 days, 150 of them repaid.
+        let newest_first = vec![
+            debit(as_of - chrono::Days::new(10), 100),
+            debit(as_of - chrono::Days::new(45), 100),
+            debit(as_of - chrono::Days::new(120), 100),
+        ];
+        let oldest_partial = newest_first[1].entry_id;
+        aging.add_account(Decimal::from(150), newest_first);
+
+        let amounts: Vec<_> = aging.buckets.iter().map(|b| b.amount).collect();
+        assert_eq!(
+            amounts,
+            vec![
+                Decimal::from(100),
+                Decimal::from(50),
+                Decimal::ZERO,
+                Decimal::ZERO
+            ]
+        );
+        assert_eq!(aging.buckets[1].entry_ids, vec![oldest_partial]);
+        assert!(aging.buckets[3].entry_ids.is_empty());
+        assert_eq!(aging.outstanding, Decimal::from(150));
+    }
+
+    #[test]
+    fn keeps_excess_credit_out_of_the_buckets() {
+        let as_of = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let args = AgingArgs::new(Currency::USD, as_of);
+        let mut aging = Aging::new(JournalId::new(), AccountId::new(), &args).unwrap();
+        aging.add_account(Decimal::from(-20), vec![debit(as_of, 100)]);
+        assert_eq!(aging.unapplied_credit, Decimal::from(20));
+        assert_eq!(aging.outstanding, Decimal::ZERO);
+        assert_eq!(aging.buckets.len(), 4);
+        assert_eq!(aging.buckets[3].from_days, 91);
+    }
+
+    #[test]
+    fn rejects_bucket_bounds_out_of_order_or_at_the_limit() {
+        let as_of = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let mut args = AgingArgs::new(Currency::USD, as_of);
+        for bounds in [vec![30, 30], vec![60, 30], vec![30, u32::MAX]] {
+            args.bucket_bounds = bounds;
+            assert!(matches!(
+                Aging::new(JournalId::new(), AccountId::new(), &args),
+                Err(ReportError::InvalidBucketBounds(_))
+            ));
+        }
+        args.bucket_bounds = vec![0, u32::MAX - 1];
+        let aging = Aging::new(JournalId::new(), AccountId::new(), &args).unwrap();
+        assert_eq!(aging.buckets[2].from_days, u32::MAX);
+    }
+}
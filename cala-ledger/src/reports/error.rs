@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use crate::primitives::{AccountSetId, JournalId};
+
+#[derive(Error, Debug)]
+pub enum ReportError {
+    #[error("ReportError - Sqlx: {0}")]
+    Sqlx(#[from] sqlx::Error),
+    #[error("ReportError - BalanceError: {0}")]
+    BalanceError(#[from] crate::balance::error::BalanceError),
+    #[error("ReportError - CelError: {0}")]
+    CelError(#[from] cel_interpreter::CelError),
+    #[error("ReportError - InvalidBucketBounds: aging bucket bounds must be strictly ascending and below u32::MAX, got {0:?}")]
+    InvalidBucketBounds(Vec<u32>),
+    #[error("ReportError - AccountSetNotFound: account set {0} does not exist")]
+    AccountSetNotFound(AccountSetId),
+    #[error("ReportError - JournalIdMismatch: account set {0} is not in journal {1}")]
+    JournalIdMismatch(AccountSetId, JournalId),
+}
@@ -0,0 +1,36 @@
+use cel_interpreter::CelExpression;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use cala_types::primitives::*;
+
+use super::trial_balance::DebitCreditTotals;
+
+/// Options of a [`super::Reports::filtered_balance`].
+#[derive(Debug, Clone)]
+pub struct FilteredBalanceArgs {
+    /// Evaluated for every account below the set with its values bound to
+    /// `account`, in the shape velocity controls see them (`id`, `name`,
+    /// `externalId`, `normalBalanceType`, `metadata`). Accounts it yields
+    /// `true` for are aggregated, e.g. `account.metadata.region == 'SV'`.
+    pub predicate: CelExpression,
+    pub currency: Currency,
+    /// Effective date the balances are taken at.
+    pub as_of: NaiveDate,
+}
+
+/// The balance of the accounts below an account set that matched a
+/// predicate, summed per layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilteredBalance {
+    pub journal_id: JournalId,
+    pub account_set_id: AccountSetId,
+    pub currency: Currency,
+    pub as_of: NaiveDate,
+    /// The accounts that matched, ordered by id. Accounts without a
+    /// balance as of the date are listed but contribute nothing.
+    pub account_ids: Vec<AccountId>,
+    pub settled: DebitCreditTotals,
+    pub pending: DebitCreditTotals,
+    pub encumbrance: DebitCreditTotals,
+}
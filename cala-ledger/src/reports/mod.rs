@@ -0,0 +1,249 @@
+//! Reports computed across a journal's balances.
+//!
+//! Account-set membership is kept by recorded time. The trial balance,
+//! itself as of a recorded time, reads the members a set had then. The
+//! statement, aging and filtered balance reports run over effective dates,
+//! which membership has no history for, and use the current members.
+
+mod aging;
+pub mod error;
+mod filtered_balance;
+mod repo;
+mod statement;
+mod trial_balance;
+
+use chrono::{DateTime, Utc};
+use es_entity::clock::ClockHandle;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use tracing::instrument;
+
+use std::collections::HashSet;
+
+use crate::{
+    balance::Balances,
+    cel_context::*,
+    primitives::{AccountId, AccountSetId, Currency, JournalId, Layer},
+};
+
+pub use aging::*;
+use error::ReportError;
+pub use filtered_balance::*;
+use repo::*;
+pub use statement::*;
+pub use trial_balance::*;
+
+/// Options narrowing a [`Reports::trial_balance`].
+#[derive(Debug, Clone, Default)]
+pub struct TrialBalanceArgs {
+    /// Only report balances in this currency.
+    pub currency: Option<Currency>,
+    /// Report the balances as they were recorded at this time instead of the
+    /// current ones.
+    pub as_of: Option<DateTime<Utc>>,
+    /// Report one line per direct member of this set (account sets
+    /// contributing their rolled up balance) instead of one per account.
+    pub account_set_id: Option<AccountSetId>,
+}
+
+#[derive(Clone)]
+pub struct Reports {
+    repo: ReportRepo,
+    balances: Balances,
+    clock: ClockHandle,
+    cel_functions: CelFunctionRegistry,
+    evaluation_limits: Option<CelEvaluationLimits>,
+}
+
+impl Reports {
+    pub(crate) fn new(
+        pool: &PgPool,
+        balances: &Balances,
+        clock: &ClockHandle,
+        cel_functions: &CelFunctionRegistry,
+        evaluation_limits: Option<CelEvaluationLimits>,
+    ) -> Self {
+        Self {
+            repo: ReportRepo::new(pool),
+            balances: balances.clone(),
+            clock: clock.clone(),
+            cel_functions: cel_functions.clone(),
+            evaluation_limits,
+        }
+    }
+
+    /// The debit and credit totals of every account in the journal per
+    /// currency and layer, together with the grand totals.
+    ///
+    /// Account-set balances are left out unless `account_set_id` is given, as
+    /// they duplicate the balances of their members. The members of the set
+    /// are those it had at `as_of`.
+    #[instrument(name = "cala_ledger.reports.trial_balance", skip(self))]
+    pub async fn trial_balance(
+        &self,
+        journal_id: JournalId,
+        args: TrialBalanceArgs,
+    ) -> Result<TrialBalance, ReportError> {
+        let lines = self
+            .repo
+            .trial_balance_lines(journal_id, args.currency, args.as_of, args.account_set_id)
+            .await?;
+        Ok(TrialBalance::new(journal_id, args.as_of, lines))
+    }
+
+    /// A hierarchical statement of the account sets `root_ids` over a period
+    /// of effective dates: each node with its opening balance, the activity
+    /// of the period and its closing balance.
+    ///
+    /// Passing the asset, liability and equity roots with `from` the first
+    /// date of the books yields a balance sheet; passing the revenue and
+    /// expense roots yields an income statement for the period. The tree is
+    /// the current membership of the roots. Requires effective balances to be
+    /// enabled on the journal.
+    #[instrument(name = "cala_ledger.reports.statement", skip(self))]
+    pub async fn statement(
+        &self,
+        journal_id: JournalId,
+        root_ids: &[AccountSetId],
+        args: StatementArgs,
+    ) -> Result<Statement, ReportError> {
+        let rows = self.repo.statement_tree(root_ids).await?;
+        let ids: Vec<_> = rows
+            .iter()
+            .map(|row| (journal_id, row.account_id, args.currency))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let ranges = self
+            .balances
+            .effective()
+            .find_all_in_range(&ids, args.from, args.until)
+            .await?
+            .into_iter()
+            .map(|((_, account_id, _), range)| (account_id, range))
+            .collect();
+        Ok(Statement {
+            roots: build_nodes(root_ids, rows, ranges, args.layer),
+            journal_id,
+            currency: args.currency,
+            layer: args.layer,
+            from: args.from,
+            until: args.until,
+        })
+    }
+
+    /// How old the debit balance of `account_id` is as of `args.as_of`, in
+    /// the buckets of `args.bucket_bounds`. Given an account set, each
+    /// account currently below it is aged on its own and the buckets are
+    /// summed.
+    ///
+    /// Requires effective balances to be enabled on the journal.
+    #[instrument(name = "cala_ledger.reports.aging", skip(self))]
+    pub async fn aging(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        args: AgingArgs,
+    ) -> Result<Aging, ReportError> {
+        let account_id = account_id.into();
+        let mut aging = Aging::new(journal_id, account_id, &args)?;
+        let ids: Vec<_> = self
+            .repo
+            .aging_accounts(account_id)
+            .await?
+            .into_iter()
+            .map(|id| (journal_id, id, args.currency))
+            .collect();
+        let outstanding: Vec<(AccountId, Decimal)> = self
+            .balances
+            .effective()
+            .find_all_cumulative(&ids, args.as_of)
+            .await?
+            .into_iter()
+            .map(|((_, id, _), balance)| {
+                let amount = balance.details.available(args.layer);
+                (id, amount.dr_balance - amount.cr_balance)
+            })
+            .collect();
+        let layers = match args.layer {
+            Layer::Settled => &[Layer::Settled][..],
+            Layer::Pending => &[Layer::Settled, Layer::Pending],
+            Layer::Encumbrance => &[Layer::Settled, Layer::Pending, Layer::Encumbrance],
+        };
+        let owing: Vec<_> = outstanding
+            .iter()
+            .filter(|(_, amount)| *amount > Decimal::ZERO)
+            .map(|(id, _)| *id)
+            .collect();
+        let mut debits = self
+            .repo
+            .aging_debits(journal_id, &owing, args.currency, layers, args.as_of)
+            .await?;
+
+        for (id, amount) in outstanding {
+            aging.add_account(amount, debits.remove(&id).unwrap_or_default());
+        }
+        Ok(aging)
+    }
+
+    /// The balance of the accounts currently below `account_set_id` that match
+    /// `args.predicate`, as of `args.as_of`. Lets a report slice a set by
+    /// account metadata without a set per slice.
+    ///
+    /// The set must belong to `journal_id`. The predicate runs under the
+    /// ledger's CEL evaluation limits. Requires effective balances to be
+    /// enabled on the journal.
+    #[instrument(name = "cala_ledger.reports.filtered_balance", skip(self))]
+    pub async fn filtered_balance(
+        &self,
+        journal_id: JournalId,
+        account_set_id: AccountSetId,
+        args: FilteredBalanceArgs,
+    ) -> Result<FilteredBalance, ReportError> {
+        match self.repo.account_set_journal(account_set_id).await? {
+            None => return Err(ReportError::AccountSetNotFound(account_set_id)),
+            Some(set_journal_id) if set_journal_id != journal_id => {
+                return Err(ReportError::JournalIdMismatch(account_set_id, journal_id))
+            }
+            Some(_) => {}
+        }
+        let mut ctx = initialize_with_functions(
+            self.clock.clone(),
+            &self.cel_functions,
+            self.evaluation_limits.as_ref(),
+        );
+        let mut account_ids = Vec::new();
+        for values in self.repo.filtered_balance_accounts(account_set_id).await? {
+            ctx.add_variable("account", &values);
+            if args.predicate.try_evaluate::<bool>(&ctx)? {
+                account_ids.push(values.id);
+            }
+        }
+        let ids: Vec<_> = account_ids
+            .iter()
+            .map(|id| (journal_id, *id, args.currency))
+            .collect();
+        let mut ret = FilteredBalance {
+            journal_id,
+            account_set_id,
+            currency: args.currency,
+            as_of: args.as_of,
+            account_ids,
+            settled: Default::default(),
+            pending: Default::default(),
+            encumbrance: Default::default(),
+        };
+        for balance in self
+            .balances
+            .effective()
+            .find_all_cumulative(&ids, args.as_of)
+            .await?
+            .into_values()
+        {
+            ret.settled += (&balance.details.settled).into();
+            ret.pending += (&balance.details.pending).into();
+            ret.encumbrance += (&balance.details.encumbrance).into();
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,306 @@
+use chrono::{DateTime, NaiveDate, SubsecRound, Utc};
+use sqlx::PgPool;
+
+use std::collections::HashMap;
+use tracing::instrument;
+
+use cala_types::{
+    balance::BalanceSnapshot, entry::EntryValues, primitives::*,
+    velocity::VelocityContextAccountValues,
+};
+
+use super::{aging::AgingDebit, statement::StatementTreeRow, trial_balance::TrialBalanceLine};
+
+#[derive(Debug, Clone)]
+pub(super) struct ReportRepo {
+    pool: PgPool,
+}
+
+impl ReportRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Reads either the current balances or, given `as_of`, the last
+    /// snapshot of each balance recorded by then. `modified_at` is rounded to
+    /// microseconds when cast, so the bound is rounded the same way. The
+    /// members of `account_set_id` are read from the membership history at
+    /// the same time; without `as_of` only the open periods match.
+    #[instrument(name = "reports.trial_balance_lines", skip(self), err(level = "warn"))]
+    pub async fn trial_balance_lines(
+        &self,
+        journal_id: JournalId,
+        currency: Option<Currency>,
+        as_of: Option<DateTime<Utc>>,
+        account_set_id: Option<AccountSetId>,
+    ) -> Result<Vec<TrialBalanceLine>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            WITH balances AS (
+                SELECT c.account_id, c.latest_values AS values
+                FROM cala_current_balances c
+                WHERE $3::timestamptz IS NULL
+                  AND c.journal_id = $1
+                  AND ($2::varchar IS NULL OR c.currency = $2)
+                UNION ALL
+                (
+                    SELECT DISTINCT ON (h.account_id, h.currency) h.account_id, h.values
+                    FROM cala_balance_history h
+                    WHERE $3::timestamptz IS NOT NULL
+                      AND h.journal_id = $1
+                      AND ($2::varchar IS NULL OR h.currency = $2)
+                      AND (h.values->>'modified_at')::timestamptz <= $3
+                    ORDER BY h.account_id, h.currency, h.version DESC
+                )
+            ),
+            members AS (
+                SELECT member_id AS account_id
+                FROM cala_account_set_member_history
+                WHERE account_set_id = $4
+                  AND joined_at <= COALESCE($3, 'infinity')
+                  AND (left_at IS NULL OR left_at > COALESCE($3, 'infinity'))
+            )
+            SELECT
+                a.code AS "code!",
+                a.name AS "name!",
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit",
+                a.is_account_set AS "is_account_set!",
+                b.values AS "values!"
+            FROM balances b
+            JOIN cala_accounts a ON a.id = b.account_id
+            WHERE CASE
+                WHEN $4::uuid IS NULL THEN NOT a.is_account_set
+                ELSE a.id IN (SELECT account_id FROM members)
+            END
+            ORDER BY a.code, b.values->>'currency'
+            "#,
+            journal_id as JournalId,
+            currency.map(|c| c.code()),
+            as_of.map(|t| t.round_subsecs(6)),
+            account_set_id as Option<AccountSetId>,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let snapshot: BalanceSnapshot = serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot");
+                TrialBalanceLine {
+                    account_id: snapshot.account_id,
+                    code: row.code,
+                    name: row.name,
+                    normal_balance_type: row.normal_balance_type,
+                    is_account_set: row.is_account_set,
+                    currency: snapshot.currency,
+                    settled: (&snapshot.settled).into(),
+                    pending: (&snapshot.pending).into(),
+                    encumbrance: (&snapshot.encumbrance).into(),
+                }
+            })
+            .collect())
+    }
+
+    /// Every account and account set below the given roots, the roots
+    /// themselves included without a parent. A member reachable along
+    /// several paths is returned once per set it belongs to.
+    #[instrument(name = "reports.statement_tree", skip(self), err(level = "warn"))]
+    pub async fn statement_tree(
+        &self,
+        root_ids: &[AccountSetId],
+    ) -> Result<Vec<StatementTreeRow>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE tree AS (
+                SELECT NULL::uuid AS parent_id, id AS account_id
+                FROM UNNEST($1::uuid[]) AS id
+                UNION
+                SELECT t.account_id, m.member_id
+                FROM tree t
+                JOIN (
+                    SELECT account_set_id, member_account_set_id AS member_id
+                    FROM cala_account_set_member_account_sets
+                    UNION ALL
+                    SELECT account_set_id, member_account_id
+                    FROM cala_account_set_member_accounts
+                ) m ON m.account_set_id = t.account_id
+            )
+            SELECT
+                t.parent_id AS "parent_id?: AccountId",
+                a.id AS "account_id!: AccountId",
+                a.code AS "code!",
+                a.name AS "name!",
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit",
+                a.is_account_set AS "is_account_set!"
+            FROM tree t
+            JOIN cala_accounts a ON a.id = t.account_id
+            ORDER BY a.code
+            "#,
+            root_ids as &[AccountSetId],
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| StatementTreeRow {
+                parent_id: row.parent_id,
+                account_id: row.account_id,
+                code: row.code,
+                name: row.name,
+                normal_balance_type: row.normal_balance_type,
+                is_account_set: row.is_account_set,
+            })
+            .collect())
+    }
+
+    /// The account itself, or every account (not account set) below it when
+    /// it is an account set.
+    #[instrument(name = "reports.aging_accounts", skip(self), err(level = "warn"))]
+    pub async fn aging_accounts(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Vec<AccountId>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE sets AS (
+                SELECT $1::uuid AS id
+                UNION
+                SELECT m.member_account_set_id
+                FROM cala_account_set_member_account_sets m
+                JOIN sets s ON m.account_set_id = s.id
+            ),
+            accounts AS (
+                SELECT $1::uuid AS id
+                UNION
+                SELECT m.member_account_id
+                FROM cala_account_set_member_accounts m
+                JOIN sets s ON m.account_set_id = s.id
+            )
+            SELECT a.id AS "id!: AccountId"
+            FROM accounts
+            JOIN cala_accounts a ON a.id = accounts.id
+            WHERE NOT a.is_account_set
+            "#,
+            account_id as AccountId,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// The journal of `account_set_id`, if the set exists.
+    #[instrument(name = "reports.account_set_journal", skip(self), err(level = "warn"))]
+    pub async fn account_set_journal(
+        &self,
+        account_set_id: AccountSetId,
+    ) -> Result<Option<JournalId>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT journal_id AS "journal_id: JournalId"
+            FROM cala_account_sets
+            WHERE id = $1
+            "#,
+            account_set_id as AccountSetId,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| row.journal_id))
+    }
+
+    /// The velocity context values of every account (not account set)
+    /// below the set, ordered by id.
+    #[instrument(
+        name = "reports.filtered_balance_accounts",
+        skip(self),
+        err(level = "warn")
+    )]
+    pub async fn filtered_balance_accounts(
+        &self,
+        account_set_id: AccountSetId,
+    ) -> Result<Vec<VelocityContextAccountValues>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE sets AS (
+                SELECT $1::uuid AS id
+                UNION
+                SELECT m.member_account_set_id
+                FROM cala_account_set_member_account_sets m
+                JOIN sets s ON m.account_set_id = s.id
+            ),
+            accounts AS (
+                SELECT m.member_account_id AS id
+                FROM cala_account_set_member_accounts m
+                JOIN sets s ON m.account_set_id = s.id
+            )
+            SELECT DISTINCT
+                a.id AS "id!: AccountId",
+                a.velocity_context_values AS "values!: VelocityContextAccountValues"
+            FROM accounts
+            JOIN cala_accounts a ON a.id = accounts.id
+            WHERE NOT a.is_account_set
+            ORDER BY a.id
+            "#,
+            account_set_id as AccountSetId,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.values).collect())
+    }
+
+    /// The debit entries of `account_ids` on the given layers effective by
+    /// `as_of`, per account, newest first.
+    #[instrument(
+        name = "reports.aging_debits",
+        skip(self, account_ids),
+        fields(accounts = account_ids.len()),
+        err(level = "warn")
+    )]
+    pub async fn aging_debits(
+        &self,
+        journal_id: JournalId,
+        account_ids: &[AccountId],
+        currency: Currency,
+        layers: &[Layer],
+        as_of: NaiveDate,
+    ) -> Result<HashMap<AccountId, Vec<AgingDebit>>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT t.effective, ev.event->'values' AS "values!"
+            FROM cala_entries e
+            JOIN cala_entry_events ev ON ev.id = e.id AND ev.sequence = 1
+            JOIN cala_transactions t ON t.id = e.transaction_id
+            WHERE e.journal_id = $1
+              AND e.account_id = ANY($2)
+              AND ev.event->'values'->>'currency' = $3
+              AND ev.event->'values'->>'direction' = 'debit'
+              AND t.effective <= $4
+            ORDER BY t.effective DESC, e.created_at DESC, e.id DESC
+            "#,
+            journal_id as JournalId,
+            account_ids as &[AccountId],
+            currency.code(),
+            as_of,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut debits: HashMap<AccountId, Vec<AgingDebit>> = HashMap::new();
+        for row in rows {
+            let entry: EntryValues =
+                serde_json::from_value(row.values).expect("Failed to deserialize entry values");
+            if layers.contains(&entry.layer) {
+                debits
+                    .entry(entry.account_id)
+                    .or_default()
+                    .push(AgingDebit {
+                        entry_id: entry.id,
+                        effective: row.effective,
+                        units: entry.units,
+                    });
+            }
+        }
+        Ok(debits)
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use cala_types::primitives::*;
+
+use crate::balance::BalanceRange;
+
+/// Options of a [`super::Reports::statement`].
+#[derive(Debug, Clone)]
+pub struct StatementArgs {
+    pub currency: Currency,
+    pub layer: Layer,
+    /// First effective date of the period.
+    pub from: NaiveDate,
+    /// Last effective date of the period, today if `None`.
+    pub until: Option<NaiveDate>,
+}
+
+/// One account or account set of a statement, with the balances of its
+/// layer expressed in its own `normal_balance_type`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatementNode {
+    pub account_id: AccountId,
+    pub code: String,
+    pub name: String,
+    pub normal_balance_type: DebitOrCredit,
+    pub is_account_set: bool,
+    pub opening: Decimal,
+    pub period: Decimal,
+    pub closing: Decimal,
+    /// Members of an account set, ordered by code.
+    pub children: Vec<StatementNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Statement {
+    pub journal_id: JournalId,
+    pub currency: Currency,
+    pub layer: Layer,
+    pub from: NaiveDate,
+    pub until: Option<NaiveDate>,
+    /// One tree per requested root, in the order requested.
+    pub roots: Vec<StatementNode>,
+}
+
+pub(super) struct StatementTreeRow {
+    pub parent_id: Option<AccountId>,
+    pub account_id: AccountId,
+    pub code: String,
+    pub name: String,
+    pub normal_balance_type: DebitOrCredit,
+    pub is_account_set: bool,
+}
+
+pub(super) fn build_nodes(
+    root_ids: &[AccountSetId],
+    rows: Vec<StatementTreeRow>,
+    ranges: HashMap<AccountId, BalanceRange>,
+    layer: Layer,
+) -> Vec<StatementNode> {
+    let mut children: HashMap<Option<AccountId>, Vec<StatementTreeRow>> = HashMap::new();
+    for row in rows {
+        children.entry(row.parent_id).or_default().push(row);
+    }
+    let roots: HashMap<_, _> = children
+        .get(&None)
+        .into_iter()
+        .flatten()
+        .map(|row| (row.account_id, row))
+        .collect();
+    root_ids
+        .iter()
+        .filter_map(|id| roots.get(&AccountId::from(id)))
+        .map(|row| build_node(row, &children, &ranges, layer))
+        .collect()
+}
+
+/// Accounts and sets belonging to several sets of the tree appear below each
+/// of them, so rows and ranges are shared rather than consumed.
+fn build_node(
+    row: &StatementTreeRow,
+    children: &HashMap<Option<AccountId>, Vec<StatementTreeRow>>,
+    ranges: &HashMap<AccountId, BalanceRange>,
+    layer: Layer,
+) -> StatementNode {
+    let (opening, period, closing) = match ranges.get(&row.account_id) {
+        Some(range) => (
+            range.open.available(layer),
+            range.period.available(layer),
+            range.close.available(layer),
+        ),
+        None => (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO),
+    };
+    StatementNode {
+        children: children
+            .get(&Some(row.account_id))
+            .into_iter()
+            .flatten()
+            .map(|child| build_node(child, children, ranges, layer))
+            .collect(),
+        account_id: row.account_id,
+        code: row.code.clone(),
+        name: row.name.clone(),
+        normal_balance_type: row.normal_balance_type,
+        is_account_set: row.is_account_set,
+        opening,
+        period,
+        closing,
+    }
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use cala_types::{balance::BalanceAmount, primitives::*};
+
+/// Debit and credit totals of one layer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebitCreditTotals {
+    pub dr: Decimal,
+    pub cr: Decimal,
+}
+
+impl DebitCreditTotals {
+    pub fn is_balanced(&self) -> bool {
+        self.dr == self.cr
+    }
+}
+
+impl std::ops::AddAssign for DebitCreditTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.dr += other.dr;
+        self.cr += other.cr;
+    }
+}
+
+impl From<&BalanceAmount> for DebitCreditTotals {
+    fn from(amount: &BalanceAmount) -> Self {
+        Self {
+            dr: amount.dr_balance,
+            cr: amount.cr_balance,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrialBalanceLine {
+    pub account_id: AccountId,
+    pub code: String,
+    pub name: String,
+    pub normal_balance_type: DebitOrCredit,
+    pub is_account_set: bool,
+    pub currency: Currency,
+    pub settled: DebitCreditTotals,
+    pub pending: DebitCreditTotals,
+    pub encumbrance: DebitCreditTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrialBalanceTotals {
+    pub currency: Currency,
+    pub settled: DebitCreditTotals,
+    pub pending: DebitCreditTotals,
+    pub encumbrance: DebitCreditTotals,
+}
+
+impl TrialBalanceTotals {
+    pub fn is_balanced(&self) -> bool {
+        self.settled.is_balanced() && self.pending.is_balanced() && self.encumbrance.is_balanced()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrialBalance {
+    pub journal_id: JournalId,
+    pub as_of: Option<DateTime<Utc>>,
+    /// Ordered by account code, then currency.
+    pub lines: Vec<TrialBalanceLine>,
+    /// One entry per currency, ordered by currency code.
+    pub totals: Vec<TrialBalanceTotals>,
+    /// Whether debits equal credits in every currency and layer.
+    pub balanced: bool,
+}
+
+impl TrialBalance {
+    pub(super) fn new(
+        journal_id: JournalId,
+        as_of: Option<DateTime<Utc>>,
+        lines: Vec<TrialBalanceLine>,
+    ) -> Self {
+        let mut totals: BTreeMap<&str, TrialBalanceTotals> = BTreeMap::new();
+        for line in lines.iter() {
+            let total = totals
+                .entry(line.currency.code())
+                .or_insert_with(|| TrialBalanceTotals {
+                    currency: line.currency,
+                    settled: DebitCreditTotals::default(),
+                    pending: DebitCreditTotals::default(),
+                    encumbrance: DebitCreditTotals::default(),
+                });
+            total.settled += line.settled;
+            total.pending += line.pending;
+            total.encumbrance += line.encumbrance;
+        }
+        let totals: Vec<_> = totals.into_values().collect();
+        let balanced = totals.iter().all(TrialBalanceTotals::is_balanced);
+        Self {
+            journal_id,
+            as_of,
+            lines,
+            totals,
+            balanced,
+        }
+    }
+}
@@ -4,7 +4,7 @@ use tracing::instrument;
 
 pub use crate::param::definition::*;
 pub use cala_types::{primitives::TxTemplateId, tx_template::*};
-use cel_interpreter::CelExpression;
+use cel_interpreter::{CelExpression, CelType};
 use es_entity::*;
 
 #[derive(EsEvent, Debug, Serialize, Deserialize)]
@@ -93,6 +93,98 @@ impl NewTxTemplate {
     pub fn builder() -> NewTxTemplateBuilder {
         NewTxTemplateBuilder::default()
     }
+
+    pub(super) fn expressions(&self) -> impl Iterator<Item = &str> {
+        let params = self
+            .params
+            .iter()
+            .flatten()
+            .filter_map(|p| p.default.as_deref());
+        let transaction = &self.transaction;
+        let transaction = [&transaction.effective, &transaction.journal_id]
+            .into_iter()
+            .map(String::as_str)
+            .chain(
+                [
+                    &transaction.correlation_id,
+                    &transaction.external_id,
+                    &transaction.description,
+                    &transaction.metadata,
+                ]
+                .into_iter()
+                .filter_map(|e| e.as_deref()),
+            );
+        let entries = self.entries.iter().flat_map(|entry| {
+            [
+                &entry.entry_type,
+                &entry.account_id,
+                &entry.layer,
+                &entry.direction,
+                &entry.units,
+                &entry.currency,
+            ]
+            .into_iter()
+            .map(String::as_str)
+            .chain(
+                [&entry.description, &entry.metadata]
+                    .into_iter()
+                    .filter_map(|e| e.as_deref()),
+            )
+        });
+        params.chain(transaction).chain(entries)
+    }
+
+    /// The param defaults with the types they must evaluate to. They are
+    /// evaluated before `params` is bound.
+    pub(super) fn typed_param_defaults(&self) -> impl Iterator<Item = (&str, CelType)> {
+        self.params
+            .iter()
+            .flatten()
+            .filter_map(NewParamDefinition::typed_default)
+    }
+
+    /// The transaction and entry expressions with the types
+    /// `prepare_transaction` coerces their results to.
+    pub(super) fn typed_expressions(&self) -> impl Iterator<Item = (&str, CelType)> {
+        let transaction = &self.transaction;
+        let transaction = [
+            (&transaction.effective, CelType::Date),
+            (&transaction.journal_id, CelType::Uuid),
+        ]
+        .into_iter()
+        .map(|(e, t)| (e.as_str(), t))
+        .chain(
+            [
+                (&transaction.correlation_id, CelType::String),
+                (&transaction.external_id, CelType::String),
+                (&transaction.description, CelType::String),
+                (&transaction.metadata, CelType::Dyn),
+            ]
+            .into_iter()
+            .filter_map(|(e, t)| Some((e.as_deref()?, t))),
+        );
+        let entries = self.entries.iter().flat_map(|entry| {
+            [
+                (&entry.entry_type, CelType::String),
+                (&entry.account_id, CelType::Uuid),
+                (&entry.layer, CelType::String),
+                (&entry.direction, CelType::String),
+                (&entry.units, CelType::Decimal),
+                (&entry.currency, CelType::String),
+            ]
+            .into_iter()
+            .map(|(e, t)| (e.as_str(), t))
+            .chain(
+                [
+                    (&entry.description, CelType::String),
+                    (&entry.metadata, CelType::Dyn),
+                ]
+                .into_iter()
+                .filter_map(|(e, t)| Some((e.as_deref()?, t))),
+            )
+        });
+        transaction.chain(entries)
+    }
 }
 
 impl IntoEvents<TxTemplateEvent> for NewTxTemplate {
@@ -2,7 +2,7 @@ use rust_decimal::Decimal;
 use thiserror::Error;
 
 use cala_types::primitives::{Currency, Layer};
-use cel_interpreter::CelError;
+use cel_interpreter::{CelError, CelTypeError};
 
 use super::repo::{
     TxTemplateColumn, TxTemplateCreateError, TxTemplateFindError, TxTemplateModifyError,
@@ -31,6 +31,10 @@ pub enum TxTemplateError {
     NotFound,
     #[error("TxTemplateError - SerdeJson: {0}")]
     SerdeJson(#[from] serde_json::Error),
+    #[error("TxTemplateError - ExpressionTooExpensive: '{0}' has estimated cost {1}, max is {2}")]
+    ExpressionTooExpensive(String, u64, u64),
+    #[error("TxTemplateError - ExpressionTypeError: '{0}': {1:?}")]
+    ExpressionTypeError(String, Vec<CelTypeError>),
     #[error("TxTemplateError - UnbalancedTransaction: currency {0}, layer {1:?}, amount {2}")]
     UnbalancedTransaction(Currency, Layer, Decimal),
     #[error("TxTemplateError - NotFound: code '{0}' not found")]
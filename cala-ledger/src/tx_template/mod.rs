@@ -11,8 +11,11 @@ use std::collections::HashMap;
 use tracing::instrument;
 use uuid::Uuid;
 
-use crate::outbox::*;
 pub use crate::param::*;
+use crate::{
+    cel_context::{CelEvaluationLimits, CelFunctionRegistry},
+    outbox::*,
+};
 use crate::{entry::NewEntry, primitives::*, transaction::NewTransaction};
 
 pub use entity::*;
@@ -80,13 +83,26 @@ impl PreparedTransaction {
 pub struct TxTemplates {
     repo: TxTemplateRepo,
     clock: ClockHandle,
+    functions: CelFunctionRegistry,
+    max_expression_cost: Option<u64>,
+    evaluation_limits: Option<CelEvaluationLimits>,
 }
 
 impl TxTemplates {
-    pub(crate) fn new(pool: &PgPool, publisher: &OutboxPublisher, clock: &ClockHandle) -> Self {
+    pub(crate) fn new(
+        pool: &PgPool,
+        publisher: &OutboxPublisher,
+        clock: &ClockHandle,
+        functions: &CelFunctionRegistry,
+        max_expression_cost: Option<u64>,
+        evaluation_limits: Option<CelEvaluationLimits>,
+    ) -> Self {
         Self {
             repo: TxTemplateRepo::new(pool, publisher),
             clock: clock.clone(),
+            functions: functions.clone(),
+            max_expression_cost,
+            evaluation_limits,
         }
     }
 
@@ -107,6 +123,32 @@ impl TxTemplates {
         db: &mut impl es_entity::AtomicOperation,
         new_tx_template: NewTxTemplate,
     ) -> Result<TxTemplate, TxTemplateError> {
+        if let Some((expression, cost)) = crate::cel_context::find_too_expensive(
+            self.max_expression_cost,
+            new_tx_template.expressions(),
+        ) {
+            return Err(TxTemplateError::ExpressionTooExpensive(
+                expression,
+                cost,
+                self.max_expression_cost.unwrap_or_default(),
+            ));
+        }
+        let env = crate::cel_context::type_env(&self.functions);
+        let mut params_env = env.clone();
+        if let Some(params) = new_tx_template.params.as_ref() {
+            params_env.add_variable("params", params_type_decl(params));
+        }
+        if let Some((expression, errors)) =
+            crate::cel_context::find_ill_typed(&env, new_tx_template.typed_param_defaults())
+                .or_else(|| {
+                    crate::cel_context::find_ill_typed(
+                        &params_env,
+                        new_tx_template.typed_expressions(),
+                    )
+                })
+        {
+            return Err(TxTemplateError::ExpressionTypeError(expression, errors));
+        }
         let tx_template = self.repo.create_in_op(db, new_tx_template).await?;
         Ok(tx_template)
     }
@@ -139,7 +181,8 @@ impl TxTemplates {
     /// Pure: no clock read that matters to persistence, no database access —
     /// which is what lets the posting flow run it before its first statement.
     /// The clock only seeds the CEL context (the `date()`/`now()` builtins
-    /// available to template expressions).
+    /// available to template expressions), next to the configured
+    /// application functions.
     #[instrument(
         level = "debug",
         name = "cala_ledger.tx_template.prepare_transaction",
@@ -151,7 +194,12 @@ impl TxTemplates {
         tmpl: &TxTemplateValues,
         params: Params,
     ) -> Result<PreparedTransaction, TxTemplateError> {
-        let ctx = params.into_context(&self.clock, tmpl.params.as_ref())?;
+        let ctx = params.into_context(
+            &self.clock,
+            &self.functions,
+            self.evaluation_limits.as_ref(),
+            tmpl.params.as_ref(),
+        )?;
 
         let journal_id: Uuid = tmpl.transaction.journal_id.try_evaluate(&ctx)?;
         let journal_id = JournalId::from(journal_id);
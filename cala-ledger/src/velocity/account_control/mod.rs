@@ -9,6 +9,7 @@ use tracing::instrument;
 use cala_types::velocity::{VelocityControlValues, VelocityLimitValues};
 
 use crate::{
+    cel_context::{CelEvaluationLimits, CelFunctionRegistry},
     param::Params,
     primitives::{AccountId, DebitOrCredit, Layer},
 };
@@ -23,14 +24,23 @@ pub struct AccountControls {
     _pool: PgPool,
     repo: AccountControlRepo,
     clock: ClockHandle,
+    functions: CelFunctionRegistry,
+    evaluation_limits: Option<CelEvaluationLimits>,
 }
 
 impl AccountControls {
-    pub fn new(pool: &PgPool, clock: &ClockHandle) -> Self {
+    pub fn new(
+        pool: &PgPool,
+        clock: &ClockHandle,
+        functions: &CelFunctionRegistry,
+        evaluation_limits: Option<CelEvaluationLimits>,
+    ) -> Self {
         Self {
             repo: AccountControlRepo::new(pool),
             _pool: pool.clone(),
             clock: clock.clone(),
+            functions: functions.clone(),
+            evaluation_limits,
         }
     }
 
@@ -42,7 +52,7 @@ impl AccountControls {
         limits: Vec<VelocityLimitValues>,
         params: impl Into<Params> + std::fmt::Debug,
     ) -> Result<(), VelocityError> {
-        let velocity_limits = Self::evaluate_velocity_limits(&self.clock, limits, params.into())?;
+        let velocity_limits = self.evaluate_velocity_limits(limits, params.into())?;
 
         let control = AccountVelocityControl {
             account_id,
@@ -90,7 +100,7 @@ impl AccountControls {
             return Ok(());
         }
 
-        let velocity_limits = Self::evaluate_velocity_limits(&self.clock, limits, params.into())?;
+        let velocity_limits = self.evaluate_velocity_limits(limits, params.into())?;
 
         let controls = account_ids
             .iter()
@@ -109,14 +119,19 @@ impl AccountControls {
     }
 
     fn evaluate_velocity_limits(
-        clock: &ClockHandle,
+        &self,
         limits: Vec<VelocityLimitValues>,
         params: Params,
     ) -> Result<Vec<AccountVelocityLimit>, VelocityError> {
         let mut velocity_limits = Vec::new();
         for velocity in limits {
             let defs = velocity.params;
-            let ctx = params.clone().into_context(clock, defs.as_ref())?;
+            let ctx = params.clone().into_context(
+                &self.clock,
+                &self.functions,
+                self.evaluation_limits.as_ref(),
+                defs.as_ref(),
+            )?;
             let mut limits = Vec::new();
             for limit in velocity.limit.balance {
                 let layer: Layer = limit.layer.try_evaluate(&ctx)?;
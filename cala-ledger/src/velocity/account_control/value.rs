@@ -1,4 +1,4 @@
-use cel_interpreter::{CelContext, CelExpression};
+use cel_interpreter::{CelContext, CelExplanation, CelExpression};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -112,6 +112,7 @@ impl AccountVelocityLimit {
                     layer: limit.layer,
                     limit: limit.amount,
                     requested,
+                    explanations: self.explain(ctx),
                 };
                 Span::current().record("velocity.limit", field::display(&err.limit));
                 Span::current().record("velocity.requested", field::display(&err.requested));
@@ -123,6 +124,22 @@ impl AccountVelocityLimit {
 
         Ok(())
     }
+
+    /// Explain the expressions that made this limit apply to the entry, to
+    /// attach to the rejection. Only done once a limit is exceeded, so the
+    /// happy path pays nothing for it.
+    fn explain(&self, ctx: &CelContext) -> Vec<CelExplanation> {
+        let condition = self
+            .condition
+            .iter()
+            .map(|condition| condition.try_evaluate_explained::<bool>(ctx).1);
+        let timestamp_source = self
+            .limit
+            .timestamp_source
+            .iter()
+            .map(|source| source.try_evaluate_explained::<DateTime<Utc>>(ctx).1);
+        condition.chain(timestamp_source).collect()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -429,6 +446,45 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[test]
+    fn enforce_explains_rejection() {
+        let mut ctx = crate::cel_context::initialize(es_entity::clock::Clock::handle().clone());
+        let time = Utc::now();
+        let limit = AccountVelocityLimit {
+            limit_id: VelocityLimitId::new(),
+            window: vec![],
+            currency: None,
+            condition: Some("entry.entryType == 'TEST_ENTRY_TYPE'".parse().unwrap()),
+            limit: AccountLimit {
+                timestamp_source: None,
+                balance: vec![AccountBalanceLimit {
+                    layer: Layer::Settled,
+                    amount: Decimal::ONE,
+                    enforcement_direction: DebitOrCredit::Debit,
+                    start: time,
+                    end: None,
+                }],
+            },
+        };
+        let mut entry = entry();
+        entry.units = Decimal::ONE_HUNDRED;
+        ctx.add_variable("entry", &entry);
+        let new_snapshot = crate::balance::Snapshots::new_snapshot(time, entry.account_id, &entry);
+        let Err(VelocityError::Enforcement(err)) = limit.enforce(&ctx, time, &new_snapshot) else {
+            panic!("limit should be exceeded");
+        };
+        let [condition] = err.explanations.as_slice() else {
+            panic!("expected the condition to be explained");
+        };
+        assert_eq!(condition.expression, "entry.entryType == 'TEST_ENTRY_TYPE'");
+        assert_eq!(condition.value.as_deref(), Some("Bool(true)"));
+        assert_eq!(condition.children[0].expression, "entry.entryType");
+        assert_eq!(
+            condition.children[0].value.as_deref(),
+            Some("String(\"TEST_ENTRY_TYPE\")")
+        );
+    }
+
     #[test]
     fn enforce_restricts_credit() {
         let ctx = crate::cel_context::initialize(es_entity::clock::Clock::handle().clone());
@@ -11,7 +11,10 @@ use cala_types::{
     velocity::VelocityContextAccountValues,
 };
 
-use crate::primitives::{AccountId, AccountSetId, TransactionId};
+use crate::{
+    cel_context::{CelEvaluationLimits, CelFunctionRegistry},
+    primitives::{AccountId, AccountSetId, TransactionId},
+};
 
 use super::{account_control::*, error::*};
 
@@ -21,13 +24,22 @@ use repo::*;
 pub(super) struct VelocityBalances {
     repo: VelocityBalanceRepo,
     clock: ClockHandle,
+    functions: CelFunctionRegistry,
+    evaluation_limits: Option<CelEvaluationLimits>,
 }
 
 impl VelocityBalances {
-    pub fn new(pool: &PgPool, clock: &ClockHandle) -> Self {
+    pub fn new(
+        pool: &PgPool,
+        clock: &ClockHandle,
+        functions: &CelFunctionRegistry,
+        evaluation_limits: Option<CelEvaluationLimits>,
+    ) -> Self {
         Self {
             repo: VelocityBalanceRepo::new(pool),
             clock: clock.clone(),
+            functions: functions.clone(),
+            evaluation_limits,
         }
     }
 
@@ -71,7 +83,9 @@ impl VelocityBalances {
                         self.clock.clone(),
                         transaction,
                         controls.values().map(|v| &v.0),
-                    ),
+                    )
+                    .with_functions(&self.functions)
+                    .with_evaluation_limits(self.evaluation_limits.as_ref()),
                 )
             })
             .collect();
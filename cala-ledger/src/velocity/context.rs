@@ -4,7 +4,7 @@ use tracing::instrument;
 use cala_types::{
     entry::EntryValues, transaction::TransactionValues, velocity::VelocityContextAccountValues,
 };
-use cel_interpreter::{CelMap, CelValue};
+use cel_interpreter::{CelMap, CelType, CelTypeDecl, CelTypeEnv, CelValue};
 use es_entity::clock::ClockHandle;
 
 use crate::{
@@ -14,6 +14,8 @@ use crate::{
 
 pub struct EvalContext {
     clock: ClockHandle,
+    functions: CelFunctionRegistry,
+    limits: Option<CelEvaluationLimits>,
     transaction: CelValue,
     entry_values: HashMap<EntryId, CelValue>,
     account_values: HashMap<AccountId, CelValue>,
@@ -28,12 +30,24 @@ impl EvalContext {
         let account_values = accounts.map(|a| (a.id, a.into())).collect();
         Self {
             clock,
+            functions: CelFunctionRegistry::default(),
+            limits: None,
             transaction: transaction.into(),
             entry_values: HashMap::new(),
             account_values,
         }
     }
 
+    pub fn with_functions(mut self, functions: &CelFunctionRegistry) -> Self {
+        self.functions = functions.clone();
+        self
+    }
+
+    pub fn with_evaluation_limits(mut self, limits: Option<&CelEvaluationLimits>) -> Self {
+        self.limits = limits.cloned();
+        self
+    }
+
     #[instrument(name = "velocity.context_for_entry", skip(self, entry), fields(account_id = %account_id, entry_id = %entry.id), level = "debug")]
     pub fn context_for_entry(&mut self, account_id: AccountId, entry: &EntryValues) -> CelContext {
         let cel_entry = self
@@ -55,13 +69,60 @@ impl EvalContext {
         let mut context = CelMap::new();
         context.insert("vars", vars);
 
-        let mut ctx = initialize(self.clock.clone());
+        let mut ctx =
+            initialize_with_functions(self.clock.clone(), &self.functions, self.limits.as_ref());
         ctx.add_variable("context", context);
 
         ctx
     }
 }
 
+/// The type environment matching [`EvalContext::context_for_entry`], for
+/// checking the expressions evaluated at posting time.
+pub(super) fn type_env(functions: &CelFunctionRegistry) -> CelTypeEnv {
+    use CelType::*;
+    let field = |t: CelType| CelTypeDecl::from(t);
+    let transaction = CelTypeDecl::fields([
+        ("id", field(Uuid)),
+        ("createdAt", field(Timestamp)),
+        ("journalId", field(Uuid)),
+        ("txTemplateId", field(Uuid)),
+        ("effective", field(Date)),
+        ("correlationId", field(String)),
+        ("metadata", field(Dyn)),
+    ]);
+    let entry = CelTypeDecl::fields([
+        ("id", field(Uuid)),
+        ("entryType", field(String)),
+        ("sequence", field(UInt)),
+        ("layer", field(String)),
+        ("direction", field(String)),
+        ("units", field(Decimal)),
+        ("currency", field(String)),
+        ("metadata", field(Dyn)),
+    ]);
+    let account = CelTypeDecl::fields([
+        ("id", field(Uuid)),
+        ("name", field(String)),
+        ("externalId", field(String)),
+        ("normalBalanceType", field(String)),
+        ("metadata", field(Dyn)),
+    ]);
+    let mut env = crate::cel_context::type_env(functions);
+    env.add_variable(
+        "context",
+        CelTypeDecl::fields([(
+            "vars",
+            CelTypeDecl::fields([
+                ("transaction", transaction),
+                ("entry", entry),
+                ("account", account),
+            ]),
+        )]),
+    );
+    env
+}
+
 #[cfg(test)]
 mod tests {
     use es_entity::clock::Clock;
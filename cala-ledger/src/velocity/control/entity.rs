@@ -1,4 +1,4 @@
-use cel_interpreter::CelExpression;
+use cel_interpreter::{CelExpression, CelType};
 use derive_builder::Builder;
 use es_entity::*;
 use serde::{Deserialize, Serialize};
@@ -78,6 +78,12 @@ impl NewVelocityControl {
     pub fn builder() -> NewVelocityControlBuilder {
         NewVelocityControlBuilder::default()
     }
+
+    /// The condition, evaluated against each entry's `context` at posting
+    /// time.
+    pub(crate) fn typed_condition(&self) -> Option<(&str, CelType)> {
+        self.condition.as_deref().map(|c| (c, CelType::Bool))
+    }
 }
 
 impl IntoEvents<VelocityControlEvent> for NewVelocityControl {
@@ -1,7 +1,7 @@
 use rust_decimal::Decimal;
 use thiserror::Error;
 
-use cel_interpreter::CelError;
+use cel_interpreter::{CelError, CelExplanation, CelTypeError};
 
 use crate::primitives::*;
 
@@ -24,6 +24,10 @@ pub enum VelocityError {
     ParamError(#[from] crate::param::error::ParamError),
     #[error("VelocityError - Could not find control by id: {0}")]
     CouldNotFindControlById(VelocityControlId),
+    #[error("VelocityError - ExpressionTooExpensive: '{0}' has estimated cost {1}, max is {2}")]
+    ExpressionTooExpensive(String, u64, u64),
+    #[error("VelocityError - ExpressionTypeError: '{0}': {1:?}")]
+    ExpressionTypeError(String, Vec<CelTypeError>),
     #[error("VelocityError - Enforcement: {0}")]
     Enforcement(#[from] LimitExceededError),
     #[error("VelocityError - HydrationError: {0}")]
@@ -116,4 +120,7 @@ pub struct LimitExceededError {
     pub direction: DebitOrCredit,
     pub limit: Decimal,
     pub requested: Decimal,
+    /// How the limit's `condition` and `timestamp_source` evaluated for the
+    /// rejected entry.
+    pub explanations: Vec<CelExplanation>,
 }
@@ -1,4 +1,4 @@
-use cel_interpreter::CelExpression;
+use cel_interpreter::{CelExpression, CelType};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
@@ -78,6 +78,74 @@ impl NewVelocityLimit {
     pub fn builder() -> NewVelocityLimitBuilder {
         NewVelocityLimitBuilder::default()
     }
+
+    pub(crate) fn expressions(&self) -> impl Iterator<Item = &str> {
+        let window = self.window.iter().map(|key| key.value.as_str());
+        let params = self
+            .params
+            .iter()
+            .flatten()
+            .filter_map(|p| p.default.as_deref());
+        let balance = self.limit.balance.iter().flat_map(|limit| {
+            [
+                &limit.layer,
+                &limit.amount,
+                &limit.enforcement_direction,
+                &limit.start,
+            ]
+            .into_iter()
+            .map(String::as_str)
+            .chain(limit.end.as_deref())
+        });
+        window
+            .chain(self.condition.as_deref())
+            .chain(params)
+            .chain(self.limit.timestamp_source.as_deref())
+            .chain(balance)
+    }
+
+    pub(crate) fn params(&self) -> Option<&[NewParamDefinition]> {
+        self.params.as_deref()
+    }
+
+    /// The param defaults with the types they must evaluate to. They are
+    /// evaluated before `params` is bound.
+    pub(crate) fn typed_param_defaults(&self) -> impl Iterator<Item = (&str, CelType)> {
+        self.params
+            .iter()
+            .flatten()
+            .filter_map(NewParamDefinition::typed_default)
+    }
+
+    /// The balance limit expressions, evaluated against the control's
+    /// `params` when it is attached to an account.
+    pub(crate) fn typed_attach_expressions(&self) -> impl Iterator<Item = (&str, CelType)> {
+        self.limit.balance.iter().flat_map(|limit| {
+            [
+                (limit.layer.as_str(), CelType::String),
+                (limit.amount.as_str(), CelType::Decimal),
+                (limit.enforcement_direction.as_str(), CelType::String),
+                (limit.start.as_str(), CelType::Timestamp),
+            ]
+            .into_iter()
+            .chain(limit.end.as_deref().map(|end| (end, CelType::Timestamp)))
+        })
+    }
+
+    /// The expressions evaluated against each entry's `context` at posting
+    /// time.
+    pub(crate) fn typed_posting_expressions(&self) -> impl Iterator<Item = (&str, CelType)> {
+        self.window
+            .iter()
+            .map(|key| (key.value.as_str(), CelType::Dyn))
+            .chain(self.condition.as_deref().map(|c| (c, CelType::Bool)))
+            .chain(
+                self.limit
+                    .timestamp_source
+                    .as_deref()
+                    .map(|source| (source, CelType::Timestamp)),
+            )
+    }
 }
 
 impl IntoEvents<VelocityLimitEvent> for NewVelocityLimit {
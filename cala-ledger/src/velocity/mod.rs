@@ -13,6 +13,7 @@ use tracing::instrument;
 
 use cala_types::{entry::EntryValues, transaction::TransactionValues};
 
+use crate::cel_context::{CelEvaluationLimits, CelFunctionRegistry};
 pub use crate::param::Params;
 
 pub(crate) use account_control::AccountVelocityControl;
@@ -29,16 +30,31 @@ pub struct Velocities {
     account_controls: AccountControls,
     balances: VelocityBalances,
     clock: ClockHandle,
+    functions: CelFunctionRegistry,
+    max_expression_cost: Option<u64>,
 }
 
 impl Velocities {
-    pub(crate) fn new(pool: &PgPool, clock: &ClockHandle) -> Self {
+    pub(crate) fn new(
+        pool: &PgPool,
+        clock: &ClockHandle,
+        functions: &CelFunctionRegistry,
+        max_expression_cost: Option<u64>,
+        evaluation_limits: Option<CelEvaluationLimits>,
+    ) -> Self {
         Self {
             limits: VelocityLimitRepo::new(pool),
             controls: VelocityControlRepo::new(pool),
-            account_controls: AccountControls::new(pool, clock),
-            balances: VelocityBalances::new(pool, clock),
+            account_controls: AccountControls::new(
+                pool,
+                clock,
+                functions,
+                evaluation_limits.clone(),
+            ),
+            balances: VelocityBalances::new(pool, clock, functions, evaluation_limits),
             clock: clock.clone(),
+            functions: functions.clone(),
+            max_expression_cost,
         }
     }
 
@@ -59,6 +75,39 @@ impl Velocities {
         db: &mut impl es_entity::AtomicOperation,
         new_limit: NewVelocityLimit,
     ) -> Result<VelocityLimit, VelocityError> {
+        if let Some((expression, cost)) = crate::cel_context::find_too_expensive(
+            self.max_expression_cost,
+            new_limit.expressions(),
+        ) {
+            return Err(VelocityError::ExpressionTooExpensive(
+                expression,
+                cost,
+                self.max_expression_cost.unwrap_or_default(),
+            ));
+        }
+        let env = crate::cel_context::type_env(&self.functions);
+        let mut params_env = env.clone();
+        if let Some(params) = new_limit.params() {
+            params_env.add_variable("params", crate::param::definition::params_type_decl(params));
+        }
+        let posting_env = context::type_env(&self.functions);
+        if let Some((expression, errors)) =
+            crate::cel_context::find_ill_typed(&env, new_limit.typed_param_defaults())
+                .or_else(|| {
+                    crate::cel_context::find_ill_typed(
+                        &params_env,
+                        new_limit.typed_attach_expressions(),
+                    )
+                })
+                .or_else(|| {
+                    crate::cel_context::find_ill_typed(
+                        &posting_env,
+                        new_limit.typed_posting_expressions(),
+                    )
+                })
+        {
+            return Err(VelocityError::ExpressionTypeError(expression, errors));
+        }
         let res = self.limits.create_in_op(db, new_limit).await?;
         Ok(res)
     }
@@ -80,6 +129,12 @@ impl Velocities {
         db: &mut impl es_entity::AtomicOperation,
         new_control: NewVelocityControl,
     ) -> Result<VelocityControl, VelocityError> {
+        if let Some((expression, errors)) = crate::cel_context::find_ill_typed(
+            &context::type_env(&self.functions),
+            new_control.typed_condition(),
+        ) {
+            return Err(VelocityError::ExpressionTypeError(expression, errors));
+        }
         let res = self.controls.create_in_op(db, new_control).await?;
         Ok(res)
     }
@@ -124,6 +179,10 @@ impl Velocities {
         Ok(control)
     }
 
+    /// Attaches `control` to the account set itself rather than to each
+    /// member. Its limits apply to the combined activity of every account
+    /// in the set, including members of nested sets: entries on any of them
+    /// are accumulated into a single velocity balance keyed by the set.
     #[instrument(level = "debug", name = "velocity.attach_control_to_account_set", skip(self), fields(control_id = %control, account_set_id = %account_set_id))]
     pub async fn attach_control_to_account_set(
         &self,
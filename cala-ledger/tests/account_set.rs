@@ -1393,6 +1393,100 @@ async fn balances() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn tree_queries() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(receiver).await?;
+
+    let new_set = |name: &str| {
+        NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal.id())
+            .balance_rollup(BalanceRollup::Synchronous)
+            .build()
+            .unwrap()
+    };
+    let parent = cala.account_sets().create(new_set("Parent")).await?;
+    let recipient_set = cala.account_sets().create(new_set("Recipient Set")).await?;
+    let sender_set = cala.account_sets().create(new_set("Sender Set")).await?;
+    cala.account_sets()
+        .add_member(recipient_set.id(), recipient.id())
+        .await?;
+    cala.account_sets()
+        .add_member(sender_set.id(), sender.id())
+        .await?;
+    cala.account_sets()
+        .add_member(parent.id(), sender_set.id())
+        .await?;
+    cala.account_sets()
+        .add_member(parent.id(), recipient_set.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", sender.id());
+    params.insert("recipient", recipient.id());
+    params.insert("amount", rust_decimal::Decimal::from(100));
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+
+    let tree = cala.account_sets().tree(parent.id(), true).await?;
+    assert_eq!(tree.name, "Parent");
+    assert_eq!(tree.depth, 0);
+    assert_eq!(tree.balances.as_ref().map(Vec::len), Some(1));
+    let set_names: Vec<_> = tree.members.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(set_names, ["Recipient Set", "Sender Set"]);
+    let leaf = &tree.members[0].members[0];
+    assert_eq!(leaf.member, AccountSetMemberId::Account(recipient.id()));
+    assert_eq!(leaf.depth, 2);
+    assert_eq!(leaf.path, vec![parent.id(), recipient_set.id()]);
+    let recipient_balance = cala
+        .balances()
+        .find(journal.id(), recipient.id(), Currency::USD)
+        .await?;
+    let leaf_balances = leaf.balances.as_ref().expect("balances were asked for");
+    assert_eq!(leaf_balances.len(), 1);
+    assert_eq!(leaf_balances[0].settled(), recipient_balance.settled());
+
+    let descendants = cala
+        .account_sets()
+        .descendants(parent.id(), Some(1), false)
+        .await?;
+    assert_eq!(descendants.len(), 2);
+    assert!(descendants
+        .iter()
+        .all(|n| n.depth == 1 && n.members.is_empty() && n.balances.is_none()));
+
+    let ancestors = cala.account_sets().ancestors(recipient.id(), false).await?;
+    let found: Vec<_> = ancestors
+        .iter()
+        .map(|a| (a.account_set_id, a.depth, a.path.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (recipient_set.id(), 1, vec![]),
+            (parent.id(), 2, vec![recipient_set.id()]),
+        ]
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn account_set_update() -> anyhow::Result<()> {
     let pool = helpers::init_pool().await?;
@@ -2055,3 +2149,809 @@ async fn double_membership_check_same_op_structure() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn move_member_migrates_balances() -> anyhow::Result<()> {
+    let pool = helpers::init_isolated_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+    jobs.start_poll().await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+
+    let new_set = |name: &str, balance_rollup: BalanceRollup| {
+        NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal.id())
+            .balance_rollup(balance_rollup)
+            .build()
+            .unwrap()
+    };
+    let parent = cala
+        .account_sets()
+        .create(new_set("Parent", BalanceRollup::Synchronous))
+        .await?;
+    let old = cala
+        .account_sets()
+        .create(new_set("Old", BalanceRollup::Synchronous))
+        .await?;
+    let new = cala
+        .account_sets()
+        .create(new_set("New", BalanceRollup::Synchronous))
+        .await?;
+    let ec = cala
+        .account_sets()
+        .create(new_set(
+            "Eventually consistent",
+            BalanceRollup::EventuallyConsistent,
+        ))
+        .await?;
+    cala.account_sets()
+        .add_member_sets(&[(parent.id(), old.id()), (parent.id(), new.id())])
+        .await?;
+    cala.account_sets()
+        .add_member(old.id(), recipient.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let transfer = |amount: i64| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender.id());
+        params.insert("recipient", recipient.id());
+        params.insert("amount", rust_decimal::Decimal::from(amount));
+        params
+    };
+    cala.post_transaction(TransactionId::new(), &tx_code, transfer(100))
+        .await?;
+    let parent_before = cala
+        .balances()
+        .find(journal.id(), parent.id(), Currency::USD)
+        .await?;
+
+    let today = chrono::Utc::now().date_naive();
+    cala.account_sets()
+        .move_member(recipient.id(), old.id(), new.id(), today)
+        .await?;
+
+    let hundred = rust_decimal::Decimal::from(100);
+    let balances = cala.balances();
+    let old_balance = balances.find(journal.id(), old.id(), Currency::USD).await?;
+    assert_eq!(old_balance.settled(), rust_decimal::Decimal::ZERO);
+    assert_eq!(
+        old_balance.details.settled.cr_balance,
+        rust_decimal::Decimal::ZERO
+    );
+    let new_balance = balances.find(journal.id(), new.id(), Currency::USD).await?;
+    assert_eq!(new_balance.settled(), hundred);
+    let parent_after = balances
+        .find(journal.id(), parent.id(), Currency::USD)
+        .await?;
+    assert_eq!(parent_after.details.version, parent_before.details.version);
+    let effective = balances
+        .effective()
+        .find_cumulative(journal.id(), new.id(), Currency::USD, today)
+        .await?;
+    assert_eq!(effective.settled(), hundred);
+    let effective = balances
+        .effective()
+        .find_cumulative(journal.id(), old.id(), Currency::USD, today)
+        .await?;
+    assert_eq!(effective.settled(), rust_decimal::Decimal::ZERO);
+
+    let err = cala
+        .account_sets()
+        .move_member(recipient.id(), old.id(), new.id(), today)
+        .await
+        .err()
+        .expect("the recipient is no longer a member of the old set");
+    assert!(matches!(err, AccountSetError::NotAMember { .. }));
+
+    // Into an EC set: the rollup must not fold the earlier posting in again.
+    cala.account_sets()
+        .move_member(recipient.id(), new.id(), ec.id(), today)
+        .await?;
+    let new_balance = balances.find(journal.id(), new.id(), Currency::USD).await?;
+    assert_eq!(new_balance.settled(), rust_decimal::Decimal::ZERO);
+    cala.post_transaction(TransactionId::new(), &tx_code, transfer(10))
+        .await?;
+    helpers::wait_for_settled(
+        &cala,
+        journal.id(),
+        ec.id(),
+        Currency::USD,
+        rust_decimal::Decimal::from(110),
+    )
+    .await?;
+    helpers::wait_for_effective(
+        &cala,
+        journal.id(),
+        ec.id(),
+        Currency::USD,
+        today,
+        rust_decimal::Decimal::from(110),
+    )
+    .await?;
+
+    // Out of the EC set again: only the entry posted since the move in has
+    // to be rolled up.
+    cala.account_sets()
+        .move_member(recipient.id(), ec.id(), old.id(), today)
+        .await?;
+    let old_balance = balances.find(journal.id(), old.id(), Currency::USD).await?;
+    assert_eq!(old_balance.settled(), rust_decimal::Decimal::from(110));
+
+    // Versions written by a move have no entry of their own.
+    let history = balances
+        .list_history(
+            journal.id(),
+            ec.id(),
+            Currency::USD,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Ascending,
+        )
+        .await?;
+    let entries: Vec<_> = history
+        .entities
+        .iter()
+        .map(|item| item.entry.as_ref().map(|entry| entry.units))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![None, Some(rust_decimal::Decimal::from(10)), None]
+    );
+
+    let report = cala
+        .verify_integrity(integrity::IntegrityScope::Journal {
+            journal_id: journal.id(),
+        })
+        .await?;
+    assert!(report.is_consistent(), "{:?}", report.discrepancies);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn add_member_with_backfill_replays_history() -> anyhow::Result<()> {
+    let pool = helpers::init_isolated_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+    jobs.start_poll().await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+
+    let new_set = |name: &str, balance_rollup: BalanceRollup| {
+        NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal.id())
+            .balance_rollup(balance_rollup)
+            .build()
+            .unwrap()
+    };
+    let synchronous = cala
+        .account_sets()
+        .create(new_set("Synchronous", BalanceRollup::Synchronous))
+        .await?;
+    let parent = cala
+        .account_sets()
+        .create(new_set("Parent", BalanceRollup::EventuallyConsistent))
+        .await?;
+    let child = cala
+        .account_sets()
+        .create(new_set("Child", BalanceRollup::EventuallyConsistent))
+        .await?;
+    cala.account_sets()
+        .add_member(parent.id(), child.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let transfer = |amount: i64| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender.id());
+        params.insert("recipient", recipient.id());
+        params.insert("amount", rust_decimal::Decimal::from(amount));
+        params
+    };
+    for _ in 0..3 {
+        cala.post_transaction(TransactionId::new(), &tx_code, transfer(100))
+            .await?;
+    }
+
+    let err = cala
+        .account_sets()
+        .add_member(child.id(), recipient.id())
+        .await
+        .err()
+        .expect("the recipient has balance history");
+    assert!(matches!(
+        err,
+        AccountSetError::MemberHasBalanceHistory { .. }
+    ));
+    let err = cala
+        .account_sets()
+        .add_member_with_backfill(synchronous.id(), recipient.id())
+        .await
+        .err()
+        .expect("only eventually-consistent sets take a backfill");
+    assert!(matches!(
+        err,
+        AccountSetError::BackfillRequiresEventuallyConsistentSet(id) if id == synchronous.id()
+    ));
+
+    cala.account_sets()
+        .add_member_with_backfill(child.id(), recipient.id())
+        .await?;
+    cala.post_transaction(TransactionId::new(), &tx_code, transfer(10))
+        .await?;
+
+    let today = chrono::Utc::now().date_naive();
+    let expected = rust_decimal::Decimal::from(310);
+    for set_id in [child.id(), parent.id()] {
+        helpers::wait_for_settled(&cala, journal.id(), set_id, Currency::USD, expected).await?;
+        helpers::wait_for_effective(&cala, journal.id(), set_id, Currency::USD, today, expected)
+            .await?;
+    }
+
+    let mut backfilling = true;
+    for _ in 0..300 {
+        backfilling = cala.account_sets().is_backfilling(parent.id()).await?;
+        if !backfilling {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(!backfilling);
+    let status = cala.ec_rollup_status().await?;
+    assert_eq!(status.backfills_pending, 0);
+    assert_eq!(status.backfill_entries_pending, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn membership_limits() -> anyhow::Result<()> {
+    let pool = helpers::init_isolated_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .membership_limits(MembershipLimits {
+            max_depth: 1,
+            max_ancestors_per_account: Some(2),
+            max_members_per_set: Some(2),
+        })
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let new_set = |name: &str| {
+        NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal.id())
+            .balance_rollup(BalanceRollup::Synchronous)
+            .build()
+            .unwrap()
+    };
+    let root = cala.account_sets().create(new_set("limits-root")).await?;
+    let branch = cala.account_sets().create(new_set("limits-branch")).await?;
+    let leaf = cala.account_sets().create(new_set("limits-leaf")).await?;
+    let other = cala.account_sets().create(new_set("limits-other")).await?;
+    let (first, second) = helpers::test_accounts();
+    let first = cala.accounts().create(first).await?;
+    let second = cala.accounts().create(second).await?;
+
+    cala.account_sets()
+        .add_member(root.id(), branch.id())
+        .await?;
+    let res = cala.account_sets().add_member(branch.id(), leaf.id()).await;
+    assert!(matches!(
+        res,
+        Err(AccountSetError::MembershipDepthExceeded {
+            depth: 2,
+            max: 1,
+            ..
+        })
+    ));
+
+    cala.account_sets()
+        .add_member(branch.id(), first.id())
+        .await?;
+    let res = cala.account_sets().add_member(other.id(), first.id()).await;
+    assert!(matches!(
+        res,
+        Err(AccountSetError::TooManyAncestors {
+            account_id,
+            ancestors: 3,
+            max: 2,
+        }) if account_id == first.id()
+    ));
+
+    cala.account_sets()
+        .add_member(root.id(), second.id())
+        .await?;
+    let (third, _) = helpers::test_accounts();
+    let third = cala.accounts().create(third).await?;
+    let res = cala
+        .account_sets()
+        .add_members(&[(root.id(), third.id())])
+        .await;
+    assert!(matches!(
+        res,
+        Err(AccountSetError::TooManyMembers {
+            account_set_id,
+            members: 3,
+            max: 2,
+        }) if account_set_id == root.id()
+    ));
+
+    // Lowering a limit below what is already stored fails on init.
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .membership_limits(MembershipLimits {
+            max_members_per_set: Some(1),
+            ..Default::default()
+        })
+        .build()?;
+    let res = CalaLedger::init(cala_config, &mut jobs).await;
+    assert!(matches!(
+        res,
+        Err(cala_ledger::error::LedgerError::AccountSetError(AccountSetError::TooManyMembers {
+            account_set_id,
+            members: 2,
+            max: 1,
+        })) if account_set_id == root.id()
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn membership_as_of() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let new_set = |name: &str| {
+        NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal.id())
+            .balance_rollup(BalanceRollup::Synchronous)
+            .build()
+            .unwrap()
+    };
+    let parent = cala
+        .account_sets()
+        .create(new_set("History Parent"))
+        .await?;
+    let child = cala.account_sets().create(new_set("History Child")).await?;
+    let (account, _) = helpers::test_accounts();
+    let account = cala.accounts().create(account).await?;
+
+    let before = chrono::Utc::now();
+    cala.account_sets()
+        .add_member(parent.id(), account.id())
+        .await?;
+    cala.account_sets()
+        .add_member(parent.id(), child.id())
+        .await?;
+    let joined = chrono::Utc::now();
+    cala.account_sets()
+        .remove_member(parent.id(), account.id())
+        .await?;
+    cala.account_sets()
+        .add_member(child.id(), account.id())
+        .await?;
+    let moved = chrono::Utc::now();
+
+    assert!(cala
+        .account_sets()
+        .list_members_as_of(parent.id(), before)
+        .await?
+        .is_empty());
+    let members = cala
+        .account_sets()
+        .list_members_as_of(parent.id(), joined)
+        .await?;
+    assert_eq!(
+        members.iter().map(|m| m.member_id).collect::<Vec<_>>(),
+        vec![
+            AccountSetMemberId::Account(account.id()),
+            AccountSetMemberId::AccountSet(child.id())
+        ]
+    );
+    assert!(members[0].left_at.is_some_and(|left_at| left_at <= moved));
+    assert!(members[1].left_at.is_none());
+    let members = cala
+        .account_sets()
+        .list_members_as_of(parent.id(), moved)
+        .await?;
+    assert_eq!(
+        members.iter().map(|m| m.member_id).collect::<Vec<_>>(),
+        vec![AccountSetMemberId::AccountSet(child.id())]
+    );
+
+    let sets = |periods: Vec<AccountSetMembershipPeriod>| {
+        periods
+            .into_iter()
+            .map(|p| p.account_set_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        sets(
+            cala.account_sets()
+                .find_where_member_as_of(account.id(), joined)
+                .await?
+        ),
+        vec![parent.id()]
+    );
+    assert_eq!(
+        sets(
+            cala.account_sets()
+                .find_where_member_as_of(account.id(), moved)
+                .await?
+        ),
+        vec![child.id()]
+    );
+    assert_eq!(
+        sets(
+            cala.account_sets()
+                .find_where_member_as_of(child.id(), moved)
+                .await?
+        ),
+        vec![parent.id()]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn import_tree() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let new_set = |name: &str, journal_id: JournalId| {
+        NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal_id)
+            .balance_rollup(BalanceRollup::Synchronous)
+            .build()
+            .unwrap()
+    };
+    let (cash, bank) = helpers::test_accounts();
+    let (deposits, _) = helpers::test_accounts();
+    let (cash_id, bank_id, deposits_id) = (cash.id, bank.id, deposits.id);
+    let spec = AccountSetTreeSpec::new(new_set("Chart", journal.id()))
+        .member_set(
+            AccountSetTreeSpec::new(new_set("Assets", journal.id()))
+                .account(cash)
+                .account(bank),
+        )
+        .member_set(
+            AccountSetTreeSpec::new(new_set("Liabilities", journal.id())).account(deposits),
+        );
+
+    let imported = cala.account_sets().import_tree(spec).await?;
+    assert_eq!(imported.account_sets.len(), 3);
+    assert_eq!(imported.accounts.len(), 3);
+
+    let root = imported.account_sets[0].id();
+    let tree = cala.account_sets().tree(root, false).await?;
+    let names: Vec<_> = tree.members.iter().map(|n| n.name.clone()).collect();
+    assert_eq!(names, vec!["Assets", "Liabilities"]);
+    // Members are ordered by name: the helpers' recipient sorts first.
+    let assets: Vec<_> = tree.members[0].members.iter().map(|n| n.member).collect();
+    assert_eq!(
+        assets,
+        vec![
+            AccountSetMemberId::Account(bank_id),
+            AccountSetMemberId::Account(cash_id),
+        ]
+    );
+    assert_eq!(
+        tree.members[1].members[0].member,
+        AccountSetMemberId::Account(deposits_id)
+    );
+
+    let other_journal = cala.journals().create(helpers::test_journal()).await?;
+    let root = new_set("Rejected", journal.id());
+    let root_id = root.id;
+    let (account, _) = helpers::test_accounts();
+    let spec = AccountSetTreeSpec::new(root)
+        .account(account)
+        .member_set(AccountSetTreeSpec::new(new_set(
+            "Elsewhere",
+            other_journal.id(),
+        )));
+    let res = cala.account_sets().import_tree(spec).await;
+    assert!(matches!(res, Err(AccountSetError::JournalIdMismatch)));
+    assert!(cala.account_sets().find(root_id).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_balance_rollup() -> anyhow::Result<()> {
+    let pool = helpers::init_isolated_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+    jobs.start_poll().await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+    let set = cala
+        .account_sets()
+        .create(
+            NewAccountSet::builder()
+                .id(AccountSetId::new())
+                .name("Deposits")
+                .journal_id(journal.id())
+                .balance_rollup(BalanceRollup::Synchronous)
+                .build()
+                .unwrap(),
+        )
+        .await?;
+    cala.account_sets()
+        .add_member(set.id(), recipient.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let transfer = |amount: i64| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender.id());
+        params.insert("recipient", recipient.id());
+        params.insert("amount", rust_decimal::Decimal::from(amount));
+        params
+    };
+    let settled = || async {
+        Ok::<_, anyhow::Error>(
+            cala.balances()
+                .find(journal.id(), set.id(), Currency::USD)
+                .await?
+                .settled(),
+        )
+    };
+    for _ in 0..3 {
+        cala.post_transaction(TransactionId::new(), &tx_code, transfer(100))
+            .await?;
+    }
+    assert_eq!(settled().await?, rust_decimal::Decimal::from(300));
+
+    let updated = cala
+        .account_sets()
+        .update_balance_rollup(set.id(), BalanceRollup::EventuallyConsistent)
+        .await?;
+    assert_eq!(
+        updated.values().balance_rollup,
+        BalanceRollup::EventuallyConsistent
+    );
+    let found = cala.account_sets().find(set.id()).await?;
+    assert_eq!(
+        found.values().balance_rollup,
+        BalanceRollup::EventuallyConsistent
+    );
+    assert!(
+        cala.accounts()
+            .find(set.id().into())
+            .await?
+            .values()
+            .config
+            .eventually_consistent
+    );
+
+    // The rollup now maintains the set, starting from the balance the
+    // postings left.
+    cala.post_transaction(TransactionId::new(), &tx_code, transfer(10))
+        .await?;
+    let today = chrono::Utc::now().date_naive();
+    let expected = rust_decimal::Decimal::from(310);
+    helpers::wait_for_settled(&cala, journal.id(), set.id(), Currency::USD, expected).await?;
+    helpers::wait_for_effective(
+        &cala,
+        journal.id(),
+        set.id(),
+        Currency::USD,
+        today,
+        expected,
+    )
+    .await?;
+
+    // Back to synchronous: postings update the set inline again.
+    cala.account_sets()
+        .update_balance_rollup(set.id(), BalanceRollup::Synchronous)
+        .await?;
+    cala.post_transaction(TransactionId::new(), &tx_code, transfer(10))
+        .await?;
+    assert_eq!(settled().await?, rust_decimal::Decimal::from(320));
+
+    let unchanged = cala
+        .account_sets()
+        .update_balance_rollup(set.id(), BalanceRollup::Synchronous)
+        .await?;
+    assert_eq!(
+        unchanged.values().balance_rollup,
+        BalanceRollup::Synchronous
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn balance_rollup_is_read_from_the_account_row() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let set = cala
+        .account_sets()
+        .create(
+            NewAccountSet::builder()
+                .id(AccountSetId::new())
+                .name("Legacy")
+                .journal_id(journal.id())
+                .balance_rollup(BalanceRollup::EventuallyConsistent)
+                .build()
+                .unwrap(),
+        )
+        .await?;
+    // Events written before the policy was recorded on them.
+    sqlx::query(
+        "UPDATE cala_account_set_events SET event = event #- '{values,balance_rollup}' WHERE id = $1",
+    )
+    .bind(set.id())
+    .execute(&pool)
+    .await?;
+
+    let found = cala.account_sets().find(set.id()).await?;
+    assert_eq!(
+        found.values().balance_rollup,
+        BalanceRollup::EventuallyConsistent
+    );
+    let found: std::collections::HashMap<AccountSetId, AccountSet> =
+        cala.account_sets().find_all(&[set.id()]).await?;
+    assert_eq!(
+        found[&set.id()].values().balance_rollup,
+        BalanceRollup::EventuallyConsistent
+    );
+
+    let unchanged = cala
+        .account_sets()
+        .update_balance_rollup(set.id(), BalanceRollup::EventuallyConsistent)
+        .await?;
+    assert_eq!(unchanged.values().version, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graph_cache_stats() -> anyhow::Result<()> {
+    // Isolated so no other test's structure change moves the graph epoch
+    // between the assertions.
+    let pool = helpers::init_isolated_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+    let (new_parent, new_child) = helpers::test_account_sets(journal.id().into());
+    let parent = cala.account_sets().create(new_parent).await?;
+    let child = cala.account_sets().create(new_child).await?;
+    cala.account_sets()
+        .add_member(parent.id(), child.id())
+        .await?;
+    cala.account_sets()
+        .add_member(child.id(), recipient.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", sender.id());
+    params.insert("recipient", recipient.id());
+    params.insert("amount", rust_decimal::Decimal::ONE);
+
+    cala.account_sets().warm_graph_cache().await?;
+    let warm = cala.account_sets().graph_cache_stats();
+    assert!(warm.epoch.is_some());
+    assert!(warm.last_refreshed_at.is_some());
+    assert_eq!((warm.nodes, warm.edges), (2, 1));
+
+    cala.post_transaction(TransactionId::new(), &tx_code, params.clone())
+        .await?;
+    let stats = cala.account_sets().graph_cache_stats();
+    assert_eq!(stats.hits, warm.hits + 1);
+    assert_eq!(stats.fallbacks, warm.fallbacks);
+
+    cala.account_sets().invalidate_graph_cache();
+    let invalidated = cala.account_sets().graph_cache_stats();
+    assert_eq!(invalidated.epoch, None);
+    assert_eq!(invalidated.last_refreshed_at, None);
+    assert_eq!((invalidated.nodes, invalidated.edges), (0, 0));
+
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+    let stats = cala.account_sets().graph_cache_stats();
+    assert_eq!(stats.hits, warm.hits + 1);
+    assert_eq!(stats.fallbacks.cold, warm.fallbacks.cold + 1);
+
+    cala.account_sets().warm_graph_cache().await?;
+    let rewarmed = cala.account_sets().graph_cache_stats();
+    assert_eq!(rewarmed.epoch, warm.epoch);
+    assert_eq!((rewarmed.nodes, rewarmed.edges), (2, 1));
+
+    Ok(())
+}
@@ -457,3 +457,392 @@ async fn list_current_balances_for_eventually_consistent_account_set() -> anyhow
 
     Ok(())
 }
+
+#[tokio::test]
+async fn find_balances_as_of_recorded_time() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+
+    let before_first = chrono::Utc::now();
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    cala.post_transaction(TransactionId::new(), &tx_code, params.clone())
+        .await?;
+    let first = cala
+        .balances()
+        .find(journal.id(), recipient_account.id(), Currency::BTC)
+        .await?;
+
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+    let second = cala
+        .balances()
+        .find(journal.id(), recipient_account.id(), Currency::BTC)
+        .await?;
+    assert_ne!(first.details.version, second.details.version);
+
+    let as_of_first = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            first.details.modified_at,
+        )
+        .await?;
+    assert_eq!(as_of_first.details, first.details);
+
+    let as_of_now = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            chrono::Utc::now(),
+        )
+        .await?;
+    assert_eq!(as_of_now.details, second.details);
+
+    let res = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            before_first,
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::NotFound(..))
+    ));
+
+    let recipient_btc = (journal.id(), recipient_account.id(), Currency::BTC);
+    let sender_btc = (journal.id(), sender_account.id(), Currency::BTC);
+    let all = cala
+        .balances()
+        .find_all_as_of(&[recipient_btc, sender_btc], first.details.modified_at)
+        .await?;
+    assert_eq!(all[&recipient_btc].details, first.details);
+    assert_eq!(all[&sender_btc].details.version, 1);
+    assert!(cala
+        .balances()
+        .find_all_as_of(&[recipient_btc, sender_btc], before_first)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_balance_history() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    for _ in 0..3 {
+        cala.post_transaction(TransactionId::new(), &tx_code, params.clone())
+            .await?;
+    }
+
+    let first_page = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 2,
+                after: None,
+            },
+            es_entity::ListDirection::Ascending,
+        )
+        .await?;
+    assert!(first_page.has_next_page);
+    let versions: Vec<_> = first_page
+        .entities
+        .iter()
+        .map(|item| item.balance.details.version)
+        .collect();
+    assert_eq!(versions, vec![1, 2]);
+    for item in first_page.entities.iter() {
+        let entry = item.entry.as_ref().expect("posted entry");
+        assert_eq!(entry.id, item.balance.details.entry_id);
+        assert_eq!(entry.account_id, recipient_account.id());
+    }
+    assert_eq!(
+        first_page.entities[1].balance.settled() - first_page.entities[0].balance.settled(),
+        first_page.entities[1]
+            .entry
+            .as_ref()
+            .expect("posted entry")
+            .units
+    );
+
+    let second_page = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 2,
+                after: first_page.end_cursor,
+            },
+            es_entity::ListDirection::Ascending,
+        )
+        .await?;
+    assert!(!second_page.has_next_page);
+    assert_eq!(second_page.entities.len(), 1);
+    let latest = cala
+        .balances()
+        .find(journal.id(), recipient_account.id(), Currency::BTC)
+        .await?;
+    assert_eq!(second_page.entities[0].balance.details, latest.details);
+
+    let descending = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            all_balances_query(),
+            es_entity::ListDirection::Descending,
+        )
+        .await?;
+    let versions: Vec<_> = descending
+        .entities
+        .iter()
+        .map(|item| item.balance.details.version)
+        .collect();
+    assert_eq!(versions, vec![3, 2, 1]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn find_consolidated_balances() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let mut fx_rates = balance::InMemoryFxRates::new();
+    fx_rates.set_rate(
+        Currency::BTC,
+        Currency::USD,
+        chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+        rust_decimal::Decimal::TWO,
+    )?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .fx_rates(fx_rates)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+    let (recipients, _) = helpers::test_account_sets(journal.id().into());
+    let recipients = cala.account_sets().create(recipients).await?;
+    cala.account_sets()
+        .add_member(recipients.id(), recipient_account.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+
+    let now = chrono::Utc::now();
+    let consolidated = cala
+        .balances()
+        .find_consolidated(journal.id(), recipient_account.id(), Currency::USD, now)
+        .await?;
+    assert_eq!(consolidated.components.len(), 2);
+    let btc = consolidated
+        .components
+        .iter()
+        .find(|component| component.balance.details.currency == Currency::BTC)
+        .expect("BTC component");
+    assert_eq!(btc.rate, rust_decimal::Decimal::TWO);
+    assert_eq!(
+        consolidated.settled(),
+        rust_decimal::Decimal::from(1290 * 2 + 100)
+    );
+    assert_eq!(consolidated.pending(), rust_decimal::Decimal::from(100));
+
+    let consolidated = cala
+        .balances()
+        .find_consolidated_for_account_set(journal.id(), recipients.id(), Currency::BTC, now)
+        .await?;
+    assert_eq!(
+        consolidated.settled(),
+        rust_decimal::Decimal::from(1290 + 50)
+    );
+
+    let eur: Currency = "EUR".parse()?;
+    let res = cala
+        .balances()
+        .find_consolidated(journal.id(), recipient_account.id(), eur, now)
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::FxRateNotFound(..))
+    ));
+
+    use balance::FxRateProvider;
+    let db_rates = balance::DbFxRates::new(&pool);
+    let effective = chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    db_rates
+        .set_rate(
+            eur,
+            Currency::USD,
+            effective,
+            rust_decimal::Decimal::new(125, 2),
+        )
+        .await?;
+    assert_eq!(
+        db_rates
+            .rate(Currency::USD, eur, effective)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?,
+        Some(rust_decimal::Decimal::new(8, 1))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn subscribe_balances() -> anyhow::Result<()> {
+    use futures::StreamExt;
+
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+    let (recipients, _) = helpers::test_account_sets(journal.id().into());
+    let recipients = cala.account_sets().create(recipients).await?;
+    cala.account_sets()
+        .add_member(recipients.id(), recipient_account.id())
+        .await?;
+
+    let mut set_updates = Box::pin(cala.subscribe_balances(
+        BalanceSubscriptionFilter::AccountSets {
+            journal_id: journal.id(),
+            account_set_ids: vec![recipients.id()],
+        },
+        None,
+    ));
+    let mut account_updates = Box::pin(cala.subscribe_balances(
+        BalanceSubscriptionFilter::Accounts {
+            journal_id: journal.id(),
+            account_ids: vec![recipient_account.id()],
+        },
+        None,
+    ));
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    for amount in [10, 20] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("amount", rust_decimal::Decimal::from(amount));
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await?;
+    }
+
+    let timeout = std::time::Duration::from_secs(10);
+    let mut first = None;
+    for (version, settled) in [(1, 10), (2, 30)] {
+        let update = tokio::time::timeout(timeout, set_updates.next())
+            .await?
+            .expect("set update")?;
+        assert_eq!(update.snapshot.account_id, recipients.id().into());
+        assert_eq!(update.snapshot.version, version);
+        assert_eq!(
+            update.snapshot.settled.cr_balance,
+            rust_decimal::Decimal::from(settled)
+        );
+
+        let update = tokio::time::timeout(timeout, account_updates.next())
+            .await?
+            .expect("account update")?;
+        assert_eq!(update.snapshot.account_id, recipient_account.id());
+        assert_eq!(update.snapshot.version, version);
+        first.get_or_insert(update.sequence);
+    }
+
+    // Resuming after the first transaction replays only the second one.
+    let mut journal_updates =
+        Box::pin(cala.subscribe_balances(BalanceSubscriptionFilter::Journal(journal.id()), first));
+    let mut account_ids = HashSet::new();
+    for _ in 0..3 {
+        let update = tokio::time::timeout(timeout, journal_updates.next())
+            .await?
+            .expect("journal update")?;
+        assert_eq!(update.snapshot.version, 2);
+        account_ids.insert(update.snapshot.account_id);
+    }
+    assert_eq!(
+        account_ids,
+        HashSet::from([
+            sender_account.id(),
+            recipient_account.id(),
+            recipients.id().into()
+        ])
+    );
+    Ok(())
+}
@@ -0,0 +1,177 @@
+mod helpers;
+
+use rand::distr::{Alphanumeric, SampleString};
+use rust_decimal::Decimal;
+
+use cala_ledger::{
+    account::{AccountUpdate, BalanceConstraint, NewAccount},
+    error::LedgerError,
+    posting::{PostingError, RejectionReason},
+    tx_template::*,
+    *,
+};
+
+#[tokio::test]
+async fn enforces_floors_and_ceilings() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let sender = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .name(format!("Constrained sender {code}"))
+        .code(code)
+        .balance_constraints(vec![BalanceConstraint::floor(
+            Layer::Settled,
+            Decimal::ZERO,
+        )])
+        .build()?;
+    let sender_account = cala.accounts().create(sender).await?;
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let recipient = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .name(format!("Constrained recipient {code}"))
+        .code(code)
+        .balance_constraints(vec![BalanceConstraint::ceiling(
+            Layer::Settled,
+            Decimal::from(100),
+        )
+        .in_currency(Currency::USD)])
+        .build()?;
+    let recipient_account = cala.accounts().create(recipient).await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let transfer = |amount: i64| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("amount", Decimal::from(amount));
+        params
+    };
+
+    // The sender is credit-normal, so sending overdraws it.
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, transfer(10))
+        .await;
+    assert!(matches!(
+        &res,
+        Err(LedgerError::PostingError(PostingError::Rejected { reason, .. }))
+            if matches!(
+                reason.as_ref(),
+                RejectionReason::BalanceConstraintViolated { account_id, layer: Layer::Settled, .. }
+                    if *account_id == sender_account.id()
+            )
+    ));
+
+    let mut sender_account = cala.accounts().find(sender_account.id()).await?;
+    let mut update = AccountUpdate::default();
+    update.balance_constraints(Vec::new());
+    assert!(sender_account.update(update).did_execute());
+    cala.accounts().persist(&mut sender_account).await?;
+
+    cala.post_transaction(TransactionId::new(), &tx_code, transfer(100))
+        .await?;
+
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, transfer(1))
+        .await;
+    assert!(matches!(
+        &res,
+        Err(LedgerError::PostingError(PostingError::Rejected { reason, .. }))
+            if matches!(
+                reason.as_ref(),
+                RejectionReason::BalanceConstraintViolated { account_id, available, .. }
+                    if *account_id == recipient_account.id() && *available == Decimal::from(101)
+            )
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn allows_repayment_into_an_account_below_its_floor() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (borrower, lender) = helpers::test_accounts();
+    let borrower = cala.accounts().create(borrower).await?;
+    let lender = cala.accounts().create(lender).await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let transfer = |sender: AccountId, recipient: AccountId, amount: i64| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender);
+        params.insert("recipient", recipient);
+        params.insert("amount", Decimal::from(amount));
+        params
+    };
+
+    // Overdraw the borrower, then put a floor on it.
+    cala.post_transaction(
+        TransactionId::new(),
+        &tx_code,
+        transfer(borrower.id(), lender.id(), 50),
+    )
+    .await?;
+    let mut borrower = cala.accounts().find(borrower.id()).await?;
+    let mut update = AccountUpdate::default();
+    update.balance_constraints(vec![BalanceConstraint::floor(
+        Layer::Settled,
+        Decimal::ZERO,
+    )]);
+    assert!(borrower.update(update).did_execute());
+    cala.accounts().persist(&mut borrower).await?;
+
+    // Moving further below the floor is rejected.
+    let res = cala
+        .post_transaction(
+            TransactionId::new(),
+            &tx_code,
+            transfer(borrower.id(), lender.id(), 1),
+        )
+        .await;
+    assert!(matches!(
+        &res,
+        Err(LedgerError::PostingError(PostingError::Rejected { reason, .. }))
+            if matches!(
+                reason.as_ref(),
+                RejectionReason::BalanceConstraintViolated { account_id, available, .. }
+                    if *account_id == borrower.id() && *available == Decimal::from(-51)
+            )
+    ));
+
+    // A repayment that leaves it still below the floor goes through.
+    cala.post_transaction(
+        TransactionId::new(),
+        &tx_code,
+        transfer(lender.id(), borrower.id(), 20),
+    )
+    .await?;
+    let balance = cala
+        .balances()
+        .find(journal.id(), borrower.id(), Currency::USD)
+        .await?;
+    assert_eq!(balance.available(Layer::Settled), Decimal::from(-30));
+
+    Ok(())
+}
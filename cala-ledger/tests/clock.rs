@@ -233,3 +233,48 @@ async fn clock_propagates_through_atomic_operations() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn membership_periods_use_clock_time() -> anyhow::Result<()> {
+    let joined_at = Utc.with_ymd_and_hms(2025, 5, 1, 8, 0, 0).unwrap();
+    let (clock_handle, clock_ctrl) = ClockHandle::manual_at(joined_at);
+
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala = CalaLedger::init(
+        CalaLedgerConfig::builder()
+            .pool(pool)
+            .exec_migrations(false)
+            .clock(clock_handle)
+            .build()?,
+        &mut jobs,
+    )
+    .await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (set, _) = helpers::test_account_sets(journal.id().into());
+    let set = cala.account_sets().create(set).await?;
+    let (account, _) = helpers::test_accounts();
+    let account = cala.accounts().create(account).await?;
+
+    cala.account_sets()
+        .add_member(set.id(), account.id())
+        .await?;
+    let left_at = Utc.with_ymd_and_hms(2025, 5, 20, 17, 0, 0).unwrap();
+    clock_ctrl
+        .advance((left_at - joined_at).to_std().expect("positive duration"))
+        .await;
+    cala.account_sets()
+        .remove_member(set.id(), account.id())
+        .await?;
+
+    let members = cala
+        .account_sets()
+        .list_members_as_of(set.id(), joined_at)
+        .await?;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].joined_at, joined_at);
+    assert_eq!(members[0].left_at, Some(left_at));
+
+    Ok(())
+}
@@ -2,7 +2,8 @@
 //! (`NewAccount::initial_account_set`): a freshly created account joins
 //! exactly one account set in the same atomic operation, taking ONLY the
 //! class-2 per-member advisory lock — no coarse membership-graph lock,
-//! no class-1 balance-history guard lock, no path-uniqueness walk. The
+//! no class-1 balance-history guard lock, no path-uniqueness walk —
+//! unless an ancestor or member cap is configured. The
 //! invariant argument lives on the `initial_account_set` field docs
 //! (k=1 is enforced at the type level — an `Option`, not a collection).
 //!
@@ -23,7 +24,7 @@ use rust_decimal_macros::dec;
 
 use cala_ledger::{
     account::{error::AccountError, NewAccount},
-    account_set::{error::AccountSetError, NewAccountSet},
+    account_set::{error::AccountSetError, MembershipLimits, NewAccountSet},
     tx_template::Params,
     *,
 };
@@ -651,3 +652,70 @@ async fn fast_path_members_locked_in_canonical_order() -> anyhow::Result<()> {
     assert_eq!(direct_membership_count(&pool, set_b.id(), hi_id).await?, 1);
     Ok(())
 }
+
+#[tokio::test]
+async fn fast_path_is_held_to_membership_caps() -> anyhow::Result<()> {
+    let pool = helpers::init_isolated_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .membership_limits(MembershipLimits {
+            max_ancestors_per_account: Some(2),
+            max_members_per_set: Some(2),
+            ..Default::default()
+        })
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let root = cala
+        .account_sets()
+        .create(new_set(journal.id(), "caps-root"))
+        .await?;
+    let branch = cala
+        .account_sets()
+        .create(new_set(journal.id(), "caps-branch"))
+        .await?;
+    let leaf = cala
+        .account_sets()
+        .create(new_set(journal.id(), "caps-leaf"))
+        .await?;
+    cala.account_sets()
+        .add_member(root.id(), branch.id())
+        .await?;
+    cala.account_sets()
+        .add_member(branch.id(), leaf.id())
+        .await?;
+
+    cala.accounts().create(new_account_in(root.id())).await?;
+    let over = new_account_in(root.id());
+    let over_id = over.id;
+    let res = cala.accounts().create(over).await;
+    assert!(matches!(
+        res,
+        Err(AccountError::TooManyMembers {
+            account_set_id,
+            members: 3,
+            max: 2,
+        }) if account_set_id == root.id()
+    ));
+    assert!(matches!(
+        cala.accounts().find(over_id).await,
+        Err(AccountError::CouldNotFindById(_))
+    ));
+
+    cala.accounts().create(new_account_in(branch.id())).await?;
+    let deep = new_account_in(leaf.id());
+    let deep_id = deep.id;
+    let res = cala.accounts().create(deep).await;
+    assert!(matches!(
+        res,
+        Err(AccountError::TooManyAncestors {
+            account_id,
+            ancestors: 3,
+            max: 2,
+        }) if account_id == deep_id
+    ));
+    Ok(())
+}
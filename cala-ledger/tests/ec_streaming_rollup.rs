@@ -33,7 +33,8 @@ use cala_ledger::{
     posting::{PostingError, RejectionReason},
     primitives::BalanceRollup,
     tx_template::Params,
-    AccountId, CalaLedger, CalaLedgerConfig, Currency, JournalId, TransactionId,
+    AccountId, BalanceSubscriptionFilter, CalaLedger, CalaLedgerConfig, Currency, JournalId,
+    TransactionId,
 };
 
 const N_MEMBERS: usize = 4;
@@ -916,3 +917,43 @@ async fn await_frontier_times_out_for_a_sequence_beyond_the_stream() -> anyhow::
     }
     Ok(())
 }
+
+/// A subscription to an EC set only sees each entry's version once the
+/// rollup has written it — never a missing or stale one.
+#[tokio::test]
+async fn balance_subscription_emits_ec_set_updates_once_rolled_up() -> anyhow::Result<()> {
+    use futures::StreamExt;
+
+    let pool = helpers::init_isolated_pool().await?;
+    let (fixture, mut jobs) = setup(pool, helpers::test_journal()).await?;
+
+    let ec_set = create_ec_set(&fixture.cala, fixture.journal_id, "subscribed EC set").await?;
+    fixture
+        .cala
+        .account_sets()
+        .add_member(ec_set.id(), fixture.members[0].id())
+        .await?;
+
+    let mut updates = Box::pin(fixture.cala.subscribe_balances(
+        BalanceSubscriptionFilter::AccountSets {
+            journal_id: fixture.journal_id,
+            account_set_ids: vec![ec_set.id()],
+        },
+        None,
+    ));
+    post_to(&fixture, fixture.members[0].id(), 2).await?;
+    jobs.start_poll().await?;
+
+    for version in 1..=2 {
+        let update = tokio::time::timeout(std::time::Duration::from_secs(30), updates.next())
+            .await?
+            .expect("EC set update")?;
+        assert_eq!(update.snapshot.account_id, ec_set.id().into());
+        assert_eq!(update.snapshot.version, version);
+        assert_eq!(
+            update.snapshot.settled.cr_balance,
+            POST_AMOUNT * Decimal::from(version)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,180 @@
+mod helpers;
+
+use rand::distr::{Alphanumeric, SampleString};
+use rust_decimal::Decimal;
+
+use cala_ledger::{account_set::*, integrity::*, tx_template::*, *};
+
+#[tokio::test]
+async fn verify_integrity() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+    let (recipients, _) = helpers::test_account_sets(journal.id().into());
+    let recipients = cala.account_sets().create(recipients).await?;
+    cala.account_sets()
+        .add_member(recipients.id(), recipient_account.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    for _ in 0..3 {
+        cala.post_transaction(TransactionId::new(), &tx_code, params.clone())
+            .await?;
+    }
+
+    let scope = IntegrityScope::Journal {
+        journal_id: journal.id(),
+    };
+    let report = cala.verify_integrity(scope.clone()).await?;
+    assert!(report.is_consistent(), "{:?}", report.discrepancies);
+    // Two accounts and the set, each in BTC and USD.
+    assert_eq!(report.balances_checked, 6);
+    // 3 BTC and 6 USD entries per account, rolled up into the set.
+    assert_eq!(report.versions_checked, 3 * (3 + 6));
+
+    sqlx::query(
+        r#"
+        UPDATE cala_current_balances
+        SET latest_values = jsonb_set(latest_values, '{settled,cr_balance}', '"1"')
+        WHERE journal_id = $1 AND account_id = $2 AND currency = 'BTC'
+        "#,
+    )
+    .bind(uuid::Uuid::from(journal.id()))
+    .bind(uuid::Uuid::from(recipient_account.id()))
+    .execute(&pool)
+    .await?;
+
+    let report = cala
+        .verify_integrity(IntegrityScope::Account {
+            journal_id: journal.id(),
+            account_id: recipient_account.id(),
+        })
+        .await?;
+    let [discrepancy] = report.discrepancies.as_slice() else {
+        panic!("unexpected discrepancies: {:?}", report.discrepancies);
+    };
+    assert_eq!(discrepancy.currency, Currency::BTC);
+    match &discrepancy.kind {
+        DiscrepancyKind::CurrentBalance {
+            expected, recorded, ..
+        } => {
+            assert_eq!(expected.settled.cr, Decimal::from(3 * 1290));
+            assert_eq!(recorded.settled.cr, Decimal::ONE);
+        }
+        kind => panic!("unexpected discrepancy: {kind:?}"),
+    }
+
+    jobs.start_poll().await?;
+    let job_id = cala.spawn_integrity_verification(scope).await?;
+    let outcome = jobs
+        .handle(job_id)
+        .await_completion(std::time::Duration::from_secs(30))
+        .await?;
+    let report: IntegrityReport = outcome.result()?.expect("job stores its report");
+    assert_eq!(report.discrepancies.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_integrity_after_backfilled_member() -> anyhow::Result<()> {
+    let pool = helpers::init_isolated_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+    jobs.start_poll().await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+    let recipients = cala
+        .account_sets()
+        .create(
+            NewAccountSet::builder()
+                .id(AccountSetId::new())
+                .name("Recipients")
+                .journal_id(journal.id())
+                .balance_rollup(BalanceRollup::EventuallyConsistent)
+                .build()?,
+        )
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    let transfer = |amount: i64| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender.id());
+        params.insert("recipient", recipient.id());
+        params.insert("amount", Decimal::from(amount));
+        params
+    };
+    for _ in 0..2 {
+        cala.post_transaction(TransactionId::new(), &tx_code, transfer(100))
+            .await?;
+    }
+    cala.account_sets()
+        .add_member_with_backfill(recipients.id(), recipient.id())
+        .await?;
+    cala.post_transaction(TransactionId::new(), &tx_code, transfer(10))
+        .await?;
+
+    let mut backfilling = true;
+    for _ in 0..300 {
+        backfilling = cala.account_sets().is_backfilling(recipients.id()).await?;
+        if !backfilling {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(!backfilling);
+    helpers::wait_for_settled(
+        &cala,
+        journal.id(),
+        recipients.id(),
+        Currency::USD,
+        Decimal::from(210),
+    )
+    .await?;
+
+    // The set's entries are counted from its own history, which the
+    // backfill fills in, rather than from its current members.
+    let report = cala
+        .verify_integrity(IntegrityScope::Account {
+            journal_id: journal.id(),
+            account_id: recipients.id().into(),
+        })
+        .await?;
+    assert!(report.is_consistent(), "{:?}", report.discrepancies);
+    assert_eq!(report.balances_checked, 1);
+
+    Ok(())
+}
@@ -0,0 +1,429 @@
+mod helpers;
+
+use chrono::NaiveDate;
+use rand::distr::{Alphanumeric, SampleString};
+use rust_decimal::Decimal;
+
+use cala_ledger::{
+    account::NewAccount,
+    error::LedgerError,
+    journal::{error::JournalError, ClosingEntries},
+    posting::{PostingError, RejectionReason},
+    tx_template::*,
+    *,
+};
+
+fn dated_transfer_template(code: &str) -> NewTxTemplate {
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("sender")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("recipient")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("amount")
+            .r#type(ParamDataType::Decimal)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("effective")
+            .r#type(ParamDataType::Date)
+            .build()
+            .unwrap(),
+    ];
+    let entries = vec![
+        NewTxTemplateEntry::builder()
+            .entry_type("'TRANSFER_DR'")
+            .account_id("params.sender")
+            .layer("SETTLED")
+            .direction("DEBIT")
+            .units("params.amount")
+            .currency("'USD'")
+            .build()
+            .unwrap(),
+        NewTxTemplateEntry::builder()
+            .entry_type("'TRANSFER_CR'")
+            .account_id("params.recipient")
+            .layer("SETTLED")
+            .direction("CREDIT")
+            .units("params.amount")
+            .currency("'USD'")
+            .build()
+            .unwrap(),
+    ];
+    NewTxTemplate::builder()
+        .id(uuid::Uuid::now_v7())
+        .code(code)
+        .params(params)
+        .transaction(
+            NewTxTemplateTransaction::builder()
+                .effective("params.effective")
+                .journal_id("params.journal_id")
+                .build()
+                .unwrap(),
+        )
+        .entries(entries)
+        .build()
+        .unwrap()
+}
+
+fn closing_template(code: &str) -> NewTxTemplate {
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("account_id")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("retained_earnings_account_id")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("currency")
+            .r#type(ParamDataType::String)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("amount")
+            .r#type(ParamDataType::Decimal)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("effective")
+            .r#type(ParamDataType::Date)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("direction")
+            .r#type(ParamDataType::String)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("retained_earnings_direction")
+            .r#type(ParamDataType::String)
+            .build()
+            .unwrap(),
+    ];
+    let entries = vec![
+        NewTxTemplateEntry::builder()
+            .entry_type("'CLOSING'")
+            .account_id("params.account_id")
+            .layer("SETTLED")
+            .direction("params.direction")
+            .units("params.amount")
+            .currency("params.currency")
+            .build()
+            .unwrap(),
+        NewTxTemplateEntry::builder()
+            .entry_type("'CLOSING_RETAINED_EARNINGS'")
+            .account_id("params.retained_earnings_account_id")
+            .layer("SETTLED")
+            .direction("params.retained_earnings_direction")
+            .units("params.amount")
+            .currency("params.currency")
+            .build()
+            .unwrap(),
+    ];
+    NewTxTemplate::builder()
+        .id(uuid::Uuid::now_v7())
+        .code(code)
+        .params(params)
+        .transaction(
+            NewTxTemplateTransaction::builder()
+                .effective("params.effective")
+                .journal_id("params.journal_id")
+                .build()
+                .unwrap(),
+        )
+        .entries(entries)
+        .build()
+        .unwrap()
+}
+
+fn account(name: &str, normal_balance_type: DebitOrCredit) -> NewAccount {
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .name(format!("{name} {code}"))
+        .code(code)
+        .normal_balance_type(normal_balance_type)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn close_period_posts_closing_entries_and_rejects_backdated_postings() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let cash = cala
+        .accounts()
+        .create(account("Cash", DebitOrCredit::Debit))
+        .await?;
+    let revenue = cala
+        .accounts()
+        .create(account("Revenue", DebitOrCredit::Credit))
+        .await?;
+    let expense = cala
+        .accounts()
+        .create(account("Expense", DebitOrCredit::Debit))
+        .await?;
+    let retained_earnings = cala
+        .accounts()
+        .create(account("Retained earnings", DebitOrCredit::Credit))
+        .await?;
+
+    let (revenue_set, expense_set) = helpers::test_account_sets(journal.id().into());
+    let revenue_set = cala.account_sets().create(revenue_set).await?;
+    let expense_set = cala.account_sets().create(expense_set).await?;
+    cala.account_sets()
+        .add_member(revenue_set.id(), revenue.id())
+        .await?;
+    cala.account_sets()
+        .add_member(expense_set.id(), expense.id())
+        .await?;
+
+    let transfer_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(dated_transfer_template(&transfer_code))
+        .await?;
+    let closing_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(closing_template(&closing_code))
+        .await?;
+    let transfer = |sender: AccountId, recipient: AccountId, amount: i64, effective: NaiveDate| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender);
+        params.insert("recipient", recipient);
+        params.insert("amount", Decimal::from(amount));
+        params.insert("effective", effective);
+        params
+    };
+
+    let mid_january = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    let end_of_january = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+    cala.post_transaction(
+        TransactionId::new(),
+        &transfer_code,
+        transfer(cash.id(), revenue.id(), 100, mid_january),
+    )
+    .await?;
+    cala.post_transaction(
+        TransactionId::new(),
+        &transfer_code,
+        transfer(expense.id(), cash.id(), 30, mid_january),
+    )
+    .await?;
+
+    let closing = ClosingEntries::builder()
+        .tx_template_code(closing_code)
+        .account_set_ids(vec![revenue_set.id(), expense_set.id()])
+        .retained_earnings_account_id(retained_earnings.id())
+        .build()?;
+    let closed = cala
+        .close_period(journal.id(), end_of_january, Some(closing.clone()))
+        .await?;
+    assert_eq!(closed.closed_through(), Some(end_of_january));
+
+    let effective = cala.balances().effective();
+    for account_id in [revenue.id(), expense.id()] {
+        let balance = effective
+            .find_cumulative(journal.id(), account_id, Currency::USD, end_of_january)
+            .await?;
+        assert_eq!(balance.settled(), Decimal::ZERO);
+    }
+    let balance = effective
+        .find_cumulative(
+            journal.id(),
+            retained_earnings.id(),
+            Currency::USD,
+            end_of_january,
+        )
+        .await?;
+    assert_eq!(balance.settled(), Decimal::from(70));
+
+    // Closing again through the same date posts nothing.
+    cala.close_period(journal.id(), end_of_january, Some(closing))
+        .await?;
+    let balance = effective
+        .find_cumulative(
+            journal.id(),
+            retained_earnings.id(),
+            Currency::USD,
+            end_of_january,
+        )
+        .await?;
+    assert_eq!(balance.settled(), Decimal::from(70));
+
+    let res = cala
+        .post_transaction(
+            TransactionId::new(),
+            &transfer_code,
+            transfer(cash.id(), revenue.id(), 10, mid_january),
+        )
+        .await;
+    assert!(matches!(
+        &res,
+        Err(LedgerError::PostingError(PostingError::Rejected { reason, .. }))
+            if matches!(
+                reason.as_ref(),
+                RejectionReason::PeriodClosed { closed_through, .. }
+                    if *closed_through == end_of_january
+            )
+    ));
+    cala.post_transaction(
+        TransactionId::new(),
+        &transfer_code,
+        transfer(
+            cash.id(),
+            revenue.id(),
+            10,
+            end_of_january.succ_opt().unwrap(),
+        ),
+    )
+    .await?;
+
+    let res = cala
+        .journals()
+        .close_period(journal.id(), mid_january)
+        .await;
+    assert!(matches!(res, Err(JournalError::PeriodAlreadyClosed(_))));
+    let res = cala.journals().reopen_period(journal.id(), None, "").await;
+    assert!(matches!(res, Err(JournalError::ReopenReasonMissing)));
+
+    let reopened = cala
+        .journals()
+        .reopen_period(
+            journal.id(),
+            Some(NaiveDate::from_ymd_opt(2024, 1, 10).unwrap()),
+            "late supplier invoice",
+        )
+        .await?;
+    assert!(!reopened.is_closed_on(mid_january));
+    cala.post_transaction(
+        TransactionId::new(),
+        &transfer_code,
+        transfer(expense.id(), cash.id(), 5, mid_january),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn close_period_waits_for_postings_in_flight() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let cash = cala
+        .accounts()
+        .create(account("Cash", DebitOrCredit::Debit))
+        .await?;
+    let revenue = cala
+        .accounts()
+        .create(account("Revenue", DebitOrCredit::Credit))
+        .await?;
+    let retained_earnings = cala
+        .accounts()
+        .create(account("Retained earnings", DebitOrCredit::Credit))
+        .await?;
+    let (revenue_set, _) = helpers::test_account_sets(journal.id().into());
+    let revenue_set = cala.account_sets().create(revenue_set).await?;
+    cala.account_sets()
+        .add_member(revenue_set.id(), revenue.id())
+        .await?;
+
+    let transfer_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(dated_transfer_template(&transfer_code))
+        .await?;
+    let closing_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(closing_template(&closing_code))
+        .await?;
+    let mid_january = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    let end_of_january = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", cash.id());
+    params.insert("recipient", revenue.id());
+    params.insert("amount", Decimal::from(100));
+    params.insert("effective", mid_january);
+
+    // A backdated posting that has passed the closed-period check but not
+    // committed yet holds the close off until it does.
+    let mut op = cala.begin_operation().await?;
+    cala.post_transaction_in_op(&mut op, TransactionId::new(), &transfer_code, params)
+        .await?;
+
+    let closing = ClosingEntries::builder()
+        .tx_template_code(closing_code)
+        .account_set_ids(vec![revenue_set.id()])
+        .retained_earnings_account_id(retained_earnings.id())
+        .build()?;
+    let close = tokio::spawn({
+        let cala = cala.clone();
+        let journal_id = journal.id();
+        async move {
+            cala.close_period(journal_id, end_of_january, Some(closing))
+                .await
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert!(!close.is_finished());
+    op.commit().await?;
+    close.await??;
+
+    // The closing entries include the posting the close waited for.
+    let effective = cala.balances().effective();
+    let balance = effective
+        .find_cumulative(journal.id(), revenue.id(), Currency::USD, end_of_january)
+        .await?;
+    assert_eq!(balance.settled(), Decimal::ZERO);
+    let balance = effective
+        .find_cumulative(
+            journal.id(),
+            retained_earnings.id(),
+            Currency::USD,
+            end_of_january,
+        )
+        .await?;
+    assert_eq!(balance.settled(), Decimal::from(100));
+
+    Ok(())
+}
@@ -0,0 +1,612 @@
+mod helpers;
+
+use chrono::NaiveDate;
+use rand::distr::{Alphanumeric, SampleString};
+use rust_decimal::Decimal;
+
+use cala_ledger::{account::*, account_set::*, reports::*, tx_template::*, *};
+
+fn new_set(journal_id: JournalId, name: &str, normal_balance_type: DebitOrCredit) -> NewAccountSet {
+    NewAccountSet::builder()
+        .id(AccountSetId::new())
+        .name(name)
+        .journal_id(journal_id)
+        .normal_balance_type(normal_balance_type)
+        .balance_rollup(BalanceRollup::Synchronous)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn trial_balance() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+    let (recipients, others) = helpers::test_account_sets(journal.id().into());
+    let recipients = cala.account_sets().create(recipients).await?;
+    let others = cala.account_sets().create(others).await?;
+    cala.account_sets()
+        .add_member(recipients.id(), recipient_account.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    cala.post_transaction(TransactionId::new(), &tx_code, params.clone())
+        .await?;
+    let after_first = chrono::Utc::now();
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+
+    let report = cala
+        .reports()
+        .trial_balance(journal.id(), TrialBalanceArgs::default())
+        .await?;
+    assert!(report.balanced);
+    assert_eq!(report.lines.len(), 4);
+    assert!(report.lines.iter().all(|line| !line.is_account_set));
+    let btc = report
+        .totals
+        .iter()
+        .find(|totals| totals.currency == Currency::BTC)
+        .expect("BTC totals");
+    assert_eq!(
+        btc.settled,
+        DebitCreditTotals {
+            dr: Decimal::from(2580),
+            cr: Decimal::from(2580),
+        }
+    );
+    let usd = report
+        .totals
+        .iter()
+        .find(|totals| totals.currency == Currency::USD)
+        .expect("USD totals");
+    assert_eq!(usd.pending.dr, Decimal::from(200));
+
+    let report = cala
+        .reports()
+        .trial_balance(
+            journal.id(),
+            TrialBalanceArgs {
+                currency: Some(Currency::USD),
+                as_of: Some(after_first),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert!(report.balanced);
+    assert_eq!(report.lines.len(), 2);
+    assert_eq!(report.totals.len(), 1);
+    assert_eq!(report.totals[0].settled.cr, Decimal::from(100));
+
+    let report = cala
+        .reports()
+        .trial_balance(
+            journal.id(),
+            TrialBalanceArgs {
+                account_set_id: Some(recipients.id()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert!(!report.balanced);
+    assert_eq!(report.lines.len(), 2);
+    assert!(report
+        .lines
+        .iter()
+        .all(|line| line.account_id == recipient_account.id()));
+
+    cala.account_sets()
+        .move_member(
+            recipient_account.id(),
+            recipients.id(),
+            others.id(),
+            chrono::Utc::now().date_naive(),
+        )
+        .await?;
+    let report = cala
+        .reports()
+        .trial_balance(
+            journal.id(),
+            TrialBalanceArgs {
+                account_set_id: Some(recipients.id()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert!(report.lines.is_empty());
+    let report = cala
+        .reports()
+        .trial_balance(
+            journal.id(),
+            TrialBalanceArgs {
+                as_of: Some(after_first),
+                account_set_id: Some(recipients.id()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(report.lines.len(), 2);
+    assert!(report
+        .lines
+        .iter()
+        .all(|line| line.account_id == recipient_account.id()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn statement_over_account_set_hierarchy() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+
+    let assets = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Assets", DebitOrCredit::Debit))
+        .await?;
+    let current_assets = cala
+        .account_sets()
+        .create(new_set(
+            journal.id(),
+            "Current Assets",
+            DebitOrCredit::Debit,
+        ))
+        .await?;
+    let liabilities = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Liabilities", DebitOrCredit::Credit))
+        .await?;
+    cala.account_sets()
+        .add_member(assets.id(), current_assets.id())
+        .await?;
+    cala.account_sets()
+        .add_member(current_assets.id(), sender_account.id())
+        .await?;
+    cala.account_sets()
+        .add_member(liabilities.id(), recipient_account.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+
+    let opening_date = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+    let period_date = NaiveDate::from_ymd_opt(2025, 2, 10).unwrap();
+    for effective in [opening_date, opening_date, period_date] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("effective", effective);
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await?;
+    }
+
+    let statement = cala
+        .reports()
+        .statement(
+            journal.id(),
+            &[assets.id(), liabilities.id()],
+            StatementArgs {
+                currency: Currency::USD,
+                layer: Layer::Settled,
+                from: NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+                until: Some(NaiveDate::from_ymd_opt(2025, 2, 28).unwrap()),
+            },
+        )
+        .await?;
+
+    let [assets_node, liabilities_node] = statement.roots.as_slice() else {
+        panic!("expected two roots, got {:?}", statement.roots);
+    };
+    assert_eq!(assets_node.account_id, AccountId::from(assets.id()));
+    assert_eq!(
+        (assets_node.opening, assets_node.period, assets_node.closing),
+        (Decimal::from(200), Decimal::from(100), Decimal::from(300))
+    );
+    let current_node = &assets_node.children[0];
+    assert!(current_node.is_account_set);
+    assert_eq!(current_node.closing, Decimal::from(300));
+    let sender_node = &current_node.children[0];
+    assert_eq!(sender_node.account_id, sender_account.id());
+    assert!(!sender_node.is_account_set);
+    // Accounts are credit-normal by default, so the debits show as negative.
+    assert_eq!(sender_node.normal_balance_type, DebitOrCredit::Credit);
+    assert_eq!(sender_node.period, Decimal::from(-100));
+
+    assert_eq!(liabilities_node.normal_balance_type, DebitOrCredit::Credit);
+    assert_eq!(
+        (
+            liabilities_node.opening,
+            liabilities_node.period,
+            liabilities_node.closing
+        ),
+        (Decimal::from(200), Decimal::from(100), Decimal::from(300))
+    );
+    assert_eq!(
+        liabilities_node.children[0].account_id,
+        recipient_account.id()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn statement_shows_shared_members_under_each_root() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+
+    // An account may only be reachable once below a set, so members are
+    // shared between separate roots reported together.
+    let left = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Left", DebitOrCredit::Debit))
+        .await?;
+    let right = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Right", DebitOrCredit::Debit))
+        .await?;
+    let shared = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Shared", DebitOrCredit::Debit))
+        .await?;
+    cala.account_sets()
+        .add_member(shared.id(), recipient_account.id())
+        .await?;
+    for set in [left.id(), right.id()] {
+        cala.account_sets()
+            .add_member(set, sender_account.id())
+            .await?;
+        cala.account_sets().add_member(set, shared.id()).await?;
+    }
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    params.insert("effective", NaiveDate::from_ymd_opt(2025, 2, 10).unwrap());
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+
+    let statement = cala
+        .reports()
+        .statement(
+            journal.id(),
+            &[left.id(), right.id()],
+            StatementArgs {
+                currency: Currency::USD,
+                layer: Layer::Settled,
+                from: NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+                until: Some(NaiveDate::from_ymd_opt(2025, 2, 28).unwrap()),
+            },
+        )
+        .await?;
+
+    assert_eq!(statement.roots.len(), 2);
+    for root_node in statement.roots.iter() {
+        assert_eq!(root_node.children.len(), 2, "{root_node:?}");
+        let sender_node = root_node
+            .children
+            .iter()
+            .find(|child| child.account_id == sender_account.id())
+            .expect("sender below each root");
+        assert_eq!(sender_node.period, Decimal::from(-100));
+        let shared_node = root_node
+            .children
+            .iter()
+            .find(|child| child.account_id == AccountId::from(shared.id()))
+            .expect("shared set below each root");
+        let [recipient_node] = shared_node.children.as_slice() else {
+            panic!("expected one member, got {:?}", shared_node.children);
+        };
+        assert_eq!(recipient_node.account_id, recipient_account.id());
+        assert_eq!(recipient_node.period, Decimal::from(100));
+        assert_eq!(shared_node.period, -recipient_node.period);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn aging_matches_credits_against_oldest_debits() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let receivable = cala.accounts().create(sender).await?;
+    let counterparty = cala.accounts().create(receiver).await?;
+    let receivables = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Receivables", DebitOrCredit::Debit))
+        .await?;
+    cala.account_sets()
+        .add_member(receivables.id(), receivable.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let post = |sender: AccountId, recipient: AccountId, effective: NaiveDate| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender);
+        params.insert("recipient", recipient);
+        params.insert("effective", effective);
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+    };
+
+    // Three debits of 100 USD to the receivable, then one repayment of 100.
+    let first = post(
+        receivable.id(),
+        counterparty.id(),
+        NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+    )
+    .await?;
+    let second = post(
+        receivable.id(),
+        counterparty.id(),
+        NaiveDate::from_ymd_opt(2025, 2, 15).unwrap(),
+    )
+    .await?;
+    let third = post(
+        receivable.id(),
+        counterparty.id(),
+        NaiveDate::from_ymd_opt(2025, 3, 20).unwrap(),
+    )
+    .await?;
+    post(
+        counterparty.id(),
+        receivable.id(),
+        NaiveDate::from_ymd_opt(2025, 3, 25).unwrap(),
+    )
+    .await?;
+
+    let mut debit_entries = Vec::new();
+    for tx in [&first, &second, &third] {
+        let entries = cala.entries().list_for_transaction_id(tx.id()).await?;
+        let entry = entries
+            .into_iter()
+            .find(|e| {
+                let v = e.values();
+                v.account_id == receivable.id()
+                    && v.layer == Layer::Settled
+                    && v.currency == Currency::USD
+            })
+            .expect("settled USD debit");
+        debit_entries.push(entry.id());
+    }
+    let [first_entry, second_entry, third_entry] = debit_entries[..] else {
+        unreachable!()
+    };
+
+    let as_of = NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+    for account_id in [receivable.id(), receivables.id().into()] {
+        let aging = cala
+            .reports()
+            .aging(
+                journal.id(),
+                account_id,
+                AgingArgs::new(Currency::USD, as_of),
+            )
+            .await?;
+        assert_eq!(aging.outstanding, Decimal::from(200));
+        assert_eq!(aging.unapplied_credit, Decimal::ZERO);
+        let amounts: Vec<_> = aging.buckets.iter().map(|b| b.amount).collect();
+        assert_eq!(
+            amounts,
+            vec![
+                Decimal::from(100),
+                Decimal::from(100),
+                Decimal::ZERO,
+                Decimal::ZERO
+            ]
+        );
+        assert_eq!(aging.buckets[0].entry_ids, vec![third_entry]);
+        assert_eq!(aging.buckets[1].entry_ids, vec![second_entry]);
+        assert!(aging
+            .buckets
+            .iter()
+            .all(|b| !b.entry_ids.contains(&first_entry)));
+    }
+
+    let aging = cala
+        .reports()
+        .aging(
+            journal.id(),
+            counterparty.id(),
+            AgingArgs {
+                bucket_bounds: vec![60],
+                ..AgingArgs::new(Currency::USD, as_of)
+            },
+        )
+        .await?;
+    assert_eq!(aging.outstanding, Decimal::ZERO);
+    assert_eq!(aging.unapplied_credit, Decimal::from(200));
+    assert_eq!(aging.buckets.len(), 2);
+    assert_eq!(aging.buckets[0].amount, Decimal::ZERO);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn filtered_balance_aggregates_matching_members() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let new_account = |region: &str| {
+        let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+        NewAccount::builder()
+            .id(AccountId::new())
+            .name(format!("Deposits {region}"))
+            .code(code)
+            .normal_balance_type(DebitOrCredit::Credit)
+            .metadata(serde_json::json!({ "region": region }))
+            .unwrap()
+            .build()
+            .unwrap()
+    };
+    let sv = cala.accounts().create(new_account("SV")).await?;
+    let other_sv = cala.accounts().create(new_account("SV")).await?;
+    let ny = cala.accounts().create(new_account("NY")).await?;
+    let (cash, _) = helpers::test_accounts();
+    let cash = cala.accounts().create(cash).await?;
+
+    let liabilities = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Liabilities", DebitOrCredit::Credit))
+        .await?;
+    let deposits = cala
+        .account_sets()
+        .create(new_set(journal.id(), "Deposits", DebitOrCredit::Credit))
+        .await?;
+    cala.account_sets()
+        .add_member(liabilities.id(), deposits.id())
+        .await?;
+    cala.account_sets()
+        .add_member(liabilities.id(), sv.id())
+        .await?;
+    cala.account_sets()
+        .add_member(deposits.id(), other_sv.id())
+        .await?;
+    cala.account_sets()
+        .add_member(deposits.id(), ny.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::simple_template_with_date_default(&tx_code))
+        .await?;
+    for (recipient, amount) in [(sv.id(), 100), (other_sv.id(), 20), (ny.id(), 50)] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", cash.id());
+        params.insert("recipient", recipient);
+        params.insert("amount", Decimal::from(amount));
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await?;
+    }
+
+    let as_of = chrono::Utc::now().date_naive();
+    let balance = cala
+        .reports()
+        .filtered_balance(
+            journal.id(),
+            liabilities.id(),
+            FilteredBalanceArgs {
+                predicate: "account.metadata.region == 'SV'".parse()?,
+                currency: Currency::USD,
+                as_of,
+            },
+        )
+        .await?;
+    let mut expected_ids = vec![sv.id(), other_sv.id()];
+    expected_ids.sort();
+    assert_eq!(balance.account_ids, expected_ids);
+    assert_eq!(balance.settled.cr, Decimal::from(120));
+    assert_eq!(balance.settled.dr, Decimal::ZERO);
+
+    let balance = cala
+        .reports()
+        .filtered_balance(
+            journal.id(),
+            liabilities.id(),
+            FilteredBalanceArgs {
+                predicate: "account.metadata.region == 'TX'".parse()?,
+                currency: Currency::USD,
+                as_of,
+            },
+        )
+        .await?;
+    assert!(balance.account_ids.is_empty());
+    assert_eq!(balance.settled.cr, Decimal::ZERO);
+
+    let other_journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let res = cala
+        .reports()
+        .filtered_balance(
+            other_journal.id(),
+            liabilities.id(),
+            FilteredBalanceArgs {
+                predicate: "account.metadata.region == 'SV'".parse()?,
+                currency: Currency::USD,
+                as_of,
+            },
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(reports::error::ReportError::JournalIdMismatch(set_id, journal_id))
+            if set_id == liabilities.id() && journal_id == other_journal.id()
+    ));
+
+    Ok(())
+}
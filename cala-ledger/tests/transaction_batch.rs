@@ -811,7 +811,7 @@ async fn a_multi_journal_batch_does_not_cross_ancestor_sets_between_journals() -
     let sender = cala.accounts().create(a).await?;
     let recipient = cala.accounts().create(b).await?;
 
-    let mut set_in = |journal_id, name: &str| {
+    let set_in = |journal_id, name: &str| {
         let s = NewAccountSet::builder()
             .id(AccountSetId::new())
             .name(name.to_string())
@@ -82,3 +82,210 @@ async fn transaction_post() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn transaction_post_with_registered_function() -> anyhow::Result<()> {
+    use cala_ledger::cel_interpreter::{CelError, CelFunctionRegistry, CelValue};
+
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let mut functions = CelFunctionRegistry::new();
+    functions.register("fee.Percent", |args| match args {
+        [CelValue::Decimal(amount)] => Ok(CelValue::Decimal(amount * Decimal::new(2, 2))),
+        _ => Err(CelError::MissingArgument),
+    });
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .cel_functions(functions)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("recipient")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("sender")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("amount")
+            .r#type(ParamDataType::Decimal)
+            .build()?,
+    ];
+    let entries = vec![
+        NewTxTemplateEntry::builder()
+            .entry_type("'FEE_DR'")
+            .account_id("params.sender")
+            .layer("SETTLED")
+            .direction("DEBIT")
+            .units("fee.Percent(params.amount)")
+            .currency("'USD'")
+            .build()?,
+        NewTxTemplateEntry::builder()
+            .entry_type("'FEE_CR'")
+            .account_id("params.recipient")
+            .layer("SETTLED")
+            .direction("CREDIT")
+            .units("fee.Percent(params.amount)")
+            .currency("'USD'")
+            .build()?,
+    ];
+    let new_template = NewTxTemplate::builder()
+        .id(uuid::Uuid::now_v7())
+        .code(&tx_code)
+        .params(params)
+        .transaction(
+            NewTxTemplateTransaction::builder()
+                .effective("date()")
+                .journal_id("params.journal_id")
+                .build()?,
+        )
+        .entries(entries)
+        .build()?;
+    cala.tx_templates().create(new_template).await?;
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    params.insert("amount", Decimal::from(500));
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+
+    let recipient_balance = cala
+        .balances()
+        .find(journal.id(), recipient_account.id(), "USD".parse()?)
+        .await?;
+    assert_eq!(recipient_balance.settled(), Decimal::from(10));
+    Ok(())
+}
+
+#[tokio::test]
+async fn transaction_post_exceeding_evaluation_limits() -> anyhow::Result<()> {
+    use cala_ledger::{
+        cel_interpreter::{CelError, CelEvaluationLimits},
+        error::LedgerError,
+        posting::{PostingError, RejectionReason},
+        tx_template::error::TxTemplateError,
+    };
+
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .cel_evaluation_limits(CelEvaluationLimits::new().max_steps(3))
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+
+    let template = |code: &str, units: &str| -> anyhow::Result<NewTxTemplate> {
+        let params = vec![
+            NewParamDefinition::builder()
+                .name("recipient")
+                .r#type(ParamDataType::Uuid)
+                .build()?,
+            NewParamDefinition::builder()
+                .name("sender")
+                .r#type(ParamDataType::Uuid)
+                .build()?,
+            NewParamDefinition::builder()
+                .name("journal_id")
+                .r#type(ParamDataType::Uuid)
+                .build()?,
+            NewParamDefinition::builder()
+                .name("amount")
+                .r#type(ParamDataType::Decimal)
+                .build()?,
+        ];
+        let entries = vec![
+            NewTxTemplateEntry::builder()
+                .entry_type("'TEST_DR'")
+                .account_id("params.sender")
+                .layer("SETTLED")
+                .direction("DEBIT")
+                .units(units)
+                .currency("'USD'")
+                .build()?,
+            NewTxTemplateEntry::builder()
+                .entry_type("'TEST_CR'")
+                .account_id("params.recipient")
+                .layer("SETTLED")
+                .direction("CREDIT")
+                .units(units)
+                .currency("'USD'")
+                .build()?,
+        ];
+        Ok(NewTxTemplate::builder()
+            .id(uuid::Uuid::now_v7())
+            .code(code)
+            .params(params)
+            .transaction(
+                NewTxTemplateTransaction::builder()
+                    .effective("date()")
+                    .journal_id("params.journal_id")
+                    .build()?,
+            )
+            .entries(entries)
+            .build()?)
+    };
+    let params = || {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("amount", Decimal::from(500));
+        params
+    };
+
+    let cheap_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(template(&cheap_code, "params.amount")?)
+        .await?;
+    cala.post_transaction(TransactionId::new(), &cheap_code, params())
+        .await?;
+
+    let expensive_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(template(
+            &expensive_code,
+            "decimal.Mul(params.amount, decimal.Add(decimal('1'), decimal('1')))",
+        )?)
+        .await?;
+    let res = cala
+        .post_transaction(TransactionId::new(), &expensive_code, params())
+        .await;
+    assert!(matches!(
+        &res,
+        Err(LedgerError::PostingError(PostingError::Rejected { reason, .. }))
+            if matches!(
+                reason.as_ref(),
+                RejectionReason::TxTemplate(TxTemplateError::CelError(CelError::EvaluationError(_, e)))
+                    if matches!(**e, CelError::StepLimitExceeded(3))
+            )
+    ));
+
+    let recipient_balance = cala
+        .balances()
+        .find(journal.id(), recipient_account.id(), "USD".parse()?)
+        .await?;
+    assert_eq!(recipient_balance.settled(), Decimal::from(500));
+    Ok(())
+}
@@ -1,5 +1,7 @@
 mod helpers;
 
+use rand::distr::{Alphanumeric, SampleString};
+
 use cala_ledger::{tx_template::error::TxTemplateError, *};
 
 #[tokio::test]
@@ -21,3 +23,159 @@ async fn duplicate_code() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn rejects_expensive_expressions() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .max_expression_cost(1_000u64)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&format!("{code}_cheap"));
+    assert!(cala.tx_templates().create(new_template).await.is_ok());
+
+    let expensive = "params.xs.map(x, params.ys.map(y, x * y)).size() > 0";
+    let new_template = tx_template::NewTxTemplate::builder()
+        .id(TxTemplateId::new())
+        .code(format!("{code}_expensive"))
+        .transaction(
+            tx_template::NewTxTemplateTransaction::builder()
+                .journal_id("params.journal_id")
+                .effective("date()")
+                .description(expensive)
+                .build()?,
+        )
+        .entries(vec![])
+        .build()?;
+    let res = cala.tx_templates().create(new_template).await;
+    assert!(
+        matches!(res, Err(TxTemplateError::ExpressionTooExpensive(ref expr, _, 1_000)) if expr == expensive)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejects_ill_typed_expressions() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config, &mut jobs).await?;
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let template = |code: String, units: &str| {
+        tx_template::NewTxTemplate::builder()
+            .id(TxTemplateId::new())
+            .code(code)
+            .params(vec![
+                tx_template::NewParamDefinition::builder()
+                    .name("journal_id")
+                    .r#type(tx_template::ParamDataType::Uuid)
+                    .build()
+                    .unwrap(),
+                tx_template::NewParamDefinition::builder()
+                    .name("account_id")
+                    .r#type(tx_template::ParamDataType::Uuid)
+                    .build()
+                    .unwrap(),
+                tx_template::NewParamDefinition::builder()
+                    .name("amount")
+                    .r#type(tx_template::ParamDataType::Decimal)
+                    .build()
+                    .unwrap(),
+            ])
+            .transaction(
+                tx_template::NewTxTemplateTransaction::builder()
+                    .journal_id("params.journal_id")
+                    .effective("date()")
+                    .build()
+                    .unwrap(),
+            )
+            .entries(vec![
+                tx_template::NewTxTemplateEntry::builder()
+                    .entry_type("'DEPOSIT_DR'")
+                    .account_id("params.account_id")
+                    .layer("SETTLED")
+                    .direction("DEBIT")
+                    .units(units)
+                    .currency("'USD'")
+                    .build()
+                    .unwrap(),
+                tx_template::NewTxTemplateEntry::builder()
+                    .entry_type("'DEPOSIT_CR'")
+                    .account_id("params.account_id")
+                    .layer("SETTLED")
+                    .direction("CREDIT")
+                    .units(units)
+                    .currency("'USD'")
+                    .build()
+                    .unwrap(),
+            ])
+            .build()
+            .unwrap()
+    };
+
+    let res = cala
+        .tx_templates()
+        .create(template(format!("{code}_typed"), "params.amount"))
+        .await;
+    assert!(res.is_ok());
+
+    let res = cala
+        .tx_templates()
+        .create(template(
+            format!("{code}_string_units"),
+            "params.account_id",
+        ))
+        .await;
+    assert!(
+        matches!(res, Err(TxTemplateError::ExpressionTypeError(ref expr, _)) if expr == "params.account_id")
+    );
+
+    let res = cala
+        .tx_templates()
+        .create(template(format!("{code}_undeclared"), "params.amount_usd"))
+        .await;
+    assert!(
+        matches!(res, Err(TxTemplateError::ExpressionTypeError(ref expr, _)) if expr == "params.amount_usd")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn preloads_precompiled_expressions() -> anyhow::Result<()> {
+    use cala_ledger::cel_interpreter::{CelCompileCache, CelExpression};
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let source = format!("'{code}'");
+    let compiled = CelExpression::try_from(source.as_str())?.compiled();
+    let json = serde_json::to_string(&compiled)?;
+
+    let pool = helpers::init_pool().await?;
+    let mut jobs = helpers::init_jobs(pool.clone()).await?;
+    let preloaded = CelCompileCache::stats().preloaded;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .precompiled_expressions(vec![serde_json::from_str(&json)?])
+        .build()?;
+    CalaLedger::init(cala_config, &mut jobs).await?;
+
+    // The source was already compiled by this process, whose program is kept
+    // rather than replaced by the precompiled one.
+    assert_eq!(CelCompileCache::stats().preloaded, preloaded);
+    assert!(CelCompileCache::export()
+        .iter()
+        .any(|compiled| compiled.source() == source));
+
+    Ok(())
+}
@@ -216,6 +216,63 @@ async fn create_control_on_account_set() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn rejects_ill_typed_limit_expressions() -> anyhow::Result<()> {
+    let (cala, _, _) = init_test().await?;
+    let velocity = cala.velocities();
+
+    let limit = |condition: &str, amount: &str| {
+        NewVelocityLimit::builder()
+            .id(VelocityLimitId::new())
+            .name("Withdrawal")
+            .description("test")
+            .window(vec![])
+            .condition(condition)
+            .limit(
+                NewLimit::builder()
+                    .balance(vec![NewBalanceLimit::builder()
+                        .layer("SETTLED")
+                        .amount(amount)
+                        .enforcement_direction("DEBIT")
+                        .always_active()
+                        .build()
+                        .expect("limit")])
+                    .build()
+                    .expect("limit"),
+            )
+            .params(vec![NewParamDefinition::builder()
+                .r#type(ParamDataType::Decimal)
+                .name("withdrawal_limit")
+                .build()
+                .expect("param")])
+            .build()
+            .expect("build limit")
+    };
+
+    velocity
+        .create_limit(limit(
+            "decimal.Cmp(context.vars.entry.units, decimal('10')) > 0",
+            "params.withdrawal_limit",
+        ))
+        .await?;
+
+    let res = velocity
+        .create_limit(limit("context.vars.entry.units", "params.withdrawal_limit"))
+        .await;
+    assert!(
+        matches!(res, Err(VelocityError::ExpressionTypeError(ref expr, _)) if expr == "context.vars.entry.units")
+    );
+
+    let res = velocity
+        .create_limit(limit("true", "params.deposit_limit"))
+        .await;
+    assert!(
+        matches!(res, Err(VelocityError::ExpressionTypeError(ref expr, _)) if expr == "params.deposit_limit")
+    );
+
+    Ok(())
+}
+
 /// Counts `tracing` spans by exact name — used to prove the batched attach
 /// issues one round trip regardless of how many accounts it covers,
 /// without depending on `pg_stat_statements` (not test-isolated: shared
@@ -900,4 +957,74 @@ mod limit_via_account_sets {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn limit_combined_activity_of_members() -> anyhow::Result<()> {
+        let (cala, journal_id, tx_code) = init_test().await?;
+        let velocity = cala.velocities();
+
+        let limit = Decimal::ONE_HUNDRED;
+        let (control_id, control_params) = control_and_limits(velocity, limit).await?;
+
+        let (member_1, recipient) = helpers::test_accounts();
+        let member_1 = cala.accounts().create(member_1).await?;
+        let recipient = cala.accounts().create(recipient).await?;
+        let (member_2, _) = helpers::test_accounts();
+        let member_2 = cala.accounts().create(member_2).await?;
+
+        let (new_parent, new_child) = helpers::test_account_sets(journal_id.into());
+        let parent = cala.account_sets().create(new_parent).await?;
+        let child = cala.account_sets().create(new_child).await?;
+        cala.account_sets()
+            .add_member(parent.id(), child.id())
+            .await?;
+        cala.account_sets()
+            .add_member(parent.id(), member_1.id())
+            .await?;
+        cala.account_sets()
+            .add_member(child.id(), member_2.id())
+            .await?;
+        velocity
+            .attach_control_to_account_set(control_id, parent.id(), control_params)
+            .await?;
+
+        let mut tx_params = Params::new();
+        tx_params.insert("journal_id", journal_id.to_string());
+        tx_params.insert("recipient", recipient.id());
+        tx_params.insert("amount", Decimal::from(60));
+
+        tx_params.insert("sender", member_1.id());
+        cala.post_transaction(TransactionId::new(), &tx_code, tx_params.clone())
+            .await?;
+
+        // Each member is under the limit on its own but not in total
+        tx_params.insert("sender", member_2.id());
+        let res = cala
+            .post_transaction(TransactionId::new(), &tx_code, tx_params.clone())
+            .await;
+        assert!(matches!(
+            res,
+            Err(LedgerError::PostingError(PostingError::VelocityError(
+                VelocityError::Enforcement(_)
+            )))
+        ));
+
+        tx_params.insert("amount", Decimal::from(40));
+        cala.post_transaction(TransactionId::new(), &tx_code, tx_params.clone())
+            .await?;
+
+        tx_params.insert("amount", Decimal::ONE);
+        tx_params.insert("sender", member_1.id());
+        let res = cala
+            .post_transaction(TransactionId::new(), &tx_code, tx_params)
+            .await;
+        assert!(matches!(
+            res,
+            Err(LedgerError::PostingError(PostingError::VelocityError(
+                VelocityError::Enforcement(_)
+            )))
+        ));
+
+        Ok(())
+    }
 }